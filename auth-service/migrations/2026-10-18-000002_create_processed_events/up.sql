@@ -0,0 +1,7 @@
+-- Create processed_events table
+-- Events this service has already acted on, so redelivered ones are skipped
+CREATE TABLE processed_events (
+    event_id VARCHAR(100) PRIMARY KEY,
+    event_type VARCHAR(64) NOT NULL,
+    processed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -1,79 +0,0 @@
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use rocket::http::Status;
-use rocket::request::{self, FromRequest, Request};
-use rocket::outcome::Outcome;
-use serde::{Deserialize, Serialize};
-use std::env;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Claims {
-    pub sub: i32, // user id
-    pub email: String,
-    pub exp: usize, // expiration time
-}
-
-pub struct AuthenticatedUser {
-    pub user_id: i32,
-    pub email: String,
-}
-
-#[rocket::async_trait]
-impl<'r> FromRequest<'r> for AuthenticatedUser {
-    type Error = ();
-
-    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        let token = request.headers().get_one("Authorization");
-        
-        match token {
-            Some(token) => {
-                let token = token.replace("Bearer ", "");
-                let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-                
-                match decode::<Claims>(
-                    &token,
-                    &DecodingKey::from_secret(secret.as_ref()),
-                    &Validation::default(),
-                ) {
-                    Ok(token_data) => Outcome::Success(AuthenticatedUser {
-                        user_id: token_data.claims.sub,
-                        email: token_data.claims.email,
-                    }),
-                    Err(_) => Outcome::Error((Status::Unauthorized, ())),
-                }
-            }
-            None => Outcome::Error((Status::Unauthorized, ())),
-        }
-    }
-}
-
-pub fn create_jwt(user_id: i32, email: String) -> Result<String, jsonwebtoken::errors::Error> {
-    let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-    let expiration = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::days(30))
-        .unwrap()
-        .timestamp() as usize;
-
-    let claims = Claims {
-        sub: user_id,
-        email,
-        exp: expiration,
-    };
-
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_ref()),
-    )
-}
-
-pub fn verify_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-    
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &Validation::default(),
-    )?;
-    
-    Ok(token_data.claims)
-}
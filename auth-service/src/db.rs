@@ -1,4 +1,8 @@
-use rocket_sync_db_pools::{database, diesel};
+use handshake_common::db::{DatabaseConfig, DbPool};
 
-#[database("auth_db")]
-pub struct DbConn(diesel::PgConnection);
+pub use handshake_common::db::DbConn;
+
+/// The service's connection pool, managed as Rocket state for [`DbConn`]
+pub fn pool(config: &DatabaseConfig) -> DbPool {
+    DbPool::new("auth_db", config)
+}
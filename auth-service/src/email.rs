@@ -1,6 +1,7 @@
-use reqwest;
+use handshake_common::metrics;
 use serde::{Deserialize, Serialize};
-use std::env;
+
+use crate::settings::Settings;
 
 #[derive(Debug, Serialize)]
 pub struct VerificationEmailRequest {
@@ -16,12 +17,24 @@ struct EmailServiceResponse {
 }
 
 pub async fn send_verification_email(
+    settings: &Settings,
+    to_email: &str,
+    to_name: &str,
+    verification_code: &str,
+) -> Result<(), String> {
+    metrics::observe_outbound(
+        "email-service",
+        post_verification_email(settings, to_email, to_name, verification_code),
+    )
+    .await
+}
+
+async fn post_verification_email(
+    settings: &Settings,
     to_email: &str,
     to_name: &str,
     verification_code: &str,
 ) -> Result<(), String> {
-    let email_service_url = env::var("EMAIL_SERVICE_URL")
-        .unwrap_or_else(|_| "http://localhost:8004".to_string());
 
     let request = VerificationEmailRequest {
         to_email: to_email.to_string(),
@@ -31,7 +44,7 @@ pub async fn send_verification_email(
 
     let client = reqwest::Client::new();
     let response = client
-        .post(format!("{}/send-verification", email_service_url))
+        .post(format!("{}/send-verification", settings.email_service_url))
         .json(&request)
         .send()
         .await
@@ -0,0 +1,97 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use rocket::fairing::AdHoc;
+
+use handshake_common::events::consumer::Handler;
+use handshake_common::events::{self, EventSettings};
+
+use super::{is_processed, mark_processed, DomainEvent, SOURCE};
+use crate::email::send_verification_email;
+use crate::models::{EmailVerification, User};
+use crate::schema::{email_verifications, users};
+use crate::settings::Settings;
+
+/// Emails verification codes for accounts registered through this service.
+/// The email is sent before the event is recorded in `processed_events`, so
+/// an event whose send failed is retried, and a redelivered one is skipped
+/// once its email went out. A crash between the two can send a code twice,
+/// which is harmless.
+pub fn consumer(settings: EventSettings) -> AdHoc {
+    events::consumer::consumer(SOURCE, settings, |rocket| {
+        rocket
+            .state::<Settings>()
+            .cloned()
+            .map(|settings| VerificationEmails { settings })
+    })
+}
+
+struct VerificationEmails {
+    settings: Settings,
+}
+
+#[rocket::async_trait]
+impl Handler for VerificationEmails {
+    async fn handle(
+        &self,
+        conn: &mut AsyncPgConnection,
+        event_id: &str,
+        payload: &str,
+    ) -> Result<(), String> {
+        let event: DomainEvent = match serde_json::from_str(payload) {
+            Ok(event) => event,
+            // Another service's event; nothing to do here
+            Err(_) => return Ok(()),
+        };
+
+        let user_id = match event {
+            DomainEvent::UserRegistered { user_id, .. }
+            | DomainEvent::VerificationCodeIssued { user_id } => user_id,
+            DomainEvent::UserVerified { .. } => return Ok(()),
+        };
+
+        let failed =
+            |e: diesel::result::Error| format!("Failed to apply event {}: {}", event_id, e);
+        if is_processed(conn, event_id).await.map_err(failed)? {
+            return Ok(());
+        }
+        send_code(conn, &self.settings, user_id).await?;
+        mark_processed(conn, event_id, event.event_type())
+            .await
+            .map_err(failed)
+    }
+}
+
+/// Email the user's newest code. Nothing is sent once the account is verified
+/// or the code has expired; they can ask for another through /resend-otp.
+async fn send_code(
+    conn: &mut AsyncPgConnection,
+    settings: &Settings,
+    user_id: i32,
+) -> Result<(), String> {
+    let user: Option<User> = users::table
+        .find(user_id)
+        .first(conn)
+        .await
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(user) = user.filter(|user| !user.email_verified) else {
+        return Ok(());
+    };
+
+    let verification: Option<EmailVerification> = email_verifications::table
+        .filter(email_verifications::user_id.eq(user_id))
+        .filter(email_verifications::expires_at.gt(Utc::now().naive_utc()))
+        .order(email_verifications::created_at.desc())
+        .first(conn)
+        .await
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(verification) = verification else {
+        return Ok(());
+    };
+
+    send_verification_email(settings, &user.email, &user.name, &verification.code)
+        .await
+        .map_err(|e| format!("Failed to send verification email: {}", e))
+}
@@ -0,0 +1,82 @@
+pub mod consumer;
+
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use handshake_common::events as outbox;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{NewProcessedEvent, User};
+use crate::schema::processed_events;
+
+/// Name this service publishes under, and its consumer group on the bus
+pub const SOURCE: &str = "auth-service";
+
+/// Something that happened to an account that the other services may react
+/// to. Sent over the bus as `{"type": "UserRegistered", "user_id": 1, ...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DomainEvent {
+    UserRegistered {
+        user_id: i32,
+        email: String,
+        name: String,
+    },
+    UserVerified {
+        user_id: i32,
+    },
+    /// A pending account registered again and was given a new code
+    VerificationCodeIssued {
+        user_id: i32,
+    },
+}
+
+impl DomainEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            DomainEvent::UserRegistered { .. } => "UserRegistered",
+            DomainEvent::UserVerified { .. } => "UserVerified",
+            DomainEvent::VerificationCodeIssued { .. } => "VerificationCodeIssued",
+        }
+    }
+
+    pub fn user_registered(user: &User) -> Self {
+        DomainEvent::UserRegistered {
+            user_id: user.id,
+            email: user.email.clone(),
+            name: user.name.clone(),
+        }
+    }
+}
+
+/// Queue `event` for the bus. Call it inside the transaction that made the
+/// change, so the event is published if and only if the change commits.
+pub async fn record(conn: &mut AsyncPgConnection, event: &DomainEvent) -> QueryResult<()> {
+    outbox::record(conn, event.event_type(), event).await
+}
+
+/// Whether an incoming event was already handled and should be skipped
+pub async fn is_processed(conn: &mut AsyncPgConnection, event_id: &str) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(
+        processed_events::table.filter(processed_events::event_id.eq(event_id)),
+    ))
+    .get_result(conn)
+    .await
+}
+
+/// Record an incoming event as handled, once its effect is done. Marking one
+/// twice is harmless.
+pub async fn mark_processed(
+    conn: &mut AsyncPgConnection,
+    event_id: &str,
+    event_type: &str,
+) -> QueryResult<()> {
+    diesel::insert_into(processed_events::table)
+        .values(&NewProcessedEvent {
+            event_id: event_id.to_string(),
+            event_type: event_type.to_string(),
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await
+        .map(|_| ())
+}
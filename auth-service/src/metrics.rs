@@ -0,0 +1,19 @@
+use handshake_common::metrics::{counter, IntCounter};
+use std::sync::LazyLock;
+
+pub static REGISTRATIONS: LazyLock<IntCounter> =
+    LazyLock::new(|| counter("registrations_total", "Accounts created through /register"));
+
+pub static USERS_VERIFIED: LazyLock<IntCounter> = LazyLock::new(|| {
+    counter(
+        "users_verified_total",
+        "Accounts that confirmed their email address",
+    )
+});
+
+/// Register the counters at startup so they read 0 instead of being
+/// missing until the first event
+pub fn register() {
+    LazyLock::force(&REGISTRATIONS);
+    LazyLock::force(&USERS_VERIFIED);
+}
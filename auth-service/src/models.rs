@@ -38,3 +38,10 @@ pub struct NewEmailVerification {
     pub code: String,
     pub expires_at: NaiveDateTime,
 }
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::processed_events)]
+pub struct NewProcessedEvent {
+    pub event_id: String,
+    pub event_type: String,
+}
@@ -1,15 +1,21 @@
 use chrono::{Duration, Utc};
 use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
 use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{get, post};
+use rocket::{get, post, State};
 use serde::{Deserialize, Serialize};
 
-use crate::auth::{create_jwt, AuthenticatedUser};
+use handshake_common::error::ApiError;
+use handshake_common::auth::{create_jwt, AuthenticatedUser, InternalService};
 use crate::db::DbConn;
-use crate::email::{generate_otp, send_verification_email};
+use crate::email::generate_otp;
+use crate::events::{self, DomainEvent};
+use crate::metrics;
 use crate::models::{EmailVerification, NewEmailVerification, NewUser, User};
 use crate::schema::{email_verifications, users};
+use crate::settings::Settings;
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
@@ -46,6 +52,20 @@ pub struct MessageResponse {
     pub message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LookupUsersRequest {
+    pub ids: Vec<i32>,
+}
+
+/// Contact details for other services. Internal only; the email is for
+/// notifications and must not be shown to other users.
+#[derive(Debug, Serialize)]
+pub struct UserContactResponse {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct UserResponse {
     pub id: i32,
@@ -54,132 +74,206 @@ pub struct UserResponse {
     pub email_verified: bool,
 }
 
-#[post("/register", data = "<request>")]
-pub async fn register(
-    db: DbConn,
-    request: Json<RegisterRequest>,
-) -> Result<Json<MessageResponse>, Status> {
-    // Check if user already exists
-    let email = request.email.clone();
-    let existing_user = db
-        .run(move |conn| {
-            users::table
-                .filter(users::email.eq(&email))
-                .first::<User>(conn)
-                .optional()
-        })
-        .await
-        .map_err(|_| Status::InternalServerError)?;
+/// Why a registration couldn't be written
+enum RegisterFailure {
+    /// A verified account already uses this email
+    Taken,
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for RegisterFailure {
+    fn from(err: diesel::result::Error) -> Self {
+        RegisterFailure::Database(err)
+    }
+}
+
+impl RegisterFailure {
+    fn error(self) -> ApiError {
+        match self {
+            RegisterFailure::Taken => {
+                ApiError::conflict("An account with this email already exists")
+            }
+            // Someone registered the same email at the same moment
+            RegisterFailure::Database(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            )) => ApiError::conflict("An account with this email already exists"),
+            RegisterFailure::Database(e) => ApiError::internal(e),
+        }
+    }
+}
+
+/// What registering an email does, given the account already using it
+#[derive(Debug, PartialEq)]
+enum Registration {
+    /// Nobody has the email yet; an account is created
+    New,
+    /// An account is waiting for this email to be verified. It only gets a new
+    /// code: it keeps the name and password it was created with, so nobody can
+    /// take it over by registering the same email before its owner verifies it.
+    Pending { same_password: bool },
+    /// A verified account has the email
+    Taken,
+}
+
+impl Registration {
+    fn for_account(existing: Option<&User>, password: &str) -> Self {
+        match existing {
+            None => Registration::New,
+            Some(user) if user.email_verified => Registration::Taken,
+            Some(user) => Registration::Pending {
+                // An unreadable stored hash can't match anything
+                same_password: bcrypt::verify(password, &user.password_hash).unwrap_or(false),
+            },
+        }
+    }
 
-    if existing_user.is_some() {
-        return Err(Status::Conflict);
+    fn message(&self) -> &'static str {
+        match self {
+            Registration::Pending {
+                same_password: false,
+            } => {
+                "This email is already registered and waiting to be verified. We sent a new \
+                 verification code; sign in with the password you first registered with."
+            }
+            _ => "Registration successful. Please check your email for verification code.",
+        }
     }
+}
 
+/// Create an account, or issue a new code for a pending one whose email was
+/// never verified, and email it a verification code
+#[post("/register", data = "<request>")]
+pub async fn register(
+    mut db: DbConn,
+    request: Json<RegisterRequest>,
+) -> Result<Json<MessageResponse>, ApiError> {
     // Hash password
     let password_hash = bcrypt::hash(&request.password, bcrypt::DEFAULT_COST)
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(ApiError::internal)?;
 
-    // Create user
     let new_user = NewUser {
         email: request.email.clone(),
         password_hash,
         name: request.name.clone(),
     };
-
-    let user: User = db
-        .run(move |conn| {
-            diesel::insert_into(users::table)
-                .values(&new_user)
-                .get_result(conn)
-        })
-        .await
-        .map_err(|_| Status::InternalServerError)?;
+    let password = request.password.clone();
 
     // Generate OTP
-    let otp_code = generate_otp();
-    let user_id = user.id;
-    let otp_code_clone = otp_code.clone();
-
-    let new_verification = NewEmailVerification {
-        user_id,
-        code: otp_code_clone,
-        expires_at: (Utc::now() + Duration::minutes(15)).naive_utc(),
-    };
-
-    db.run(move |conn| {
-        diesel::insert_into(email_verifications::table)
-            .values(&new_verification)
-            .execute(conn)
-    })
-    .await
-    .map_err(|_| Status::InternalServerError)?;
-
-    // Send verification email
-    send_verification_email(&user.email, &user.name, &otp_code)
+    let code = generate_otp();
+
+    // The account, its code and the event that gets the code emailed are
+    // written together so a failure can't leave an account nobody can verify
+    let registration = db
+        .transaction(|conn| {
+            async move {
+                let existing: Option<User> = users::table
+                    .filter(users::email.eq(&new_user.email))
+                    .for_update()
+                    .first(conn)
+                    .await
+                    .optional()?;
+
+                let registration = Registration::for_account(existing.as_ref(), &password);
+                let user = match (&registration, existing) {
+                    (Registration::Taken, _) => return Err(RegisterFailure::Taken),
+                    (_, Some(user)) => {
+                        diesel::delete(
+                            email_verifications::table
+                                .filter(email_verifications::user_id.eq(user.id)),
+                        )
+                        .execute(conn)
+                        .await?;
+                        events::record(
+                            conn,
+                            &DomainEvent::VerificationCodeIssued { user_id: user.id },
+                        )
+                        .await?;
+                        user
+                    }
+                    (_, None) => {
+                        let user: User = diesel::insert_into(users::table)
+                            .values(&new_user)
+                            .get_result(conn)
+                            .await?;
+                        events::record(conn, &DomainEvent::user_registered(&user)).await?;
+                        user
+                    }
+                };
+
+                diesel::insert_into(email_verifications::table)
+                    .values(&NewEmailVerification {
+                        user_id: user.id,
+                        code,
+                        expires_at: (Utc::now() + Duration::minutes(15)).naive_utc(),
+                    })
+                    .execute(conn)
+                    .await?;
+                Ok(registration)
+            }
+            .scope_boxed()
+        })
         .await
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(RegisterFailure::error)?;
+    if registration == Registration::New {
+        metrics::REGISTRATIONS.inc();
+    }
 
     Ok(Json(MessageResponse {
-        message: "Registration successful. Please check your email for verification code."
-            .to_string(),
+        message: registration.message().to_string(),
     }))
 }
 
 #[post("/verify-email", data = "<request>")]
 pub async fn verify_email(
-    db: DbConn,
+    mut db: DbConn,
     request: Json<VerifyEmailRequest>,
-) -> Result<Json<MessageResponse>, Status> {
-    let email = request.email.clone();
-    let code = request.code.clone();
-
+) -> Result<Json<MessageResponse>, ApiError> {
     // Get user
-    let user: User = db
-        .run(move |conn| users::table.filter(users::email.eq(&email)).first(conn))
+    let user: User = users::table
+        .filter(users::email.eq(&request.email))
+        .first(&mut db)
         .await
-        .map_err(|_| Status::NotFound)?;
+        .map_err(ApiError::when_missing(Status::NotFound, "No account with this email"))?;
 
     if user.email_verified {
-        return Err(Status::BadRequest);
+        return Err(ApiError::bad_request("Email is already verified"));
     }
 
-    let user_id = user.id;
-
     // Check verification code
-    let verification: EmailVerification = db
-        .run(move |conn| {
-            email_verifications::table
-                .filter(email_verifications::user_id.eq(user_id))
-                .filter(email_verifications::code.eq(&code))
-                .order(email_verifications::created_at.desc())
-                .first(conn)
-        })
+    let verification: EmailVerification = email_verifications::table
+        .filter(email_verifications::user_id.eq(user.id))
+        .filter(email_verifications::code.eq(&request.code))
+        .order(email_verifications::created_at.desc())
+        .first(&mut db)
         .await
-        .map_err(|_| Status::Unauthorized)?;
+        .map_err(ApiError::when_missing(Status::Unauthorized, "Invalid verification code"))?;
 
     // Check if code is expired
     if verification.expires_at < Utc::now().naive_utc() {
-        return Err(Status::Gone);
+        return Err(ApiError::new(Status::Gone, "Verification code has expired"));
     }
 
-    // Update user as verified
-    let user_id = user.id;
-    db.run(move |conn| {
-        diesel::update(users::table.find(user_id))
-            .set(users::email_verified.eq(true))
-            .execute(conn)
-    })
-    .await
-    .map_err(|_| Status::InternalServerError)?;
-
-    // Delete used verification codes
+    // Mark the user verified and drop their used codes
     let user_id = user.id;
-    db.run(move |conn| {
-        diesel::delete(email_verifications::table.filter(email_verifications::user_id.eq(user_id)))
+    db.transaction(|conn| {
+        async move {
+            diesel::update(users::table.find(user_id))
+                .set(users::email_verified.eq(true))
+                .execute(conn)
+                .await?;
+            diesel::delete(
+                email_verifications::table.filter(email_verifications::user_id.eq(user_id)),
+            )
             .execute(conn)
+            .await?;
+            events::record(conn, &DomainEvent::UserVerified { user_id }).await
+        }
+        .scope_boxed()
     })
     .await
-    .map_err(|_| Status::InternalServerError)?;
+    .map_err(ApiError::internal)?;
+    metrics::USERS_VERIFIED.inc();
 
     Ok(Json(MessageResponse {
         message: "Email verified successfully. You can now login.".to_string(),
@@ -187,30 +281,32 @@ pub async fn verify_email(
 }
 
 #[post("/login", data = "<request>")]
-pub async fn login(db: DbConn, request: Json<LoginRequest>) -> Result<Json<AuthResponse>, Status> {
-    let email = request.email.clone();
-    let password = request.password.clone();
-
-    let user: User = db
-        .run(move |conn| users::table.filter(users::email.eq(&email)).first(conn))
+pub async fn login(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    request: Json<LoginRequest>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    let user: User = users::table
+        .filter(users::email.eq(&request.email))
+        .first(&mut db)
         .await
-        .map_err(|_| Status::Unauthorized)?;
+        .map_err(ApiError::when_missing(Status::Unauthorized, "Invalid email or password"))?;
 
     // Check if email is verified
     if !user.email_verified {
-        return Err(Status::Forbidden);
+        return Err(ApiError::forbidden("Email is not verified").with_code("email_not_verified"));
     }
 
     // Verify password
     let valid =
-        bcrypt::verify(&password, &user.password_hash).map_err(|_| Status::InternalServerError)?;
+        bcrypt::verify(&request.password, &user.password_hash).map_err(ApiError::internal)?;
 
     if !valid {
-        return Err(Status::Unauthorized);
+        return Err(ApiError::unauthorized("Invalid email or password"));
     }
 
     // Create JWT
-    let token = create_jwt(user.id, user.email.clone()).map_err(|_| Status::InternalServerError)?;
+    let token = create_jwt(&settings.auth, user.id, user.email.clone()).map_err(ApiError::internal)?;
 
     Ok(Json(AuthResponse {
         token,
@@ -223,54 +319,48 @@ pub async fn login(db: DbConn, request: Json<LoginRequest>) -> Result<Json<AuthR
     }))
 }
 
+/// Replace the account's code with a new one, emailed by the event consumer
 #[post("/resend-otp", data = "<request>")]
 pub async fn resend_otp(
-    db: DbConn,
+    mut db: DbConn,
     request: Json<ResendOtpRequest>,
-) -> Result<Json<MessageResponse>, Status> {
-    let email = request.email.clone();
-
-    let user: User = db
-        .run(move |conn| users::table.filter(users::email.eq(&email)).first(conn))
+) -> Result<Json<MessageResponse>, ApiError> {
+    let user: User = users::table
+        .filter(users::email.eq(&request.email))
+        .first(&mut db)
         .await
-        .map_err(|_| Status::NotFound)?;
+        .map_err(ApiError::when_missing(Status::NotFound, "No account with this email"))?;
 
     if user.email_verified {
-        return Err(Status::BadRequest);
+        return Err(ApiError::bad_request("Email is already verified"));
     }
 
-    // Delete old verification codes
-    let user_id = user.id;
-    db.run(move |conn| {
-        diesel::delete(email_verifications::table.filter(email_verifications::user_id.eq(user_id)))
-            .execute(conn)
-    })
-    .await
-    .map_err(|_| Status::InternalServerError)?;
-
-    // Generate new OTP
-    let otp_code = generate_otp();
-    let user_id = user.id;
-    let otp_code_clone = otp_code.clone();
-
+    // The new code and the event that gets it emailed are written together,
+    // so the old code is only replaced once the new one is sure to be sent
     let new_verification = NewEmailVerification {
-        user_id,
-        code: otp_code_clone,
+        user_id: user.id,
+        code: generate_otp(),
         expires_at: (Utc::now() + Duration::minutes(15)).naive_utc(),
     };
 
-    db.run(move |conn| {
-        diesel::insert_into(email_verifications::table)
-            .values(&new_verification)
+    db.transaction(|conn| {
+        async move {
+            let user_id = new_verification.user_id;
+            diesel::delete(
+                email_verifications::table.filter(email_verifications::user_id.eq(user_id)),
+            )
             .execute(conn)
+            .await?;
+            diesel::insert_into(email_verifications::table)
+                .values(&new_verification)
+                .execute(conn)
+                .await?;
+            events::record(conn, &DomainEvent::VerificationCodeIssued { user_id }).await
+        }
+        .scope_boxed()
     })
     .await
-    .map_err(|_| Status::InternalServerError)?;
-
-    // Send verification email
-    send_verification_email(&user.email, &user.name, &otp_code)
-        .await
-        .map_err(|_| Status::InternalServerError)?;
+    .map_err(ApiError::internal)?;
 
     Ok(Json(MessageResponse {
         message: "Verification code resent. Please check your email.".to_string(),
@@ -278,13 +368,12 @@ pub async fn resend_otp(
 }
 
 #[get("/me")]
-pub async fn me(db: DbConn, auth: AuthenticatedUser) -> Result<Json<UserResponse>, Status> {
-    let user_id = auth.user_id;
-
-    let user: User = db
-        .run(move |conn| users::table.find(user_id).first(conn))
+pub async fn me(mut db: DbConn, auth: AuthenticatedUser) -> Result<Json<UserResponse>, ApiError> {
+    let user: User = users::table
+        .find(auth.user_id)
+        .first(&mut db)
         .await
-        .map_err(|_| Status::NotFound)?;
+        .map_err(ApiError::when_missing(Status::NotFound, "User not found"))?;
 
     Ok(Json(UserResponse {
         id: user.id,
@@ -293,3 +382,72 @@ pub async fn me(db: DbConn, auth: AuthenticatedUser) -> Result<Json<UserResponse
         email_verified: user.email_verified,
     }))
 }
+
+/// Names and emails for a batch of users, for services that show who is on
+/// the other side of an order or email them. Unknown ids are left out.
+#[post("/users/lookup", data = "<request>")]
+pub async fn lookup_users(
+    mut db: DbConn,
+    _service: InternalService,
+    request: Json<LookupUsersRequest>,
+) -> Result<Json<Vec<UserContactResponse>>, ApiError> {
+    const MAX_LOOKUP: usize = 200;
+
+    let ids = request.into_inner().ids;
+    if ids.len() > MAX_LOOKUP {
+        return Err(ApiError::new(
+            Status::PayloadTooLarge,
+            format!("At most {} users can be looked up at once", MAX_LOOKUP),
+        ));
+    }
+
+    let found: Vec<(i32, String, String)> = users::table
+        .filter(users::id.eq_any(ids))
+        .select((users::id, users::name, users::email))
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(
+        found
+            .into_iter()
+            .map(|(id, name, email)| UserContactResponse { id, name, email })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(password: &str, email_verified: bool) -> User {
+        User {
+            id: 1,
+            email: "buyer@example.com".to_string(),
+            password_hash: bcrypt::hash(password, 4).unwrap(),
+            name: "Buyer".to_string(),
+            email_verified,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn registering_again_before_verifying_keeps_the_first_password() {
+        assert_eq!(Registration::for_account(None, "first-password"), Registration::New);
+
+        let pending = account("first-password", false);
+        let same = Registration::for_account(Some(&pending), "first-password");
+        assert_eq!(same, Registration::Pending { same_password: true });
+        assert_eq!(same.message(), Registration::New.message());
+
+        let changed = Registration::for_account(Some(&pending), "second-password");
+        assert_eq!(changed, Registration::Pending { same_password: false });
+        assert!(changed.message().contains("password you first registered with"));
+
+        let verified = account("first-password", true);
+        assert_eq!(
+            Registration::for_account(Some(&verified), "second-password"),
+            Registration::Taken
+        );
+    }
+}
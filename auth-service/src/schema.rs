@@ -11,6 +11,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    processed_events (event_id) {
+        #[max_length = 100]
+        event_id -> Varchar,
+        #[max_length = 64]
+        event_type -> Varchar,
+        processed_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Int4,
@@ -27,4 +37,4 @@ diesel::table! {
 
 diesel::joinable!(email_verifications -> users (user_id));
 
-diesel::allow_tables_to_appear_in_same_query!(email_verifications, users,);
+diesel::allow_tables_to_appear_in_same_query!(email_verifications, processed_events, users,);
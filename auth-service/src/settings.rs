@@ -0,0 +1,49 @@
+use handshake_common::auth::AuthConfig;
+use handshake_common::config::{self, check_url, Validate};
+use handshake_common::db::DatabaseConfig;
+use handshake_common::error::FieldError;
+use handshake_common::events::EventSettings;
+use serde::Deserialize;
+
+/// Everything auth-service reads from `Rocket.toml` and the environment,
+/// loaded once in `main` and managed as Rocket state
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub database: DatabaseConfig,
+    #[serde(default = "default_email_service_url")]
+    pub email_service_url: String,
+    /// Public URL of the frontend, the only origin besides the local dev
+    /// server allowed to call in
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub app_url: Option<String>,
+    #[serde(flatten)]
+    pub events: EventSettings,
+    #[serde(flatten)]
+    pub auth: AuthConfig,
+}
+
+fn default_email_service_url() -> String {
+    "http://localhost:8004".to_string()
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = self.auth.validate();
+        problems.extend(self.database.validate());
+        problems.extend(check_url("email_service_url", &self.email_service_url));
+        if let Some(app_url) = &self.app_url {
+            problems.extend(check_url("app_url", app_url));
+        }
+        // Verification codes are only ever emailed by the event consumer, so
+        // registering without a bus would never send one
+        if self.events.event_bus_url.as_deref().unwrap_or_default().is_empty() {
+            problems.push(FieldError::new(
+                "event_bus_url",
+                "is required; verification codes are sent through the event bus",
+            ));
+        }
+        problems.extend(self.events.validate());
+        problems
+    }
+}
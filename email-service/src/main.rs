@@ -1,54 +1,41 @@
 pub mod health;
+pub mod metrics;
 pub mod routes;
+pub mod settings;
 pub mod smtp;
 
-use rocket::fairing::{Fairing, Info, Kind};
-use rocket::http::Header;
+use handshake_common::config;
+use handshake_common::metrics::RequestMetrics;
+use handshake_common::telemetry::{self, RequestTracing};
 use rocket::routes;
-use rocket::{Request, Response};
 
-use rocket_cors::CorsOptions;
-
-pub struct CORS;
-
-#[rocket::async_trait]
-impl Fairing for CORS {
-    fn info(&self) -> Info {
-        Info {
-            name: "Add CORS headers to responses",
-            kind: Kind::Response,
-        }
-    }
-
-    async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
-        response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
-        response.set_header(Header::new(
-            "Access-Control-Allow-Methods",
-            "POST, GET, OPTIONS",
-        ));
-        response.set_header(Header::new(
-            "Access-Control-Allow-Headers",
-            "*, Authorization",
-        ));
-        response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
-    }
-}
+use crate::settings::Settings;
 
+#[allow(clippy::result_large_err)]
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
-    dotenv::dotenv().ok();
-
-    let cors = CorsOptions::default().to_cors().unwrap();
+    config::load_env();
+    let _telemetry = telemetry::init("email-service");
+    let settings: Settings = config::load();
+    metrics::register();
 
     let _rocket = rocket::build()
-        // .attach(CORS)
-        .attach(cors)
+        .attach(config::cors(settings.app_url.as_deref()))
+        .attach(RequestTracing)
+        .attach(RequestMetrics)
+        .manage(settings)
+        .register("/", handshake_common::error::catchers())
         .mount("/", routes![health::live, health::ready])
+        .mount("/", handshake_common::metrics::routes())
         .mount(
             "/",
             routes![
                 routes::send_verification,
                 routes::send_order_notification,
+                routes::send_favorite_alert,
+                routes::send_listing_expiry_reminder,
+                routes::send_meetup_email,
+                routes::send_saved_search_digest,
                 routes::send_custom_email,
             ],
         )
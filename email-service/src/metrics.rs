@@ -0,0 +1,11 @@
+use handshake_common::metrics::{counter, IntCounter};
+use std::sync::LazyLock;
+
+pub static EMAILS_SENT: LazyLock<IntCounter> =
+    LazyLock::new(|| counter("emails_sent_total", "Emails Mailjet accepted for delivery"));
+
+/// Register the counters at startup so they read 0 instead of being
+/// missing until the first event
+pub fn register() {
+    LazyLock::force(&EMAILS_SENT);
+}
@@ -1,9 +1,15 @@
-use rocket::http::Status;
-use rocket::post;
+use rocket::{post, State};
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
 
-use crate::smtp::{render_order_notification, render_verification_email, send_email};
+use handshake_common::error::ApiError;
+
+use crate::settings::Settings;
+use crate::smtp::{
+    render_favorite_alert, render_listing_expiry_reminder, render_meetup_email,
+    render_order_notification, render_saved_search_digest, render_verification_email, send_email,
+    send_email_with_attachments, Attachment,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct VerificationEmailRequest {
@@ -21,6 +27,68 @@ pub struct OrderNotificationRequest {
     pub midpoint_address: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FavoriteAlertType {
+    PriceDrop,
+    Reserved,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FavoriteAlertRequest {
+    pub to_email: String,
+    pub alert_type: FavoriteAlertType,
+    pub product_id: i32,
+    pub product_title: String,
+    pub product_url: String,
+    pub old_price: f64,
+    pub new_price: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListingExpiryReminderRequest {
+    pub to_email: String,
+    pub product_title: String,
+    pub product_url: String,
+    pub renew_url: String,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeetupEmailKind {
+    Confirmed,
+    Reminder,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MeetupEmailRequest {
+    pub to_email: String,
+    pub kind: MeetupEmailKind,
+    pub product_title: String,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub location: String,
+    pub maps_url: Option<String>,
+    /// iCalendar file for the meetup, attached as `meetup.ics`
+    pub ics: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigestItem {
+    pub title: String,
+    pub price: f64,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SavedSearchDigestRequest {
+    pub to_email: String,
+    pub search_name: String,
+    pub unsubscribe_url: String,
+    pub products: Vec<DigestItem>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CustomEmailRequest {
     pub to_email: String,
@@ -36,14 +104,15 @@ pub struct EmailResponse {
 
 #[post("/send-verification", data = "<request>")]
 pub async fn send_verification(
+    settings: &State<Settings>,
     request: Json<VerificationEmailRequest>,
-) -> Result<Json<EmailResponse>, Status> {
+) -> Result<Json<EmailResponse>, ApiError> {
     let body = render_verification_email(&request.to_name, &request.verification_code)
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(ApiError::internal)?;
 
-    send_email(&request.to_email, "Verify your Handshake account", body)
+    send_email(settings, &request.to_email, "Verify your Handshake account", body)
         .await
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(|e| ApiError::upstream("Mailjet", e))?;
 
     Ok(Json(EmailResponse {
         success: true,
@@ -53,23 +122,25 @@ pub async fn send_verification(
 
 #[post("/send-order-notification", data = "<request>")]
 pub async fn send_order_notification(
+    settings: &State<Settings>,
     request: Json<OrderNotificationRequest>,
-) -> Result<Json<EmailResponse>, Status> {
+) -> Result<Json<EmailResponse>, ApiError> {
     let body = render_order_notification(
         &request.to_name,
         &request.product_title,
         request.order_id,
         &request.midpoint_address,
     )
-    .map_err(|_| Status::InternalServerError)?;
+    .map_err(ApiError::internal)?;
 
     send_email(
+        settings,
         &request.to_email,
         &format!("Order Confirmation - {}", request.product_title),
         body,
     )
     .await
-    .map_err(|_| Status::InternalServerError)?;
+    .map_err(|e| ApiError::upstream("Mailjet", e))?;
 
     Ok(Json(EmailResponse {
         success: true,
@@ -77,11 +148,157 @@ pub async fn send_order_notification(
     }))
 }
 
+#[post("/send-favorite-alert", data = "<request>")]
+pub async fn send_favorite_alert(
+    settings: &State<Settings>,
+    request: Json<FavoriteAlertRequest>,
+) -> Result<Json<EmailResponse>, ApiError> {
+    let (alert_type, subject) = match request.alert_type {
+        FavoriteAlertType::PriceDrop => (
+            "price_drop",
+            format!("Price drop - {}", request.product_title),
+        ),
+        FavoriteAlertType::Reserved => (
+            "reserved",
+            format!("Almost gone - {}", request.product_title),
+        ),
+    };
+
+    let body = render_favorite_alert(
+        alert_type,
+        &request.product_title,
+        &request.product_url,
+        request.old_price,
+        request.new_price,
+    )
+    .map_err(ApiError::internal)?;
+
+    send_email(settings, &request.to_email, &subject, body)
+        .await
+        .map_err(|e| ApiError::upstream("Mailjet", e))?;
+
+    Ok(Json(EmailResponse {
+        success: true,
+        message: "Favorite alert sent successfully".to_string(),
+    }))
+}
+
+#[post("/send-saved-search-digest", data = "<request>")]
+pub async fn send_saved_search_digest(
+    settings: &State<Settings>,
+    request: Json<SavedSearchDigestRequest>,
+) -> Result<Json<EmailResponse>, ApiError> {
+    if request.products.is_empty() {
+        return Err(ApiError::bad_request("A digest needs at least one product"));
+    }
+
+    let products: Vec<(String, f64, String)> = request
+        .products
+        .iter()
+        .map(|p| (p.title.clone(), p.price, p.url.clone()))
+        .collect();
+
+    let body = render_saved_search_digest(&request.search_name, &request.unsubscribe_url, &products)
+        .map_err(ApiError::internal)?;
+
+    send_email(
+        settings,
+        &request.to_email,
+        &format!("New listings for \"{}\"", request.search_name),
+        body,
+    )
+    .await
+    .map_err(|e| ApiError::upstream("Mailjet", e))?;
+
+    Ok(Json(EmailResponse {
+        success: true,
+        message: "Saved search digest sent successfully".to_string(),
+    }))
+}
+
+#[post("/send-listing-expiry-reminder", data = "<request>")]
+pub async fn send_listing_expiry_reminder(
+    settings: &State<Settings>,
+    request: Json<ListingExpiryReminderRequest>,
+) -> Result<Json<EmailResponse>, ApiError> {
+    let body = render_listing_expiry_reminder(
+        &request.product_title,
+        &request.product_url,
+        &request.renew_url,
+        &request.expires_at,
+    )
+    .map_err(ApiError::internal)?;
+
+    send_email(
+        settings,
+        &request.to_email,
+        &format!("Your listing is expiring soon - {}", request.product_title),
+        body,
+    )
+    .await
+    .map_err(|e| ApiError::upstream("Mailjet", e))?;
+
+    Ok(Json(EmailResponse {
+        success: true,
+        message: "Listing expiry reminder sent successfully".to_string(),
+    }))
+}
+
+#[post("/send-meetup-email", data = "<request>")]
+pub async fn send_meetup_email(
+    settings: &State<Settings>,
+    request: Json<MeetupEmailRequest>,
+) -> Result<Json<EmailResponse>, ApiError> {
+    let request = request.into_inner();
+    let (kind, subject) = match request.kind {
+        MeetupEmailKind::Confirmed => (
+            "confirmed",
+            format!("Meetup confirmed - {}", request.product_title),
+        ),
+        MeetupEmailKind::Reminder => (
+            "reminder",
+            format!("Upcoming meetup - {}", request.product_title),
+        ),
+    };
+
+    let body = render_meetup_email(
+        kind,
+        &request.product_title,
+        &request.starts_at,
+        &request.ends_at,
+        &request.location,
+        request.maps_url.as_deref(),
+    )
+    .map_err(ApiError::internal)?;
+
+    let attachments = request
+        .ics
+        .map(|ics| Attachment {
+            filename: "meetup.ics".to_string(),
+            content_type: "text/calendar".to_string(),
+            content: ics.into_bytes(),
+        })
+        .into_iter()
+        .collect();
+
+    send_email_with_attachments(settings, &request.to_email, &subject, body, attachments)
+        .await
+        .map_err(|e| ApiError::upstream("Mailjet", e))?;
+
+    Ok(Json(EmailResponse {
+        success: true,
+        message: "Meetup email sent successfully".to_string(),
+    }))
+}
+
 #[post("/send-custom", data = "<request>")]
-pub async fn send_custom_email(request: Json<CustomEmailRequest>) -> Result<Json<EmailResponse>, Status> {
-    send_email(&request.to_email, &request.subject, request.body.clone())
+pub async fn send_custom_email(
+    settings: &State<Settings>,
+    request: Json<CustomEmailRequest>,
+) -> Result<Json<EmailResponse>, ApiError> {
+    send_email(settings, &request.to_email, &request.subject, request.body.clone())
         .await
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(|e| ApiError::upstream("Mailjet", e))?;
 
     Ok(Json(EmailResponse {
         success: true,
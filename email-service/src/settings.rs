@@ -0,0 +1,71 @@
+use handshake_common::config::{self, check_url, Validate};
+use handshake_common::error::FieldError;
+use serde::Deserialize;
+
+/// Everything email-service reads from `Rocket.toml` and the environment,
+/// loaded once in `main` and managed as Rocket state
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    /// Optional in debug builds so the service starts without an account;
+    /// sends then fail and `/ready` reports Mailjet as not configured
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub mailjet_api_key: Option<String>,
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub mailjet_secret_key: Option<String>,
+    #[serde(default = "default_from_email")]
+    pub from_email: String,
+    #[serde(default = "default_from_name")]
+    pub from_name: String,
+    /// Public URL of the frontend, the only origin besides the local dev
+    /// server allowed to call in
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub app_url: Option<String>,
+}
+
+fn default_from_email() -> String {
+    "noreply@handshake.local".to_string()
+}
+
+fn default_from_name() -> String {
+    "Handshake Marketplace".to_string()
+}
+
+impl Settings {
+    /// API key and secret, when both are set
+    pub fn mailjet_credentials(&self) -> Option<(&str, &str)> {
+        match (&self.mailjet_api_key, &self.mailjet_secret_key) {
+            (Some(key), Some(secret)) if !key.is_empty() && !secret.is_empty() => {
+                Some((key, secret))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        match (&self.mailjet_api_key, &self.mailjet_secret_key) {
+            (Some(_), None) => problems.push(FieldError::new(
+                "mailjet_secret_key",
+                "is required when mailjet_api_key is set",
+            )),
+            (None, Some(_)) => problems.push(FieldError::new(
+                "mailjet_api_key",
+                "is required when mailjet_secret_key is set",
+            )),
+            (None, None) if !cfg!(debug_assertions) => problems.push(FieldError::new(
+                "mailjet_api_key",
+                "is required; set MAILJET_API_KEY and MAILJET_SECRET_KEY",
+            )),
+            _ => {}
+        }
+        if !self.from_email.contains('@') {
+            problems.push(FieldError::new("from_email", "must be an email address"));
+        }
+        if let Some(app_url) = &self.app_url {
+            problems.extend(check_url("app_url", app_url));
+        }
+        problems
+    }
+}
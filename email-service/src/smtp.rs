@@ -1,31 +1,12 @@
 use base64::engine::general_purpose;
 use base64::Engine;
+use handshake_common::metrics::observe_outbound;
 use reqwest;
 use serde::{Deserialize, Serialize};
-use std::env;
 use tera::{Context, Tera};
 
-pub struct EmailConfig {
-    pub mailjet_api_key: String,
-    pub mailjet_secret_key: String,
-    pub from_email: String,
-    pub from_name: String,
-}
-
-impl EmailConfig {
-    pub fn from_env() -> Result<Self, String> {
-        Ok(EmailConfig {
-            mailjet_api_key: env::var("MAILJET_API_KEY")
-                .map_err(|_| "MAILJET_API_KEY not set")?,
-            mailjet_secret_key: env::var("MAILJET_SECRET_KEY")
-                .map_err(|_| "MAILJET_SECRET_KEY not set")?,
-            from_email: env::var("FROM_EMAIL")
-                .unwrap_or_else(|_| "noreply@handshake.local".to_string()),
-            from_name: env::var("FROM_NAME")
-                .unwrap_or_else(|_| "Handshake Marketplace".to_string()),
-        })
-    }
-}
+use crate::metrics::EMAILS_SENT;
+use crate::settings::Settings;
 
 #[derive(Debug, Serialize)]
 struct MailjetRecipient {
@@ -45,6 +26,25 @@ struct MailjetMessage {
     subject: String,
     #[serde(rename = "HTMLPart")]
     html_part: String,
+    #[serde(rename = "Attachments", skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<MailjetAttachment>,
+}
+
+#[derive(Debug, Serialize)]
+struct MailjetAttachment {
+    #[serde(rename = "ContentType")]
+    content_type: String,
+    #[serde(rename = "Filename")]
+    filename: String,
+    #[serde(rename = "Base64Content")]
+    base64_content: String,
+}
+
+/// A file sent along with an email
+pub struct Attachment {
+    pub filename: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
 }
 
 #[derive(Debug, Serialize)]
@@ -63,26 +63,51 @@ struct MailjetResponse {
 struct MailjetMessageResponse {
     #[serde(rename = "Status")]
     status: String,
-    #[serde(rename = "To", default)]
-    to: Vec<MailjetRecipientResponse>,
 }
 
-#[derive(Debug, Deserialize)]
-struct MailjetRecipientResponse {
-    #[serde(rename = "Email")]
-    email: String,
-    #[serde(rename = "MessageID")]
-    message_id: i64,
+pub async fn send_email(
+    settings: &Settings,
+    to_email: &str,
+    subject: &str,
+    body: String,
+) -> Result<(), String> {
+    send_email_with_attachments(settings, to_email, subject, body, Vec::new()).await
+}
+
+pub async fn send_email_with_attachments(
+    settings: &Settings,
+    to_email: &str,
+    subject: &str,
+    body: String,
+    attachments: Vec<Attachment>,
+) -> Result<(), String> {
+    let sent = observe_outbound(
+        "mailjet",
+        post_to_mailjet(settings, to_email, subject, body, attachments),
+    )
+    .await;
+    if sent.is_ok() {
+        EMAILS_SENT.inc();
+    }
+    sent
 }
 
-pub async fn send_email(to_email: &str, subject: &str, body: String) -> Result<(), String> {
-    let config = EmailConfig::from_env()?;
+async fn post_to_mailjet(
+    settings: &Settings,
+    to_email: &str,
+    subject: &str,
+    body: String,
+    attachments: Vec<Attachment>,
+) -> Result<(), String> {
+    let (api_key, secret_key) = settings
+        .mailjet_credentials()
+        .ok_or("Mailjet credentials are not configured")?;
 
     let mailjet_request = MailjetRequest {
         messages: vec![MailjetMessage {
             from: MailjetRecipient {
-                email: config.from_email,
-                name: Some(config.from_name),
+                email: settings.from_email.clone(),
+                name: Some(settings.from_name.clone()),
             },
             to: vec![MailjetRecipient {
                 email: to_email.to_string(),
@@ -90,13 +115,21 @@ pub async fn send_email(to_email: &str, subject: &str, body: String) -> Result<(
             }],
             subject: subject.to_string(),
             html_part: body,
+            attachments: attachments
+                .into_iter()
+                .map(|a| MailjetAttachment {
+                    content_type: a.content_type,
+                    filename: a.filename,
+                    base64_content: general_purpose::STANDARD.encode(a.content),
+                })
+                .collect(),
         }],
     };
 
     let client = reqwest::Client::new();
     let auth = general_purpose::STANDARD.encode(format!(
         "{}:{}",
-        config.mailjet_api_key, config.mailjet_secret_key
+        api_key, secret_key
     ));
 
     let response = client
@@ -174,4 +207,113 @@ pub fn render_order_notification(
 
     tera.render("order", &context)
         .map_err(|e| format!("Failed to render template: {}", e))
-}
\ No newline at end of file
+}
+
+pub fn render_favorite_alert(
+    alert_type: &str,
+    product_title: &str,
+    product_url: &str,
+    old_price: f64,
+    new_price: f64,
+) -> Result<String, String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template(
+        "favorite_alert",
+        include_str!("../templates/favorite_alert.html"),
+    )
+    .map_err(|e| format!("Failed to load template: {}", e))?;
+
+    let mut context = Context::new();
+    context.insert("alert_type", alert_type);
+    context.insert("product_title", product_title);
+    context.insert("product_url", product_url);
+    context.insert("old_price", &format!("{:.2}", old_price));
+    context.insert("new_price", &format!("{:.2}", new_price));
+
+    tera.render("favorite_alert", &context)
+        .map_err(|e| format!("Failed to render template: {}", e))
+}
+
+pub fn render_listing_expiry_reminder(
+    product_title: &str,
+    product_url: &str,
+    renew_url: &str,
+    expires_at: &str,
+) -> Result<String, String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template(
+        "listing_expiry_reminder",
+        include_str!("../templates/listing_expiry_reminder.html"),
+    )
+    .map_err(|e| format!("Failed to load template: {}", e))?;
+
+    let mut context = Context::new();
+    context.insert("product_title", product_title);
+    context.insert("product_url", product_url);
+    context.insert("renew_url", renew_url);
+    context.insert("expires_at", expires_at);
+
+    tera.render("listing_expiry_reminder", &context)
+        .map_err(|e| format!("Failed to render template: {}", e))
+}
+
+pub fn render_meetup_email(
+    kind: &str,
+    product_title: &str,
+    starts_at: &str,
+    ends_at: &str,
+    location: &str,
+    maps_url: Option<&str>,
+) -> Result<String, String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template("meetup", include_str!("../templates/meetup.html"))
+        .map_err(|e| format!("Failed to load template: {}", e))?;
+
+    let mut context = Context::new();
+    context.insert("kind", kind);
+    context.insert("product_title", product_title);
+    context.insert("starts_at", starts_at);
+    context.insert("ends_at", ends_at);
+    context.insert("location", location);
+    context.insert("maps_url", &maps_url);
+
+    tera.render("meetup", &context)
+        .map_err(|e| format!("Failed to render template: {}", e))
+}
+
+#[derive(Debug, Serialize)]
+struct DigestListing<'a> {
+    title: &'a str,
+    price: String,
+    url: &'a str,
+}
+
+pub fn render_saved_search_digest(
+    search_name: &str,
+    unsubscribe_url: &str,
+    products: &[(String, f64, String)],
+) -> Result<String, String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template(
+        "saved_search_digest",
+        include_str!("../templates/saved_search_digest.html"),
+    )
+    .map_err(|e| format!("Failed to load template: {}", e))?;
+
+    let listings: Vec<DigestListing> = products
+        .iter()
+        .map(|(title, price, url)| DigestListing {
+            title,
+            price: format!("{:.2}", price),
+            url,
+        })
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("search_name", search_name);
+    context.insert("unsubscribe_url", unsubscribe_url);
+    context.insert("products", &listings);
+
+    tera.render("saved_search_digest", &context)
+        .map_err(|e| format!("Failed to render template: {}", e))
+}
@@ -63,21 +63,162 @@ async function getConfig(): Promise<RuntimeConfig> {
   return configPromise;
 }
 
+export interface Page<T> {
+  items: T[];
+  total: number;
+  has_more: boolean;
+  next_cursor?: string;
+}
+
 export interface Order {
   id: number;
   product_id: number;
   buyer_id: number;
   seller_id: number;
   status: string;
-  buyer_location: LocationInfo;
-  seller_location: LocationInfo;
-  midpoint_info: MidpointInfo;
+  quantity: number;
+  buyer_location?: LocationInfo;
+  seller_location?: LocationInfo;
+  midpoint_info?: MidpointInfo;
+  meetup_starts_at?: string;
+  meetup_ends_at?: string;
+  completed_at?: string;
+  cancelled_by?: number;
+  cancellation_reason?: CancellationReason;
+  cancellation_note?: string;
+}
+
+export type CancellationReason =
+  | "changed_mind"
+  | "found_elsewhere"
+  | "item_unavailable"
+  | "price_disagreement"
+  | "schedule_conflict"
+  | "counterparty_unresponsive"
+  | "safety_concern"
+  | "other";
+
+export type DisputeReason =
+  | "item_not_as_described"
+  | "item_damaged"
+  | "no_show"
+  | "payment_issue"
+  | "safety_concern"
+  | "other";
+
+export interface DisputeMessage {
+  id: number;
+  dispute_id: number;
+  author_id: number;
+  body: string;
+  created_at: string;
+}
+
+export interface DisputeEvidence {
+  id: number;
+  dispute_id: number;
+  uploaded_by: number;
+  url: string;
+  description?: string;
+  created_at: string;
+}
+
+export interface Dispute {
+  id: number;
+  order_id: number;
+  raised_by: number;
+  reason: DisputeReason;
+  description: string;
+  order_status: string;
+  status: "open" | "resolved";
+  outcome?: "buyer_favored" | "seller_favored" | "no_fault";
+  resolution_note?: string;
+  created_at: string;
+  resolved_at?: string;
+  messages: DisputeMessage[];
+  evidence: DisputeEvidence[];
+}
+
+export interface Reputation {
+  user_id: number;
+  score: number;
+  completed_orders: number;
+  disputes_lost: number;
+  cancellations: number;
+}
+
+export type PaymentStatus =
+  | "pending"
+  | "authorized"
+  | "captured"
+  | "voided"
+  | "refunded"
+  | "failed";
+
+export interface Payment {
+  id: number;
+  order_id: number;
+  provider: string;
+  reference?: string;
+  amount: number;
+  currency: string;
+  status: PaymentStatus;
+  attempts: number;
+  failure_reason?: string;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface HandoffCode {
+  code: string;
+  qr_payload: string;
+  expires_at: string;
+}
+
+export interface AvailabilityWindow {
+  id: number;
+  user_id: number;
+  starts_at: string;
+  ends_at: string;
+  created_at: string;
+}
+
+export type MeetupProposalStatus = "pending" | "accepted" | "declined" | "superseded";
+
+export interface MeetupProposal {
+  id: number;
+  order_id: number;
+  proposed_by: number;
+  starts_at: string;
+  ends_at: string;
+  status: MeetupProposalStatus;
+  created_at: string;
+  responded_at?: string;
+}
+
+export type OrderRole = "buyer" | "seller";
+
+export interface OrderListItem extends Order {
+  created_at: string;
+  role: OrderRole;
+  product_title?: string;
+  product_thumbnail?: string;
+  counterparty_id: number;
+  counterparty_name?: string;
+}
+
+export interface OrderFilters {
+  role?: OrderRole;
+  status?: string;
+  from?: string;
+  to?: string;
 }
 
 export interface LocationInfo {
   latitude: number;
   longitude: number;
   address: string;
+  approximate: boolean;
 }
 
 export interface MidpointInfo {
@@ -104,6 +245,15 @@ export interface LocationUpsertResponse {
   address: string;
 }
 
+export type ListingStatus =
+  | "draft"
+  | "active"
+  | "reserved"
+  | "sold"
+  | "expired"
+  | "removed"
+  | "hidden";
+
 export interface Product {
   id: number;
   seller_id: number;
@@ -113,7 +263,38 @@ export interface Product {
   description: string;
   price: number;
   image_url?: string;
-  status: string;
+  status: ListingStatus;
+  attributes: Record<string, string | number | boolean>;
+  condition: string;
+  quantity: number;
+  listing_type: string;
+  requires_meetup: boolean;
+  favorite_count: number;
+  latitude?: number;
+  longitude?: number;
+  distance_km?: number;
+  expires_at: string;
+  revision: number;
+}
+
+export interface ListingRevision {
+  product_id: number;
+  revision: number;
+  snapshot: {
+    category_id: number;
+    title: string;
+    description: string;
+    price: number;
+    image_url?: string;
+    status: ListingStatus;
+    attributes: Record<string, string | number | boolean>;
+    condition: string;
+    quantity: number;
+    listing_type: string;
+    latitude?: number;
+    longitude?: number;
+  };
+  created_at: string;
 }
 
 export interface Category {
@@ -121,6 +302,8 @@ export interface Category {
   name: string;
   slug: string;
   icon?: string;
+  parent_id?: number;
+  sort_order: number;
 }
 
 export interface User {
@@ -192,11 +375,13 @@ export async function getCategories(): Promise<Category[]> {
 export async function getProducts(
   categoryId?: number,
   limit = 20,
-): Promise<Product[]> {
+  cursor?: string,
+): Promise<Page<Product>> {
   const config = await getConfig();
   const params = new URLSearchParams();
   if (categoryId) params.append("category_id", categoryId.toString());
   params.append("limit", limit.toString());
+  if (cursor) params.append("cursor", cursor);
 
   const response = await fetch(`${config.PRODUCT_SERVICE}/products?${params}`);
   if (!response.ok) throw new Error("Failed to fetch products");
@@ -213,10 +398,13 @@ export async function getProduct(id: number): Promise<Product> {
 export async function getCategoryProducts(
   slug: string,
   limit = 20,
-): Promise<Product[]> {
+  cursor?: string,
+): Promise<Page<Product>> {
   const config = await getConfig();
+  const params = new URLSearchParams({ limit: limit.toString() });
+  if (cursor) params.append("cursor", cursor);
   const response = await fetch(
-    `${config.PRODUCT_SERVICE}/categories/${slug}/products?limit=${limit}`,
+    `${config.PRODUCT_SERVICE}/categories/${slug}/products?${params}`,
   );
   if (!response.ok) throw new Error("Failed to fetch category products");
   return response.json();
@@ -250,12 +438,14 @@ export async function createOrder(
   token: string,
   data: {
     product_id: number;
-    seller_id: number;
-    buyer_location: {
+    seller_id?: number;
+    quantity?: number;
+    buyer_location?: {
       latitude: number;
       longitude: number;
       address: string;
     };
+    payment_method?: string;
   },
 ) {
   const config = await getConfig();
@@ -280,11 +470,310 @@ export async function getOrder(token: string, id: number): Promise<Order> {
   return response.json();
 }
 
-export async function getMyOrders(token: string): Promise<Order[]> {
+export async function acceptOrder(token: string, orderId: number): Promise<Order> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/accept`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (response.status === 402) throw new Error("The buyer's payment was declined");
+  if (!response.ok) throw new Error("Failed to accept order");
+  return response.json();
+}
+
+export async function cancelOrder(
+  token: string,
+  orderId: number,
+  reason: CancellationReason,
+  note?: string,
+): Promise<Order> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/cancel`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ reason, note }),
+  });
+  if (response.status === 422) throw new Error("Please describe why you are cancelling");
+  if (!response.ok) throw new Error("Failed to cancel order");
+  return response.json();
+}
+
+export async function raiseDispute(
+  token: string,
+  orderId: number,
+  reason: DisputeReason,
+  description: string,
+): Promise<Dispute> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/dispute`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ reason, description }),
+  });
+  if (!response.ok) throw new Error("Failed to open dispute");
+  return response.json();
+}
+
+export async function getOrderDispute(token: string, orderId: number): Promise<Dispute> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/dispute`, {
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) throw new Error("Failed to fetch dispute");
+  return response.json();
+}
+
+export async function postDisputeMessage(
+  token: string,
+  orderId: number,
+  body: string,
+): Promise<DisputeMessage> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/dispute/messages`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ body }),
+  });
+  if (!response.ok) throw new Error("Failed to send message");
+  return response.json();
+}
+
+export async function addDisputeEvidence(
+  token: string,
+  orderId: number,
+  url: string,
+  description?: string,
+): Promise<DisputeEvidence> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/dispute/evidence`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ url, description }),
+  });
+  if (!response.ok) throw new Error("Failed to add evidence");
+  return response.json();
+}
+
+export async function getReputation(token: string, userId: number): Promise<Reputation> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/reputation/users/${userId}`, {
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) throw new Error("Failed to fetch reputation");
+  return response.json();
+}
+
+export async function getOrderPayment(token: string, orderId: number): Promise<Payment> {
   const config = await getConfig();
-  const response = await fetch(`${config.ORDER_SERVICE}/orders/my-orders`, {
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/payment`, {
     headers: { Authorization: `Bearer ${token}` },
   });
+  if (!response.ok) throw new Error("Failed to fetch payment");
+  return response.json();
+}
+
+export async function updatePaymentMethod(
+  token: string,
+  orderId: number,
+  paymentMethod: string,
+): Promise<Payment> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/payment/method`, {
+    method: "PUT",
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ payment_method: paymentMethod }),
+  });
+  if (!response.ok) throw new Error("Failed to update payment method");
+  return response.json();
+}
+
+export async function issueHandoffCode(token: string, orderId: number): Promise<HandoffCode> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/handoff/code`, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) throw new Error("Failed to issue handoff code");
+  return response.json();
+}
+
+export async function completeHandoff(
+  token: string,
+  orderId: number,
+  code: string,
+  location?: { latitude: number; longitude: number },
+): Promise<Order> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/handoff`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ code, ...location }),
+  });
+  if (response.status === 422) throw new Error("That code is not valid");
+  if (response.status === 410) throw new Error("That code has expired; ask the buyer for a new one");
+  if (response.status === 429) throw new Error("Too many wrong codes; ask the buyer for a new one");
+  if (!response.ok) throw new Error("Failed to complete handoff");
+  return response.json();
+}
+
+export async function getOrderListing(
+  token: string,
+  id: number,
+): Promise<ListingRevision> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${id}/listing`, {
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) throw new Error("Failed to fetch ordered listing");
+  return response.json();
+}
+
+export async function getAvailability(
+  token: string,
+  userId: number,
+): Promise<AvailabilityWindow[]> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/availability/users/${userId}`, {
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) throw new Error("Failed to fetch availability");
+  return response.json();
+}
+
+export async function createAvailability(
+  token: string,
+  startsAt: string,
+  endsAt: string,
+): Promise<AvailabilityWindow> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/availability`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ starts_at: startsAt, ends_at: endsAt }),
+  });
+  if (!response.ok) throw new Error("Failed to add availability");
+  return response.json();
+}
+
+export async function deleteAvailability(token: string, id: number): Promise<void> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/availability/${id}`, {
+    method: "DELETE",
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) throw new Error("Failed to remove availability");
+}
+
+export async function getMeetupProposals(
+  token: string,
+  orderId: number,
+): Promise<MeetupProposal[]> {
+  const config = await getConfig();
+  const response = await fetch(
+    `${config.ORDER_SERVICE}/orders/${orderId}/meetup/proposals`,
+    { headers: { Authorization: `Bearer ${token}` } },
+  );
+  if (!response.ok) throw new Error("Failed to fetch meetup proposals");
+  return response.json();
+}
+
+export async function proposeMeetup(
+  token: string,
+  orderId: number,
+  startsAt: string,
+  durationMinutes?: number,
+): Promise<MeetupProposal> {
+  const config = await getConfig();
+  const response = await fetch(
+    `${config.ORDER_SERVICE}/orders/${orderId}/meetup/proposals`,
+    {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+        Authorization: `Bearer ${token}`,
+      },
+      body: JSON.stringify({ starts_at: startsAt, duration_minutes: durationMinutes }),
+    },
+  );
+  if (!response.ok) throw new Error("Failed to propose meetup");
+  return response.json();
+}
+
+export async function confirmMeetup(
+  token: string,
+  orderId: number,
+  proposalId: number,
+): Promise<Order> {
+  const config = await getConfig();
+  const response = await fetch(
+    `${config.ORDER_SERVICE}/orders/${orderId}/meetup/proposals/${proposalId}/confirm`,
+    { method: "POST", headers: { Authorization: `Bearer ${token}` } },
+  );
+  if (!response.ok) throw new Error("Failed to confirm meetup");
+  return response.json();
+}
+
+export async function declineMeetup(
+  token: string,
+  orderId: number,
+  proposalId: number,
+): Promise<MeetupProposal> {
+  const config = await getConfig();
+  const response = await fetch(
+    `${config.ORDER_SERVICE}/orders/${orderId}/meetup/proposals/${proposalId}/decline`,
+    { method: "POST", headers: { Authorization: `Bearer ${token}` } },
+  );
+  if (!response.ok) throw new Error("Failed to decline meetup");
+  return response.json();
+}
+
+export async function getMeetupCalendar(token: string, orderId: number): Promise<Blob> {
+  const config = await getConfig();
+  const response = await fetch(`${config.ORDER_SERVICE}/orders/${orderId}/meetup.ics`, {
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) throw new Error("Failed to download meetup calendar");
+  return response.blob();
+}
+
+export async function getMyOrders(
+  token: string,
+  cursor?: string,
+  filters: OrderFilters = {},
+): Promise<Page<OrderListItem>> {
+  const config = await getConfig();
+  const params = new URLSearchParams();
+  if (cursor) params.append("cursor", cursor);
+  for (const [key, value] of Object.entries(filters)) {
+    if (value) params.append(key, value);
+  }
+  const response = await fetch(
+    `${config.ORDER_SERVICE}/orders/my-orders?${params}`,
+    {
+      headers: { Authorization: `Bearer ${token}` },
+    },
+  );
   if (!response.ok) throw new Error("Failed to fetch orders");
   return response.json();
 }
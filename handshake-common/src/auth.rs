@@ -0,0 +1,223 @@
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest, Request};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Validate};
+use crate::error::FieldError;
+
+/// Used when `JWT_SECRET` isn't set, so local setups work out of the box.
+/// Every service falls back to the same one, so tokens issued by
+/// auth-service are accepted everywhere. Release builds refuse to start
+/// with it.
+const DEV_JWT_SECRET: &str = "dev-secret-key-change-in-production";
+const TOKEN_TTL_DAYS: i64 = 30;
+
+fn dev_jwt_secret() -> String {
+    DEV_JWT_SECRET.to_string()
+}
+
+/// Secrets and allow-lists the request guards check against. Flattened into
+/// each service's settings and managed as Rocket state.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default = "dev_jwt_secret", deserialize_with = "config::text")]
+    pub jwt_secret: String,
+    /// Comma-separated user ids allowed to moderate and resolve disputes
+    #[serde(default, deserialize_with = "config::text")]
+    pub admin_user_ids: String,
+    /// Shared secret for service-to-service calls; internal endpoints
+    /// reject everything while it's unset
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub internal_api_token: Option<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            jwt_secret: dev_jwt_secret(),
+            admin_user_ids: String::new(),
+            internal_api_token: None,
+        }
+    }
+}
+
+impl Validate for AuthConfig {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        if self.jwt_secret.is_empty() {
+            problems.push(FieldError::new("jwt_secret", "must not be empty"));
+        } else if self.jwt_secret == DEV_JWT_SECRET && !cfg!(debug_assertions) {
+            problems.push(FieldError::new(
+                "jwt_secret",
+                "is the development secret; set JWT_SECRET",
+            ));
+        }
+        if self
+            .admin_user_ids
+            .split(',')
+            .map(str::trim)
+            .any(|id| !id.is_empty() && id.parse::<i32>().is_err())
+        {
+            problems.push(FieldError::new(
+                "admin_user_ids",
+                "must be a comma-separated list of user ids",
+            ));
+        }
+        problems
+    }
+}
+
+/// The [`AuthConfig`] the service manages; guards fail closed without one
+fn auth_config<'r>(request: &'r Request<'_>) -> Option<&'r AuthConfig> {
+    request.rocket().state::<AuthConfig>()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: i32, // user id
+    pub email: String,
+    pub exp: usize, // expiration time
+}
+
+pub fn create_jwt(
+    config: &AuthConfig,
+    user_id: i32,
+    email: String,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let expiration = chrono::Utc::now()
+        .checked_add_signed(chrono::Duration::days(TOKEN_TTL_DAYS))
+        .unwrap()
+        .timestamp() as usize;
+
+    let claims = Claims {
+        sub: user_id,
+        email,
+        exp: expiration,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_ref()),
+    )
+}
+
+pub fn verify_jwt(config: &AuthConfig, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let token_data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_ref()),
+        &Validation::default(),
+    )?;
+
+    Ok(token_data.claims)
+}
+
+/// A user presenting a valid `Authorization: Bearer <jwt>` issued by auth-service
+pub struct AuthenticatedUser {
+    pub user_id: i32,
+    pub email: String,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuthenticatedUser {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let Some(config) = auth_config(request) else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+        let token = match request.headers().get_one("Authorization") {
+            Some(header) => header.trim_start_matches("Bearer ").trim(),
+            None => return Outcome::Error((Status::Unauthorized, ())),
+        };
+
+        match verify_jwt(config, token) {
+            Ok(claims) => Outcome::Success(AuthenticatedUser {
+                user_id: claims.sub,
+                email: claims.email,
+            }),
+            Err(_) => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// Whether `user_id` is listed in `admin_ids` (comma-separated)
+fn is_admin(admin_ids: &str, user_id: i32) -> bool {
+    admin_ids
+        .split(',')
+        .filter_map(|id| id.trim().parse::<i32>().ok())
+        .any(|id| id == user_id)
+}
+
+/// An authenticated user whose id is listed in `ADMIN_USER_IDS`
+/// (comma-separated). Guards moderation and dispute resolution.
+pub struct AdminUser {
+    pub user_id: i32,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminUser {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let user = match AuthenticatedUser::from_request(request).await {
+            Outcome::Success(user) => user,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(f) => return Outcome::Forward(f),
+        };
+
+        let admin_ids = auth_config(request).map_or("", |config| &config.admin_user_ids);
+        if is_admin(admin_ids, user.user_id) {
+            Outcome::Success(AdminUser {
+                user_id: user.user_id,
+            })
+        } else {
+            Outcome::Error((Status::Forbidden, ()))
+        }
+    }
+}
+
+/// Another Handshake service calling an internal endpoint. Requests must
+/// carry `X-Internal-Token` matching `INTERNAL_API_TOKEN`.
+pub struct InternalService;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for InternalService {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let expected = match auth_config(request).and_then(|c| c.internal_api_token.as_deref()) {
+            Some(token) if !token.is_empty() => token,
+            _ => return Outcome::Error((Status::Unauthorized, ())),
+        };
+
+        match request.headers().get_one("X-Internal-Token") {
+            Some(token) if token == expected => Outcome::Success(InternalService),
+            _ => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_tokens_verify() {
+        let config = AuthConfig::default();
+        let token = create_jwt(&config, 42, "buyer@example.com".to_string()).unwrap();
+        let claims = verify_jwt(&config, &token).unwrap();
+        assert_eq!(claims.sub, 42);
+        assert_eq!(claims.email, "buyer@example.com");
+        assert!(verify_jwt(&config, &format!("{}x", token)).is_err());
+    }
+
+    #[test]
+    fn admin_ids_are_comma_separated() {
+        assert!(is_admin("3, 17,42", 17));
+        assert!(!is_admin("3,17", 4));
+        assert!(!is_admin("", 1));
+    }
+}
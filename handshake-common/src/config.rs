@@ -0,0 +1,192 @@
+use rocket::figment::error::Kind;
+use rocket::figment::providers::Env;
+use rocket::figment::Figment;
+use rocket_cors::{AllowedOrigins, Cors, CorsOptions};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use std::env;
+
+use crate::error::FieldError;
+
+/// Command-line flag that validates the settings and exits instead of serving
+pub const CHECK_CONFIG_FLAG: &str = "--check-config";
+
+/// Local dev server of the frontend, always allowed alongside `APP_URL`
+pub const DEV_FRONTEND_ORIGIN: &str = "http://localhost:3000";
+
+/// Load `.env` if there is one. Variables already set in the environment win.
+pub fn load_env() {
+    dotenv::dotenv().ok();
+}
+
+/// Settings a service loads once at startup
+pub trait Validate {
+    /// Problems serde can't catch, e.g. a URL without a scheme. Empty when
+    /// the settings are usable.
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+/// Where service settings come from: the selected profile of `Rocket.toml`,
+/// overridden by environment variables named after the field in upper case
+/// (`EMAIL_SERVICE_URL` sets `email_service_url`)
+pub fn figment() -> Figment {
+    rocket::Config::figment().merge(Env::raw().global())
+}
+
+/// `T` from `figment`, or every problem with it: missing and malformed
+/// values first, then whatever [`Validate`] finds
+pub fn extract<T: DeserializeOwned + Validate>(figment: &Figment) -> Result<T, Vec<FieldError>> {
+    let settings: T = figment
+        .extract()
+        .map_err(|errors| errors.into_iter().map(setting_error).collect::<Vec<_>>())?;
+
+    let problems = settings.validate();
+    if problems.is_empty() {
+        Ok(settings)
+    } else {
+        Err(problems)
+    }
+}
+
+fn setting_error(error: rocket::figment::Error) -> FieldError {
+    match &error.kind {
+        Kind::MissingField(field) => {
+            FieldError::new(field, &format!("is required; set {}", field.to_uppercase()))
+        }
+        kind => FieldError::new(&error.path.join("."), &kind.to_string()),
+    }
+}
+
+/// Load the service's settings, or log what's wrong with them and exit.
+/// Run with `--check-config` to only validate: exits 0 if they're fine.
+pub fn load<T: DeserializeOwned + Validate>() -> T {
+    let check_only = env::args().any(|arg| arg == CHECK_CONFIG_FLAG);
+
+    match extract(&figment()) {
+        Ok(settings) if !check_only => settings,
+        Ok(_) => {
+            tracing::info!("Configuration is valid");
+            std::process::exit(0);
+        }
+        Err(problems) => {
+            for problem in &problems {
+                tracing::error!(
+                    setting = %problem.field,
+                    "Invalid configuration: {}",
+                    problem.message
+                );
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+/// An absolute `http(s)` URL, or a problem naming `field`
+pub fn check_url(field: &str, url: &str) -> Option<FieldError> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => None,
+        Ok(_) => Some(FieldError::new(field, "must be an http or https URL")),
+        Err(e) => Some(FieldError::new(
+            field,
+            &format!("is not a valid URL: {}", e),
+        )),
+    }
+}
+
+/// For counts, periods and limits that must be above zero
+pub fn check_positive<T: PartialOrd + Default>(field: &str, value: T) -> Option<FieldError> {
+    (value <= T::default()).then(|| FieldError::new(field, "must be positive"))
+}
+
+/// Read a setting as text even when it looks like a number or a boolean.
+/// Environment values are parsed, so `ADMIN_USER_IDS=7` would otherwise
+/// arrive as an integer.
+pub fn text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Text {
+        String(String),
+        Unsigned(u64),
+        Signed(i64),
+        Float(f64),
+        Bool(bool),
+    }
+
+    Ok(match Text::deserialize(deserializer)? {
+        Text::String(value) => value,
+        Text::Unsigned(value) => value.to_string(),
+        Text::Signed(value) => value.to_string(),
+        Text::Float(value) => value.to_string(),
+        Text::Bool(value) => value.to_string(),
+    })
+}
+
+/// [`text`] for settings that may be left out
+pub fn optional_text<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    text(deserializer).map(Some)
+}
+
+/// CORS for browser calls from the frontend. With the `app_url` setting,
+/// only it and the local dev server may call in; without it any origin may.
+pub fn cors(app_url: Option<&str>) -> Cors {
+    let allowed_origins = match app_url {
+        Some(app_url) if !app_url.is_empty() => {
+            AllowedOrigins::some_exact(&[app_url, DEV_FRONTEND_ORIGIN])
+        }
+        _ => AllowedOrigins::all(),
+    };
+
+    CorsOptions {
+        allowed_origins,
+        ..Default::default()
+    }
+    .to_cors()
+    .expect("CORS options are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::figment::providers::Serialized;
+
+    #[derive(Debug, Deserialize)]
+    struct Example {
+        database_url: String,
+        #[serde(deserialize_with = "text")]
+        admin_user_ids: String,
+    }
+
+    impl Validate for Example {
+        fn validate(&self) -> Vec<FieldError> {
+            check_url("database_url", &self.database_url)
+                .into_iter()
+                .collect()
+        }
+    }
+
+    #[test]
+    fn missing_settings_name_their_variable() {
+        let figment = Figment::from(Serialized::default("admin_user_ids", "1"));
+        let problems = extract::<Example>(&figment).unwrap_err();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].field, "database_url");
+        assert!(problems[0].message.contains("DATABASE_URL"));
+    }
+
+    #[test]
+    fn numeric_values_are_read_as_text() {
+        let figment = Figment::from(Serialized::default("database_url", "http://db"))
+            .merge(Serialized::default("admin_user_ids", 7));
+        let settings = extract::<Example>(&figment).unwrap();
+        assert_eq!(settings.admin_user_ids, "7");
+    }
+
+    #[test]
+    fn urls_need_an_http_scheme() {
+        assert!(check_url("app_url", "https://handshake.example").is_none());
+        assert!(check_url("app_url", "ftp://handshake.example").is_some());
+        assert!(check_url("app_url", "localhost:3000").is_some());
+    }
+}
@@ -0,0 +1,224 @@
+use diesel::{Connection, PgConnection};
+use diesel_async::pooled_connection::bb8::{Pool, PooledConnection, RunError};
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::Deserialize;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+
+use crate::config::Validate;
+use crate::error::FieldError;
+use crate::metrics;
+
+/// Apply pending migrations before the service starts taking traffic. Exits
+/// if the database is unreachable or a migration fails.
+pub fn run_migrations(database_url: &str, migrations: EmbeddedMigrations) {
+    tracing::info!("Running database migrations...");
+    let mut connection = PgConnection::establish(database_url).unwrap_or_else(|e| {
+        tracing::error!(error = %e, "Error connecting to database for migrations");
+        std::process::exit(1);
+    });
+
+    match connection.run_pending_migrations(migrations) {
+        Ok(migrations) => {
+            if migrations.is_empty() {
+                tracing::info!("No pending migrations to run.");
+            } else {
+                tracing::info!(count = migrations.len(), "Ran pending migrations");
+                for migration in migrations {
+                    tracing::info!(migration = %migration, "Applied migration");
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error running migrations");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Where the database is and how many connections to keep to it. Flattened
+/// into each service's settings, so `DATABASE_POOL_SIZE` etc. set it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseConfig {
+    #[serde(rename = "database_url")]
+    pub url: String,
+    /// Most connections the pool opens at once
+    #[serde(rename = "database_pool_size", default = "default_pool_size")]
+    pub pool_size: u32,
+    /// Connections kept open while idle; none by default
+    #[serde(rename = "database_min_idle", default)]
+    pub min_idle: Option<u32>,
+    /// How long a request waits for a free connection before getting 503
+    #[serde(
+        rename = "database_pool_timeout_secs",
+        default = "default_pool_timeout_secs"
+    )]
+    pub pool_timeout_secs: u64,
+}
+
+fn default_pool_size() -> u32 {
+    10
+}
+
+fn default_pool_timeout_secs() -> u64 {
+    5
+}
+
+impl Validate for DatabaseConfig {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        if !(self.url.starts_with("postgres://") || self.url.starts_with("postgresql://")) {
+            problems.push(FieldError::new(
+                "database_url",
+                "must be a postgres:// connection URL",
+            ));
+        }
+        if self.pool_size == 0 {
+            problems.push(FieldError::new("database_pool_size", "must be at least 1"));
+        }
+        if self.min_idle.is_some_and(|idle| idle > self.pool_size) {
+            problems.push(FieldError::new(
+                "database_min_idle",
+                "must not exceed database_pool_size",
+            ));
+        }
+        if self.pool_timeout_secs == 0 {
+            problems.push(FieldError::new(
+                "database_pool_timeout_secs",
+                "must be at least 1",
+            ));
+        }
+        problems
+    }
+}
+
+/// A service's async connection pool, managed as Rocket state. Handlers get
+/// a connection through [`DbConn`]; background tasks call [`DbPool::get`].
+#[derive(Clone)]
+pub struct DbPool {
+    name: &'static str,
+    max_size: u32,
+    pool: Pool<AsyncPgConnection>,
+}
+
+impl DbPool {
+    /// A pool for `config` that connects on first use. `name` labels its
+    /// metrics. Must be called from within the Tokio runtime.
+    pub fn new(name: &'static str, config: &DatabaseConfig) -> Self {
+        let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(&config.url);
+        let pool = Pool::builder()
+            .max_size(config.pool_size)
+            .min_idle(config.min_idle)
+            .connection_timeout(Duration::from_secs(config.pool_timeout_secs))
+            .build_unchecked(manager);
+
+        DbPool {
+            name,
+            max_size: config.pool_size,
+            pool,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn max_size(&self) -> u32 {
+        self.max_size
+    }
+
+    /// Open and idle connections right now
+    pub fn state(&self) -> bb8::State {
+        self.pool.state()
+    }
+
+    /// A connection, waiting up to the configured timeout for one to free up
+    pub async fn get(&self) -> Result<DbConn, RunError> {
+        let started = Instant::now();
+        let conn = self.pool.get_owned().await;
+        metrics::record_checkout(self.name, started.elapsed(), conn.is_ok());
+        conn.map(DbConn)
+    }
+}
+
+/// A pooled connection held for the rest of the request. Use it wherever
+/// diesel-async expects a connection, e.g. `.load(&mut db).await`.
+pub struct DbConn(PooledConnection<'static, AsyncPgConnection>);
+
+impl Deref for DbConn {
+    type Target = AsyncPgConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for DbConn {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DbConn {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(pool) = request.rocket().state::<DbPool>() else {
+            tracing::error!("No database pool is managed");
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+
+        match pool.get().await {
+            Ok(conn) => Outcome::Success(conn),
+            Err(e) => {
+                tracing::warn!(pool = pool.name, error = %e, "No database connection available");
+                Outcome::Error((Status::ServiceUnavailable, ()))
+            }
+        }
+    }
+}
+
+/// Cheapest possible round trip, for readiness probes
+pub async fn ping(conn: &mut AsyncPgConnection) -> Result<(), String> {
+    diesel::sql_query("SELECT 1")
+        .execute(conn)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(url: &str, pool_size: u32, min_idle: Option<u32>) -> DatabaseConfig {
+        DatabaseConfig {
+            url: url.to_string(),
+            pool_size,
+            min_idle,
+            pool_timeout_secs: 5,
+        }
+    }
+
+    #[test]
+    fn pool_settings_are_checked() {
+        assert!(config("postgres://localhost/db", 10, Some(2))
+            .validate()
+            .is_empty());
+
+        let fields: Vec<String> = config("mysql://localhost/db", 0, Some(1))
+            .validate()
+            .into_iter()
+            .map(|problem| problem.field)
+            .collect();
+        assert_eq!(
+            fields,
+            ["database_url", "database_pool_size", "database_min_idle"]
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use rocket::{catch, catchers, Catcher};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::request_id::RequestId;
+use crate::telemetry;
+
+/// A problem with one input field, e.g. `{"field": "price", "message": "must be positive"}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Body of every error response:
+/// `{"code": "not_found", "message": "Order not found", "request_id": "..."}`,
+/// plus `fields` when the request failed validation
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    pub request_id: String,
+}
+
+/// Error returned by handlers. Renders as an [`ErrorBody`]; the underlying
+/// cause is logged with the request id and never sent to the client.
+#[derive(Debug, PartialEq)]
+pub struct ApiError {
+    pub status: Status,
+    pub code: &'static str,
+    pub message: String,
+    pub fields: Vec<FieldError>,
+    source: Option<String>,
+}
+
+/// Stable machine-readable code for `status`
+fn code_for(status: Status) -> &'static str {
+    match status.code {
+        400 => "bad_request",
+        401 => "unauthorized",
+        402 => "payment_required",
+        403 => "forbidden",
+        404 => "not_found",
+        409 => "conflict",
+        410 => "gone",
+        412 => "precondition_failed",
+        413 => "payload_too_large",
+        422 => "unprocessable_entity",
+        428 => "precondition_required",
+        429 => "too_many_requests",
+        502 => "bad_gateway",
+        503 => "service_unavailable",
+        code if code >= 500 => "internal_error",
+        _ => "error",
+    }
+}
+
+impl ApiError {
+    pub fn new(status: Status, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            code: code_for(status),
+            message: message.into(),
+            fields: Vec::new(),
+            source: None,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::new(Status::BadRequest, message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::new(Status::Unauthorized, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::new(Status::Forbidden, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::new(Status::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::new(Status::Conflict, message)
+    }
+
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        ApiError::new(Status::UnprocessableEntity, message)
+    }
+
+    /// 422 listing every invalid field
+    pub fn invalid(fields: Vec<FieldError>) -> Self {
+        ApiError::unprocessable("Some fields are invalid")
+            .with_code("validation_failed")
+            .with_fields(fields)
+    }
+
+    /// 500 for an unexpected failure. `err` is logged, not returned.
+    pub fn internal(err: impl Display) -> Self {
+        ApiError::new(Status::InternalServerError, "Something went wrong").with_source(err)
+    }
+
+    /// 502 for another service or provider that failed or couldn't be reached
+    pub fn upstream(service: &str, err: impl Display) -> Self {
+        ApiError::new(Status::BadGateway, format!("{} is unavailable", service)).with_source(err)
+    }
+
+    /// Keep `err` for the server log
+    pub fn with_source(mut self, err: impl Display) -> Self {
+        self.source = Some(err.to_string());
+        self
+    }
+
+    /// Point the client at the fields to fix
+    pub fn with_fields(mut self, fields: Vec<FieldError>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Override the status-derived code, e.g. `payment_declined` for a 402
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// For `map_err` on a query whose `NotFound` means `status`, e.g. 409
+    /// when a conditional update matched no row. Other database errors are 500s.
+    #[cfg(feature = "db")]
+    pub fn when_missing(
+        status: Status,
+        message: &'static str,
+    ) -> impl FnOnce(diesel::result::Error) -> ApiError {
+        move |err| match err {
+            diesel::result::Error::NotFound => ApiError::new(status, message),
+            err => ApiError::internal(err),
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.status.code, self.message)?;
+        if let Some(source) = &self.source {
+            write!(f, ": {}", source)?;
+        }
+        Ok(())
+    }
+}
+
+/// Plain statuses from guards and helpers get the standard reason as message
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        ApiError::new(status, status.reason().unwrap_or("Error"))
+    }
+}
+
+impl From<FieldError> for ApiError {
+    fn from(field: FieldError) -> Self {
+        ApiError::invalid(vec![field])
+    }
+}
+
+impl From<Vec<FieldError>> for ApiError {
+    fn from(fields: Vec<FieldError>) -> Self {
+        ApiError::invalid(fields)
+    }
+}
+
+#[cfg(feature = "db")]
+impl From<diesel::result::Error> for ApiError {
+    fn from(err: diesel::result::Error) -> Self {
+        match err {
+            diesel::result::Error::NotFound => ApiError::not_found("Not found"),
+            err => ApiError::internal(err),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let request_id = RequestId::of(request);
+        let span = telemetry::current_span(request);
+        let source = self.source.as_deref().unwrap_or_default();
+        if self.status.code >= 500 {
+            tracing::error!(parent: span, code = self.code, error = source, "{}", self.message);
+        } else if self.source.is_some() {
+            tracing::warn!(parent: span, code = self.code, error = source, "{}", self.message);
+        }
+
+        let body = ErrorBody {
+            code: self.code,
+            message: self.message,
+            fields: self.fields,
+            request_id: request_id.0,
+        };
+        response::Response::build_from(Json(body).respond_to(request)?)
+            .status(self.status)
+            .ok()
+    }
+}
+
+#[catch(401)]
+fn unauthorized() -> ApiError {
+    ApiError::unauthorized("Missing or invalid credentials")
+}
+
+#[catch(404)]
+fn not_found(request: &Request<'_>) -> ApiError {
+    ApiError::not_found(format!(
+        "No route for {} {}",
+        request.method(),
+        request.uri().path()
+    ))
+}
+
+#[catch(422)]
+fn unprocessable() -> ApiError {
+    ApiError::unprocessable("The request body could not be understood")
+}
+
+#[catch(500)]
+fn internal() -> ApiError {
+    ApiError::new(Status::InternalServerError, "Something went wrong")
+}
+
+#[catch(default)]
+fn default(status: Status, _request: &Request<'_>) -> ApiError {
+    ApiError::from(status)
+}
+
+/// Catchers so failures Rocket handles itself (failed guards, unmatched
+/// routes, unparseable bodies) use the same JSON shape as handler errors
+pub fn catchers() -> Vec<Catcher> {
+    catchers![unauthorized, not_found, unprocessable, internal, default]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_follow_the_status() {
+        assert_eq!(ApiError::from(Status::Conflict).code, "conflict");
+        assert_eq!(ApiError::from(Status::Conflict).message, "Conflict");
+        assert_eq!(ApiError::internal("pool timed out").code, "internal_error");
+        assert_eq!(
+            ApiError::invalid(vec![FieldError::new("price", "must be positive")]).code,
+            "validation_failed"
+        );
+    }
+
+    #[test]
+    fn unmatched_routes_get_the_json_shape() {
+        use crate::request_id::REQUEST_ID_HEADER;
+        use crate::telemetry::RequestTracing;
+        use rocket::http::Header;
+        use rocket::local::blocking::Client;
+
+        let rocket = rocket::build()
+            .attach(RequestTracing)
+            .register("/", catchers());
+        let client = Client::tracked(rocket).unwrap();
+        let response = client
+            .get("/missing")
+            .header(Header::new(REQUEST_ID_HEADER, "test-123"))
+            .dispatch();
+
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(
+            response.headers().get_one(REQUEST_ID_HEADER),
+            Some("test-123")
+        );
+        let body: serde_json::Value = response.into_json().unwrap();
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["request_id"], "test-123");
+        assert!(body.get("fields").is_none());
+    }
+
+    #[test]
+    fn causes_stay_out_of_the_message() {
+        let err = ApiError::internal("password authentication failed for user");
+        assert_eq!(err.message, "Something went wrong");
+        assert!(err.to_string().contains("password authentication failed"));
+    }
+}
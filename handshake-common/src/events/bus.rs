@@ -0,0 +1,184 @@
+use redis::aio::MultiplexedConnection;
+use redis::streams::{StreamMaxlen, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+
+use super::{EventSettings, OutboxEvent};
+
+const READ_COUNT: usize = 50;
+const READ_BLOCK_MS: usize = 5000;
+
+/// A Redis stream shared by all services. Each service relays its outbox
+/// into it and reads it back through its own consumer group.
+pub struct EventBus {
+    client: redis::Client,
+    /// Name of the service publishing through this handle
+    source: &'static str,
+    stream: String,
+    maxlen: usize,
+}
+
+/// An entry read back from the stream
+pub struct Delivery {
+    /// Redis stream entry id, used to acknowledge it
+    pub entry_id: String,
+    /// Stable id of the event, the same however often it is delivered
+    pub event_id: String,
+    pub payload: String,
+}
+
+impl EventBus {
+    /// A handle on the configured bus for `source`, or `None` when no bus
+    /// is configured
+    pub fn new(settings: &EventSettings, source: &'static str) -> Option<Self> {
+        let url = settings
+            .event_bus_url
+            .as_deref()
+            .filter(|url| !url.is_empty())?;
+        let client = match redis::Client::open(url) {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::warn!(error = %e, "Invalid EVENT_BUS_URL");
+                return None;
+            }
+        };
+
+        Some(EventBus {
+            client,
+            source,
+            stream: settings.event_stream.clone(),
+            maxlen: settings.event_stream_maxlen,
+        })
+    }
+
+    pub async fn connect(&self) -> Result<MultiplexedConnection, String> {
+        self.client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| format!("Failed to connect to event bus: {}", e))
+    }
+
+    pub async fn publish(
+        &self,
+        redis: &mut MultiplexedConnection,
+        event: &OutboxEvent,
+    ) -> Result<(), String> {
+        let fields = [
+            ("id", self.event_id(event.id)),
+            ("type", event.event_type.clone()),
+            ("source", self.source.to_string()),
+            ("occurred_at", event.created_at.and_utc().to_rfc3339()),
+            ("payload", event.payload.to_string()),
+        ];
+
+        let _: String = redis
+            .xadd_maxlen(
+                &self.stream,
+                StreamMaxlen::Approx(self.maxlen),
+                "*",
+                &fields,
+            )
+            .await
+            .map_err(|e| format!("Failed to publish event {}: {}", event.id, e))?;
+        Ok(())
+    }
+
+    /// Create `group` unless it already exists. A new group starts from the
+    /// oldest entry still in the stream, so nothing published before the
+    /// consumer's first deployment is missed.
+    pub async fn ensure_group(
+        &self,
+        redis: &mut MultiplexedConnection,
+        group: &str,
+    ) -> Result<(), String> {
+        let created: redis::RedisResult<()> =
+            redis.xgroup_create_mkstream(&self.stream, group, "0").await;
+        match created {
+            Ok(()) => Ok(()),
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            Err(e) => Err(format!("Failed to create consumer group {}: {}", group, e)),
+        }
+    }
+
+    /// The next entries for `consumer`. Start from `"0"` to get back entries
+    /// it was given before but never acknowledged, or `">"` to wait for new ones.
+    pub async fn read(
+        &self,
+        redis: &mut MultiplexedConnection,
+        group: &str,
+        consumer: &str,
+        start: &str,
+    ) -> Result<Vec<Delivery>, String> {
+        let options = StreamReadOptions::default()
+            .group(group, consumer)
+            .count(READ_COUNT)
+            .block(READ_BLOCK_MS);
+
+        let reply: StreamReadReply = redis
+            .xread_options(&[&self.stream], &[start], &options)
+            .await
+            .map_err(|e| format!("Failed to read events: {}", e))?;
+
+        Ok(reply
+            .keys
+            .into_iter()
+            .flat_map(|key| key.ids)
+            .map(|entry| Delivery {
+                event_id: entry.get("id").unwrap_or_default(),
+                payload: entry.get("payload").unwrap_or_default(),
+                entry_id: entry.id,
+            })
+            .collect())
+    }
+
+    pub async fn ack(
+        &self,
+        redis: &mut MultiplexedConnection,
+        group: &str,
+        entry_id: &str,
+    ) -> Result<(), String> {
+        let _: i64 = redis
+            .xack(&self.stream, group, &[entry_id])
+            .await
+            .map_err(|e| format!("Failed to acknowledge event {}: {}", entry_id, e))?;
+        Ok(())
+    }
+
+    /// Move an entry `group` has given up on to the dead-letter stream, next
+    /// to the error it last failed with, and acknowledge it
+    pub async fn dead_letter(
+        &self,
+        redis: &mut MultiplexedConnection,
+        group: &str,
+        delivery: &Delivery,
+        error: &str,
+    ) -> Result<(), String> {
+        let fields = [
+            ("id", delivery.event_id.as_str()),
+            ("group", group),
+            ("entry_id", delivery.entry_id.as_str()),
+            ("payload", delivery.payload.as_str()),
+            ("error", error),
+        ];
+
+        let _: String = redis
+            .xadd_maxlen(
+                self.dead_letter_stream(),
+                StreamMaxlen::Approx(self.maxlen),
+                "*",
+                &fields,
+            )
+            .await
+            .map_err(|e| format!("Failed to dead-letter event {}: {}", delivery.event_id, e))?;
+        self.ack(redis, group, &delivery.entry_id).await
+    }
+
+    /// Where entries no consumer could handle are kept for inspection
+    pub fn dead_letter_stream(&self) -> String {
+        format!("{}:dead", self.stream)
+    }
+
+    /// Globally unique id for one of the source's outbox rows
+    fn event_id(&self, outbox_id: i64) -> String {
+        format!("{}:{}", self.source, outbox_id)
+    }
+}
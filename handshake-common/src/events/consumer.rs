@@ -0,0 +1,218 @@
+use diesel_async::AsyncPgConnection;
+use rocket::fairing::AdHoc;
+use rocket::{tokio, Orbit, Rocket};
+use std::collections::HashMap;
+use std::env;
+use std::time::{Duration, Instant};
+
+use super::bus::EventBus;
+use super::EventSettings;
+use crate::db::DbPool;
+
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// Failures after which an event is moved to the dead-letter stream
+const MAX_ATTEMPTS: u32 = 8;
+
+/// What a service does with the events it reads from the bus
+#[rocket::async_trait]
+pub trait Handler: Send + Sync + 'static {
+    /// Apply one event. `event_id` is the same however often the event is
+    /// delivered, so handlers can record it to apply each event only once.
+    /// An error leaves the entry unacknowledged to be retried later.
+    async fn handle(
+        &self,
+        conn: &mut AsyncPgConnection,
+        event_id: &str,
+        payload: &str,
+    ) -> Result<(), String>;
+}
+
+/// Reads the bus through the `group` consumer group and passes each event to
+/// the handler `build` makes from the launched service's state. Entries are
+/// acknowledged once handled. One that fails is retried with backoff while
+/// later ones go ahead, and after `MAX_ATTEMPTS` failures it is moved to the
+/// dead-letter stream so it can't hold the consumer up for good.
+pub fn consumer<H, F>(group: &'static str, settings: EventSettings, build: F) -> AdHoc
+where
+    H: Handler,
+    F: FnOnce(&Rocket<Orbit>) -> Option<H> + Send + Sync + 'static,
+{
+    AdHoc::on_liftoff("Event consumer", move |rocket| {
+        Box::pin(async move {
+            let bus = match EventBus::new(&settings, group) {
+                Some(bus) => bus,
+                None => {
+                    tracing::warn!("Event consumer disabled: EVENT_BUS_URL not set");
+                    return;
+                }
+            };
+            let pool = match rocket.state::<DbPool>() {
+                Some(pool) => pool.clone(),
+                None => {
+                    tracing::warn!("Event consumer disabled: database pool unavailable");
+                    return;
+                }
+            };
+            let Some(handler) = build(rocket) else {
+                tracing::warn!("Event consumer disabled: handler state unavailable");
+                return;
+            };
+            // Replicas share the group, so each needs its own name in it
+            let consumer = env::var("HOSTNAME").unwrap_or_else(|_| group.to_string());
+
+            tokio::spawn(async move {
+                let mut retries = Retries::default();
+                loop {
+                    let consumed =
+                        consume(&pool, &bus, group, &consumer, &handler, &mut retries).await;
+                    if let Err(e) = consumed {
+                        tracing::error!(error = %e, "Event consumer failed");
+                    }
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+            });
+        })
+    })
+}
+
+/// Entries that failed and when to hand them to the handler again
+#[derive(Default)]
+struct Retries {
+    due: HashMap<String, Retry>,
+}
+
+struct Retry {
+    attempts: u32,
+    at: Instant,
+}
+
+impl Retries {
+    /// Whether any failed entry is waiting to be tried again by now
+    fn any_due(&self, now: Instant) -> bool {
+        self.due.values().any(|retry| retry.at <= now)
+    }
+
+    /// Whether `entry_id` failed before and is still backing off
+    fn waiting(&self, entry_id: &str, now: Instant) -> bool {
+        self.due.get(entry_id).is_some_and(|retry| retry.at > now)
+    }
+
+    /// Count a failure of `entry_id`. Returns `false` once it has failed
+    /// `MAX_ATTEMPTS` times and should be given up on.
+    fn failed(&mut self, entry_id: &str, now: Instant) -> bool {
+        let retry = self.due.entry(entry_id.to_string()).or_insert(Retry {
+            attempts: 0,
+            at: now,
+        });
+        retry.attempts += 1;
+        if retry.attempts >= MAX_ATTEMPTS {
+            self.due.remove(entry_id);
+            return false;
+        }
+        retry.at = now + backoff(retry.attempts);
+        true
+    }
+
+    fn done(&mut self, entry_id: &str) {
+        self.due.remove(entry_id);
+    }
+}
+
+/// Wait before retrying after `attempts` failures: doubling from
+/// `RETRY_DELAY` up to `MAX_BACKOFF`
+fn backoff(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    RETRY_DELAY.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+async fn consume<H: Handler>(
+    pool: &DbPool,
+    bus: &EventBus,
+    group: &str,
+    consumer: &str,
+    handler: &H,
+    retries: &mut Retries,
+) -> Result<(), String> {
+    let mut redis = bus.connect().await?;
+    bus.ensure_group(&mut redis, group).await?;
+
+    // Entries handed out before a restart but never acknowledged come first
+    let mut pending = true;
+    loop {
+        let start = if pending { "0" } else { ">" };
+        let deliveries = bus.read(&mut redis, group, consumer, start).await?;
+        // Entries that failed stay pending. Go back for them once one is due,
+        // but only after waiting for new entries in between, so ones still
+        // backing off can't keep the loop spinning.
+        pending = start == ">" && retries.any_due(Instant::now());
+        if deliveries.is_empty() {
+            continue;
+        }
+
+        let mut conn = pool.get().await.map_err(|e| e.to_string())?;
+        for delivery in deliveries {
+            if retries.waiting(&delivery.entry_id, Instant::now()) {
+                continue;
+            }
+
+            match handler
+                .handle(&mut conn, &delivery.event_id, &delivery.payload)
+                .await
+            {
+                Ok(()) => {
+                    bus.ack(&mut redis, group, &delivery.entry_id).await?;
+                    retries.done(&delivery.entry_id);
+                }
+                // Later entries go ahead while this one backs off
+                Err(e) if retries.failed(&delivery.entry_id, Instant::now()) => {
+                    tracing::warn!(
+                        event_id = %delivery.event_id,
+                        error = %e,
+                        "Event failed, will retry"
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        event_id = %delivery.event_id,
+                        error = %e,
+                        "Giving up on event after {} attempts",
+                        MAX_ATTEMPTS
+                    );
+                    bus.dead_letter(&mut redis, group, &delivery, &e).await?;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff(1), RETRY_DELAY);
+        assert_eq!(backoff(2), RETRY_DELAY * 2);
+        assert_eq!(backoff(3), RETRY_DELAY * 4);
+        assert_eq!(backoff(30), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn failing_entries_back_off_then_are_given_up_on() {
+        let mut retries = Retries::default();
+        let now = Instant::now();
+
+        assert!(retries.failed("1-0", now));
+        assert!(retries.waiting("1-0", now));
+        assert!(!retries.waiting("2-0", now));
+        assert!(!retries.any_due(now));
+        assert!(retries.any_due(now + RETRY_DELAY));
+
+        for _ in 2..MAX_ATTEMPTS {
+            assert!(retries.failed("1-0", now));
+        }
+        assert!(!retries.failed("1-0", now));
+        assert!(!retries.waiting("1-0", now));
+    }
+}
@@ -0,0 +1,136 @@
+//! Transactional outbox and the Redis stream it is relayed to. Each
+//! Postgres-backed service keeps its own `outbox_events` table with the same
+//! layout, writes events to it next to the change they describe, and attaches
+//! [`relay::relay`] to publish them under its name and [`consumer::consumer`]
+//! to react to the events of others.
+
+pub mod bus;
+pub mod consumer;
+pub mod relay;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, check_positive, Validate};
+use crate::error::FieldError;
+
+/// Where events are relayed to and how often, flattened into the settings of
+/// every service with an outbox
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventSettings {
+    /// e.g. `redis://localhost:6379`. Without it events stay in the outbox
+    /// until a bus is configured.
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub event_bus_url: Option<String>,
+    #[serde(default = "default_event_stream")]
+    pub event_stream: String,
+    /// Approximate number of entries the stream keeps. Consumers are expected
+    /// to keep up well within this; anything older is trimmed.
+    #[serde(default = "default_event_stream_maxlen")]
+    pub event_stream_maxlen: usize,
+    #[serde(default = "default_outbox_relay_interval_ms")]
+    pub outbox_relay_interval_ms: u64,
+    /// Published events are pruned from the outbox after this many days
+    #[serde(default = "default_outbox_retention_days")]
+    pub outbox_retention_days: i64,
+}
+
+fn default_event_stream() -> String {
+    "handshake:events".to_string()
+}
+
+fn default_event_stream_maxlen() -> usize {
+    100_000
+}
+
+fn default_outbox_relay_interval_ms() -> u64 {
+    500
+}
+
+fn default_outbox_retention_days() -> i64 {
+    7
+}
+
+impl Validate for EventSettings {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        if let Some(url) = self.event_bus_url.as_deref().filter(|url| !url.is_empty()) {
+            if let Err(e) = redis::Client::open(url) {
+                problems.push(FieldError::new(
+                    "event_bus_url",
+                    &format!("is not a valid Redis URL: {}", e),
+                ));
+            }
+        }
+        if self.event_stream.is_empty() {
+            problems.push(FieldError::new("event_stream", "must not be empty"));
+        }
+        problems.extend(check_positive(
+            "event_stream_maxlen",
+            self.event_stream_maxlen,
+        ));
+        problems.extend(check_positive(
+            "outbox_relay_interval_ms",
+            self.outbox_relay_interval_ms,
+        ));
+        problems.extend(check_positive(
+            "outbox_retention_days",
+            self.outbox_retention_days,
+        ));
+        problems
+    }
+}
+
+diesel::table! {
+    outbox_events (id) {
+        id -> Int8,
+        #[max_length = 64]
+        event_type -> Varchar,
+        payload -> Jsonb,
+        created_at -> Timestamp,
+        published_at -> Nullable<Timestamp>,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(table_name = outbox_events)]
+pub struct OutboxEvent {
+    pub id: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub published_at: Option<NaiveDateTime>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = outbox_events)]
+pub struct NewOutboxEvent {
+    pub event_type: String,
+    pub payload: serde_json::Value,
+}
+
+/// Queue `event` for the bus. Call it inside the transaction that made the
+/// change, so the event is published if and only if the change commits.
+pub async fn record<E: Serialize>(
+    conn: &mut AsyncPgConnection,
+    event_type: &str,
+    event: &E,
+) -> QueryResult<()> {
+    let payload = serde_json::to_value(event)
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+    diesel::insert_into(outbox_events::table)
+        .values(&NewOutboxEvent {
+            event_type: event_type.to_string(),
+            payload,
+        })
+        .execute(conn)
+        .await
+        .map(|_| ())
+}
@@ -0,0 +1,154 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use redis::aio::MultiplexedConnection;
+use rocket::fairing::AdHoc;
+use rocket::tokio;
+use std::time::Duration;
+
+use super::bus::EventBus;
+use super::{outbox_events, EventSettings, OutboxEvent};
+use crate::db::DbPool;
+
+const BATCH_SIZE: i64 = 100;
+
+/// Publishes outbox events to the bus in the order they were written.
+/// Delivery is at least once: an event published just before a crash is sent
+/// again on restart, and consumers skip it by its event id.
+pub fn relay(source: &'static str, settings: EventSettings) -> AdHoc {
+    AdHoc::on_liftoff("Outbox relay", move |rocket| {
+        Box::pin(async move {
+            let bus = match EventBus::new(&settings, source) {
+                Some(bus) => bus,
+                None => {
+                    tracing::warn!(
+                        "Outbox relay disabled: EVENT_BUS_URL not set, events stay in the outbox"
+                    );
+                    return;
+                }
+            };
+            let pool = match rocket.state::<DbPool>() {
+                Some(pool) => pool.clone(),
+                None => {
+                    tracing::warn!("Outbox relay disabled: database pool unavailable");
+                    return;
+                }
+            };
+            let period = Duration::from_millis(settings.outbox_relay_interval_ms);
+            let retention = ChronoDuration::days(settings.outbox_retention_days);
+
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(period);
+                let mut redis = None;
+                loop {
+                    ticker.tick().await;
+                    let mut conn = match pool.get().await {
+                        Ok(conn) => conn,
+                        Err(e) => {
+                            tracing::warn!(error = %e, "Outbox relay: no database connection");
+                            continue;
+                        }
+                    };
+                    if let Err(e) = relay_pending(&mut conn, &bus, &mut redis, retention).await {
+                        tracing::error!(error = %e, "Outbox relay failed");
+                        // Reconnect on the next tick
+                        redis = None;
+                    }
+                }
+            });
+        })
+    })
+}
+
+async fn relay_pending(
+    conn: &mut AsyncPgConnection,
+    bus: &EventBus,
+    redis: &mut Option<MultiplexedConnection>,
+    retention: ChronoDuration,
+) -> Result<(), String> {
+    // Rows stay locked until they are marked published, so other replicas
+    // running the relay skip them instead of publishing the same batch
+    let failure = conn
+        .transaction(|conn| {
+            async move {
+                let pending: Vec<OutboxEvent> = outbox_events::table
+                    .filter(outbox_events::published_at.is_null())
+                    .order(outbox_events::id.asc())
+                    .limit(BATCH_SIZE)
+                    .for_update()
+                    .skip_locked()
+                    .load(conn)
+                    .await?;
+
+                let [first, ..] = pending.as_slice() else {
+                    return Ok(None);
+                };
+
+                // Another replica is still publishing earlier events; wait for it
+                // rather than overtake them
+                let oldest: Option<i64> = outbox_events::table
+                    .filter(outbox_events::published_at.is_null())
+                    .order(outbox_events::id.asc())
+                    .select(outbox_events::id)
+                    .first(conn)
+                    .await
+                    .optional()?;
+                if oldest != Some(first.id) {
+                    return Ok(None);
+                }
+
+                let redis = match redis {
+                    Some(redis) => redis,
+                    None => match bus.connect().await {
+                        Ok(connection) => redis.insert(connection),
+                        Err(e) => return Ok(Some(e)),
+                    },
+                };
+
+                let mut published = Vec::new();
+                let mut failure = None;
+                for event in &pending {
+                    match bus.publish(redis, event).await {
+                        Ok(()) => published.push(event.id),
+                        Err(e) => {
+                            // Stop here so later events aren't published ahead of this one
+                            failure = Some((event.id, e));
+                            break;
+                        }
+                    }
+                }
+
+                diesel::update(outbox_events::table.filter(outbox_events::id.eq_any(published)))
+                    .set(outbox_events::published_at.eq(Some(Utc::now().naive_utc())))
+                    .execute(conn)
+                    .await?;
+
+                if let Some((id, error)) = &failure {
+                    diesel::update(outbox_events::table.find(*id))
+                        .set((
+                            outbox_events::attempts.eq(outbox_events::attempts + 1),
+                            outbox_events::last_error.eq(Some(error)),
+                        ))
+                        .execute(conn)
+                        .await?;
+                }
+
+                Ok::<_, diesel::result::Error>(failure.map(|(_, e)| e))
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let cutoff = Utc::now().naive_utc() - retention;
+    diesel::delete(outbox_events::table.filter(outbox_events::published_at.lt(cutoff)))
+        .execute(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
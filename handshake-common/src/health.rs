@@ -0,0 +1,240 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+const SERVICE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveResponse {
+    pub status: HealthStatus,
+    pub service: &'static str,
+}
+
+/// Outcome of probing one dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub ok: bool,
+    /// Whether the service is down without it, rather than just degraded
+    pub required: bool,
+    /// False for optional dependencies this environment doesn't use
+    pub configured: bool,
+    pub latency_ms: Option<u128>,
+    pub error: Option<String>,
+}
+
+impl Check {
+    /// Run `probe` and time it
+    pub async fn timed<F>(probe: F) -> Check
+    where
+        F: Future<Output = Result<(), String>>,
+    {
+        let start = Instant::now();
+        let result = probe.await;
+        let latency_ms = Some(start.elapsed().as_millis());
+
+        match result {
+            Ok(()) => Check {
+                ok: true,
+                required: true,
+                configured: true,
+                latency_ms,
+                error: None,
+            },
+            Err(e) => Check {
+                ok: false,
+                required: true,
+                configured: true,
+                latency_ms,
+                error: Some(e),
+            },
+        }
+    }
+
+    pub fn not_configured(reason: &str) -> Check {
+        Check {
+            ok: false,
+            required: true,
+            configured: false,
+            latency_ms: None,
+            error: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Body of `/ready`: the overall status plus one entry per dependency,
+/// e.g. `{"status": "ok", "service": "order-service", "db": {...}}`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadyResponse {
+    pub status: HealthStatus,
+    pub service: &'static str,
+    #[serde(flatten)]
+    pub checks: BTreeMap<&'static str, Check>,
+}
+
+/// Collects dependency checks into a readiness response. A failing required
+/// check takes the service down (503); a failing optional one only degrades
+/// it, and an optional one that isn't configured is ignored.
+pub struct Readiness {
+    service: &'static str,
+    checks: BTreeMap<&'static str, Check>,
+}
+
+impl Readiness {
+    pub fn new(service: &'static str) -> Self {
+        Readiness {
+            service,
+            checks: BTreeMap::new(),
+        }
+    }
+
+    pub fn require(mut self, name: &'static str, check: Check) -> Self {
+        self.checks.insert(
+            name,
+            Check {
+                required: true,
+                ..check
+            },
+        );
+        self
+    }
+
+    pub fn optional(mut self, name: &'static str, check: Check) -> Self {
+        self.checks.insert(
+            name,
+            Check {
+                required: false,
+                ..check
+            },
+        );
+        self
+    }
+
+    pub fn status(&self) -> HealthStatus {
+        let failing = self.checks.values().filter(|check| !check.ok);
+        let mut status = HealthStatus::Ok;
+        for check in failing {
+            if check.required {
+                return HealthStatus::Down;
+            }
+            if check.configured {
+                status = HealthStatus::Degraded;
+            }
+        }
+        status
+    }
+
+    pub fn respond(self) -> (Status, Json<ReadyResponse>) {
+        let status = self.status();
+        let http_status = match status {
+            // Still serving traffic, just without some extras
+            HealthStatus::Ok | HealthStatus::Degraded => Status::Ok,
+            HealthStatus::Down => Status::ServiceUnavailable,
+        };
+
+        (
+            http_status,
+            Json(ReadyResponse {
+                status,
+                service: self.service,
+                checks: self.checks,
+            }),
+        )
+    }
+}
+
+/// Liveness probe body - the process is up and serving requests
+pub fn live(service: &'static str) -> Json<LiveResponse> {
+    Json(LiveResponse {
+        status: HealthStatus::Ok,
+        service,
+    })
+}
+
+/// Probe the `/live` endpoint of another Handshake service at `base_url`
+pub async fn check_service(base_url: &str) -> Check {
+    Check::timed(async move {
+        let client = reqwest::Client::builder()
+            .timeout(SERVICE_CHECK_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let response = client
+            .get(format!("{}/live", base_url))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to connect: {}", e))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Returned status: {}", response.status()))
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passing() -> Check {
+        Check {
+            ok: true,
+            required: true,
+            configured: true,
+            latency_ms: Some(1),
+            error: None,
+        }
+    }
+
+    fn failing() -> Check {
+        Check {
+            ok: false,
+            error: Some("connection refused".to_string()),
+            ..passing()
+        }
+    }
+
+    #[test]
+    fn required_failures_take_the_service_down() {
+        let readiness = Readiness::new("test")
+            .require("db", failing())
+            .optional("email_service", passing());
+        assert_eq!(readiness.status(), HealthStatus::Down);
+        assert_eq!(readiness.respond().0, Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn optional_failures_only_degrade_it() {
+        let readiness = Readiness::new("test")
+            .require("db", passing())
+            .optional("email_service", failing());
+        assert_eq!(readiness.status(), HealthStatus::Degraded);
+
+        let unconfigured = Readiness::new("test")
+            .require("db", passing())
+            .optional("email_service", Check::not_configured("not set"));
+        assert_eq!(unconfigured.status(), HealthStatus::Ok);
+    }
+
+    #[test]
+    fn checks_sit_beside_the_status() {
+        let (_, Json(body)) = Readiness::new("order-service")
+            .require("db", passing())
+            .respond();
+        let json = serde_json::to_value(body).unwrap();
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["db"]["ok"], true);
+    }
+}
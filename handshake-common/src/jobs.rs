@@ -0,0 +1,28 @@
+use rocket::tokio;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::db::{DbConn, DbPool};
+
+/// Spawn a background task that runs `job` every `period` with a pooled
+/// connection. Failures are logged and the job runs again on the next tick.
+pub fn spawn_periodic<F, Fut>(name: &'static str, pool: DbPool, period: Duration, job: F)
+where
+    F: Fn(DbConn) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), String>> + Send,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        loop {
+            ticker.tick().await;
+            match pool.get().await {
+                Ok(conn) => {
+                    if let Err(e) = job(conn).await {
+                        tracing::error!(job = name, error = %e, "Job failed");
+                    }
+                }
+                Err(e) => tracing::warn!(job = name, error = %e, "No database connection"),
+            }
+        }
+    });
+}
@@ -0,0 +1,20 @@
+//! Plumbing shared by the Handshake services: request guards for JWT and
+//! service-to-service auth, JSON errors, request ids, tracing, metrics,
+//! health probes, environment config, migrations, periodic jobs, the event
+//! outbox and its consumers, cursor pagination and location fuzzing.
+
+pub mod auth;
+pub mod config;
+#[cfg(feature = "db")]
+pub mod db;
+pub mod error;
+#[cfg(feature = "events")]
+pub mod events;
+pub mod health;
+#[cfg(feature = "db")]
+pub mod jobs;
+pub mod metrics;
+pub mod pagination;
+pub mod privacy;
+pub mod request_id;
+pub mod telemetry;
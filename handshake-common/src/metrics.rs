@@ -0,0 +1,264 @@
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge_vec,
+    Encoder, HistogramVec, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::ContentType;
+use rocket::request::Request;
+use rocket::{get, routes, Data, Response, Route};
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::Instant;
+
+pub use prometheus::IntCounter;
+
+static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "http_requests_total",
+        "Requests handled, by route and response status",
+        &["method", "route", "status"]
+    )
+    .expect("http_requests_total registers once")
+});
+
+static HTTP_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "http_request_duration_seconds",
+        "Time from receiving a request to sending its response",
+        &["method", "route"]
+    )
+    .expect("http_request_duration_seconds registers once")
+});
+
+static OUTBOUND_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "outbound_requests_total",
+        "Calls to other services and third-party APIs, by outcome",
+        &["target", "outcome"]
+    )
+    .expect("outbound_requests_total registers once")
+});
+
+static OUTBOUND_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "outbound_request_duration_seconds",
+        "Time spent waiting on other services and third-party APIs",
+        &["target"]
+    )
+    .expect("outbound_request_duration_seconds registers once")
+});
+
+static DB_POOL_SIZE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "db_pool_max_connections",
+        "Connections the database pool may open",
+        &["pool"]
+    )
+    .expect("db_pool_max_connections registers once")
+});
+
+static DB_POOL_CONNECTIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "db_pool_connections",
+        "Open database connections, by whether they are idle or in use",
+        &["pool", "state"]
+    )
+    .expect("db_pool_connections registers once")
+});
+
+static DB_POOL_CHECKOUT: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "db_pool_checkout_seconds",
+        "Time to check a connection out of the database pool",
+        &["pool"]
+    )
+    .expect("db_pool_checkout_seconds registers once")
+});
+
+static DB_POOL_TIMEOUTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "db_pool_checkout_failures_total",
+        "Checkouts that timed out or couldn't connect",
+        &["pool"]
+    )
+    .expect("db_pool_checkout_failures_total registers once")
+});
+
+/// A counter in the same registry as the request metrics, for services to
+/// count business events such as registrations or orders
+pub fn counter(name: &str, help: &str) -> IntCounter {
+    register_int_counter!(name, help).expect("business counters register once")
+}
+
+/// Everything registered so far, in the Prometheus text format
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .expect("text encoding never fails");
+    String::from_utf8(buffer).expect("text encoding is UTF-8")
+}
+
+#[get("/metrics")]
+fn metrics() -> (ContentType, String) {
+    (ContentType::Plain, render())
+}
+
+/// `GET /metrics`, for Prometheus to scrape
+pub fn routes() -> Vec<Route> {
+    routes![metrics]
+}
+
+/// Time `call` to `target` (e.g. `"nominatim"`) and count whether it failed
+pub async fn observe_outbound<F, T, E>(target: &'static str, call: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let result = call.await;
+    OUTBOUND_REQUEST_DURATION
+        .with_label_values(&[target])
+        .observe(started.elapsed().as_secs_f64());
+    let outcome = if result.is_ok() { "success" } else { "error" };
+    OUTBOUND_REQUESTS
+        .with_label_values(&[target, outcome])
+        .inc();
+    result
+}
+
+struct RequestStarted(Instant);
+
+/// Label for the route that handled `request`; requests that matched
+/// nothing share one label so stray paths can't blow up the series count
+fn route_label(request: &Request<'_>) -> String {
+    request
+        .route()
+        .map(|route| route.uri.to_string())
+        .unwrap_or_else(|| "unmatched".to_string())
+}
+
+/// Counts and times every request by method, matched route and status
+pub struct RequestMetrics;
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStarted(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestStarted(started) = request.local_cache(|| RequestStarted(Instant::now()));
+        let method = request.method().as_str();
+        let route = route_label(request);
+        let status = response.status().code.to_string();
+
+        HTTP_REQUESTS
+            .with_label_values(&[method, &route, &status])
+            .inc();
+        HTTP_REQUEST_DURATION
+            .with_label_values(&[method, &route])
+            .observe(started.elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(feature = "db")]
+pub use pool::pool_metrics;
+#[cfg(feature = "db")]
+pub(crate) use pool::record_checkout;
+
+#[cfg(feature = "db")]
+mod pool {
+    use rocket::fairing::AdHoc;
+    use rocket::tokio;
+    use std::time::Duration;
+
+    use super::{DB_POOL_CHECKOUT, DB_POOL_CONNECTIONS, DB_POOL_SIZE, DB_POOL_TIMEOUTS};
+    use crate::db::DbPool;
+
+    const POOL_SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+    /// Time one checkout from `pool`, counting it as a failure if it timed
+    /// out or couldn't connect
+    pub(crate) fn record_checkout(pool: &str, waited: Duration, ok: bool) {
+        DB_POOL_CHECKOUT
+            .with_label_values(&[pool])
+            .observe(waited.as_secs_f64());
+        if !ok {
+            DB_POOL_TIMEOUTS.with_label_values(&[pool]).inc();
+        }
+    }
+
+    /// Export the size of the managed [`DbPool`] and sample how many of its
+    /// connections are idle or in use
+    pub fn pool_metrics() -> AdHoc {
+        AdHoc::on_liftoff("Database pool metrics", |rocket| {
+            Box::pin(async move {
+                let Some(pool) = rocket.state::<DbPool>().cloned() else {
+                    tracing::warn!("Pool metrics disabled: no database pool");
+                    return;
+                };
+                let name = pool.name();
+                DB_POOL_SIZE
+                    .with_label_values(&[name])
+                    .set(i64::from(pool.max_size()));
+                // Show the failure count at zero rather than leaving it absent
+                DB_POOL_TIMEOUTS.with_label_values(&[name]);
+
+                tokio::spawn(async move {
+                    let mut ticker = tokio::time::interval(POOL_SAMPLE_INTERVAL);
+                    loop {
+                        ticker.tick().await;
+                        let state = pool.state();
+                        let in_use = state.connections.saturating_sub(state.idle_connections);
+                        DB_POOL_CONNECTIONS
+                            .with_label_values(&[name, "idle"])
+                            .set(i64::from(state.idle_connections));
+                        DB_POOL_CONNECTIONS
+                            .with_label_values(&[name, "in_use"])
+                            .set(i64::from(in_use));
+                    }
+                });
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::blocking::Client;
+
+    #[test]
+    fn requests_are_counted_by_route() {
+        let rocket = rocket::build().attach(RequestMetrics).mount("/", routes());
+        let client = Client::tracked(rocket).expect("valid rocket");
+
+        client.get("/nowhere/42").dispatch();
+        let response = client.get("/metrics").dispatch();
+        assert_eq!(response.content_type(), Some(ContentType::Plain));
+
+        let body = response.into_string().unwrap();
+        assert!(
+            body.contains(r#"http_requests_total{method="GET",route="unmatched",status="404"}"#)
+        );
+    }
+
+    #[test]
+    fn outbound_failures_are_counted() {
+        let result: Result<(), &str> =
+            rocket::execute(observe_outbound("test-target", async { Err("refused") }));
+        assert!(result.is_err());
+
+        let failures = OUTBOUND_REQUESTS
+            .with_label_values(&["test-target", "error"])
+            .get();
+        assert_eq!(failures, 1);
+    }
+}
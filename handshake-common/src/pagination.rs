@@ -0,0 +1,104 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
+
+/// One page of results, with what a client needs to fetch the next one
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Number of rows matching the query across all pages
+    pub total: i64,
+    pub has_more: bool,
+    /// Pass back as `cursor` to get the following page
+    pub next_cursor: Option<String>,
+}
+
+/// Position just after the last row of a page: that row's sort key, with its
+/// id to break ties between rows sharing the same key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor<K> {
+    pub key: K,
+    pub id: i32,
+}
+
+impl<K: Serialize + DeserializeOwned> Cursor<K> {
+    pub fn new(key: K, id: i32) -> Self {
+        Cursor { key, id }
+    }
+
+    /// Opaque form handed to clients
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(value).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+pub fn clamp_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+impl<T> Page<T> {
+    /// Build a page from rows fetched with `limit + 1`; the extra row only
+    /// tells us whether there is another page and is dropped
+    pub fn from_rows<F>(mut rows: Vec<T>, limit: i64, total: i64, cursor_for: F) -> Self
+    where
+        F: Fn(&T) -> String,
+    {
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit as usize);
+        let next_cursor = if has_more {
+            rows.last().map(cursor_for)
+        } else {
+            None
+        };
+
+        Page {
+            items: rows,
+            total,
+            has_more,
+            next_cursor,
+        }
+    }
+
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            has_more: self.has_more,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip() {
+        let cursor = Cursor::new(12.5_f64, 42);
+        assert_eq!(Cursor::<f64>::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(Cursor::<f64>::decode("not a cursor"), None);
+    }
+
+    #[test]
+    fn extra_row_means_more_pages() {
+        let page = Page::from_rows(vec![1, 2, 3], 2, 10, |n| n.to_string());
+        assert_eq!(page.items, vec![1, 2]);
+        assert!(page.has_more);
+        assert_eq!(page.next_cursor.as_deref(), Some("2"));
+
+        let last = Page::from_rows(vec![1, 2], 2, 2, |n| n.to_string());
+        assert!(!last.has_more);
+        assert_eq!(last.next_cursor, None);
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Deserialize;
+
+use crate::auth::AuthConfig;
+use crate::config::{self, Validate};
+use crate::error::FieldError;
+
+const KM_PER_DEGREE: f64 = 111.32;
+const DEFAULT_GRID_KM: f64 = 1.0;
+const DEFAULT_OFFSET_KM: f64 = 1.0;
+
+/// How public-facing coordinates are obscured
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FuzzMode {
+    /// Coordinates are shown as-is
+    Exact,
+    /// Snap to the centre of a grid cell `cell_km` wide
+    Grid { cell_km: f64 },
+    /// Shift by a stable per-user offset of up to `radius_km`
+    Offset { radius_km: f64 },
+}
+
+/// The `location_fuzz_mode` setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FuzzKind {
+    #[default]
+    Grid,
+    Offset,
+    Exact,
+}
+
+/// How other users see someone's location before an order is accepted.
+/// Flattened into the settings of every service that shows locations, so a
+/// listing's pickup area and its seller's location are obscured the same way.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocationPrivacy {
+    #[serde(default)]
+    pub location_fuzz_mode: FuzzKind,
+    /// Grid cell size or offset radius
+    pub location_fuzz_km: Option<f64>,
+    /// Seeds per-user offsets; the JWT secret is used when unset
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub location_fuzz_secret: Option<String>,
+}
+
+impl LocationPrivacy {
+    pub fn fuzz(&self, auth: &AuthConfig) -> FuzzConfig {
+        let secret = self
+            .location_fuzz_secret
+            .as_deref()
+            .unwrap_or(&auth.jwt_secret);
+        FuzzConfig::new(self.location_fuzz_mode, self.location_fuzz_km, secret)
+    }
+}
+
+impl Validate for LocationPrivacy {
+    fn validate(&self) -> Vec<FieldError> {
+        if self
+            .location_fuzz_km
+            .is_some_and(|km| !km.is_finite() || km <= 0.0)
+        {
+            vec![FieldError::new(
+                "location_fuzz_km",
+                "must be a positive distance",
+            )]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FuzzConfig {
+    pub mode: FuzzMode,
+    pub secret: u64,
+}
+
+impl FuzzConfig {
+    /// `distance_km` is the grid cell size or offset radius, 1 km by default.
+    /// `secret` seeds the per-user offsets.
+    pub fn new(kind: FuzzKind, distance_km: Option<f64>, secret: &str) -> Self {
+        let mode = match kind {
+            FuzzKind::Exact => FuzzMode::Exact,
+            FuzzKind::Offset => FuzzMode::Offset {
+                radius_km: distance_km.unwrap_or(DEFAULT_OFFSET_KM),
+            },
+            FuzzKind::Grid => FuzzMode::Grid {
+                cell_km: distance_km.unwrap_or(DEFAULT_GRID_KM),
+            },
+        };
+        let secret = secret
+            .bytes()
+            .fold(0u64, |acc, b| splitmix64(acc ^ b as u64));
+
+        FuzzConfig { mode, secret }
+    }
+
+    /// Obscure a user's coordinates for display to anyone but themselves
+    pub fn fuzz(&self, user_id: i32, latitude: f64, longitude: f64) -> (f64, f64) {
+        match self.mode {
+            FuzzMode::Exact => (latitude, longitude),
+            FuzzMode::Grid { cell_km } => snap_to_grid(latitude, longitude, cell_km),
+            FuzzMode::Offset { radius_km } => {
+                let seed = splitmix64(self.secret ^ user_id as u64);
+                offset(latitude, longitude, radius_km, seed)
+            }
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.mode == FuzzMode::Exact
+    }
+}
+
+/// SplitMix64 step; a stable hash so a user's offset never changes between
+/// requests (otherwise averaging many responses would reveal the true point)
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn snap_to_grid(latitude: f64, longitude: f64, cell_km: f64) -> (f64, f64) {
+    let lat_step = cell_km / KM_PER_DEGREE;
+    let snapped_lat = ((latitude / lat_step).floor() + 0.5) * lat_step;
+
+    // Size longitude cells by the snapped latitude so the whole cell shares one grid
+    let lon_step = cell_km / (KM_PER_DEGREE * snapped_lat.to_radians().cos().max(0.01));
+    let snapped_lon = ((longitude / lon_step).floor() + 0.5) * lon_step;
+
+    (snapped_lat.clamp(-90.0, 90.0), snapped_lon.clamp(-180.0, 180.0))
+}
+
+fn offset(latitude: f64, longitude: f64, radius_km: f64, seed: u64) -> (f64, f64) {
+    let angle = (seed & 0xffff_ffff) as f64 / u32::MAX as f64 * std::f64::consts::TAU;
+    // Keep at least half the radius so the offset is never negligible
+    let distance = radius_km * (0.5 + 0.5 * (seed >> 32) as f64 / u32::MAX as f64);
+
+    let dlat = distance * angle.sin() / KM_PER_DEGREE;
+    let dlon = distance * angle.cos() / (KM_PER_DEGREE * latitude.to_radians().cos().max(0.01));
+
+    ((latitude + dlat).clamp(-90.0, 90.0), (longitude + dlon).clamp(-180.0, 180.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_snaps_nearby_points_together() {
+        let config = FuzzConfig {
+            mode: FuzzMode::Grid { cell_km: 1.0 },
+            secret: 0,
+        };
+
+        let a = config.fuzz(1, -6.20880, 106.84560);
+        let b = config.fuzz(2, -6.20890, 106.84570);
+        assert_eq!(a, b);
+        assert_ne!(a, (-6.20880, 106.84560));
+    }
+
+    #[test]
+    fn offset_is_stable_and_bounded() {
+        let config = FuzzConfig {
+            mode: FuzzMode::Offset { radius_km: 1.0 },
+            secret: 42,
+        };
+
+        let first = config.fuzz(7, -6.2088, 106.8456);
+        let second = config.fuzz(7, -6.2088, 106.8456);
+        assert_eq!(first, second);
+
+        // Equirectangular distance is accurate enough at this scale
+        let dlat = (first.0 + 6.2088) * KM_PER_DEGREE;
+        let dlon = (first.1 - 106.8456) * KM_PER_DEGREE * (-6.2088f64).to_radians().cos();
+        let distance = (dlat * dlat + dlon * dlon).sqrt();
+        assert!((0.5..=1.01).contains(&distance));
+    }
+}
@@ -0,0 +1,67 @@
+use rand::Rng;
+use rocket::request::{FromRequest, Outcome, Request};
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Identifies one request in logs and error responses. Taken from the
+/// caller's `X-Request-Id` if it sent a usable one, otherwise generated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// The id for `request`, assigned on first use and the same afterwards
+    pub fn of(request: &Request<'_>) -> RequestId {
+        request
+            .local_cache(|| {
+                let incoming = request
+                    .headers()
+                    .get_one(REQUEST_ID_HEADER)
+                    .filter(|id| is_valid(id));
+                RequestId(incoming.map(str::to_string).unwrap_or_else(generate))
+            })
+            .clone()
+    }
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn generate() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestId::of(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_ids_that_could_forge_log_lines() {
+        assert!(is_valid("7f3c2a-checkout.1"));
+        assert!(!is_valid("abc\nERROR fake"));
+        assert!(!is_valid(""));
+        assert!(!is_valid(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+        assert_eq!(generate().len(), 32);
+    }
+}
@@ -0,0 +1,230 @@
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Header, HeaderMap};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{Data, Response};
+use std::collections::HashMap;
+use std::env;
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::request_id::{RequestId, REQUEST_ID_HEADER};
+
+/// Keeps trace export running; flushes buffered spans when dropped at shutdown
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush traces: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global subscriber. Call first thing in `main` and keep the
+/// guard alive until the server stops.
+///
+/// - `RUST_LOG` filters what is logged (default `info`)
+/// - `LOG_FORMAT=json` writes one JSON object per line instead of plain text
+/// - `OTEL_EXPORTER_OTLP_ENDPOINT` (e.g. `http://localhost:4318`) exports
+///   request spans to an OpenTelemetry collector over OTLP/HTTP
+pub fn init(service: &'static str) -> Telemetry {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let json = env::var("LOG_FORMAT").is_ok_and(|format| format == "json");
+    let (json_layer, text_layer) = if json {
+        (Some(tracing_subscriber::fmt::layer().json()), None)
+    } else {
+        (None, Some(tracing_subscriber::fmt::layer()))
+    };
+
+    let provider = env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .filter(|endpoint| !endpoint.is_empty())
+        .and_then(|endpoint| match tracer_provider(service, &endpoint) {
+            Ok(provider) => Some(provider),
+            Err(e) => {
+                eprintln!("Trace export disabled: {}", e);
+                None
+            }
+        });
+    let otel_layer = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(service)));
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let installed = tracing_subscriber::registry()
+        .with(filter)
+        .with(json_layer)
+        .with(text_layer)
+        .with(otel_layer)
+        .try_init();
+    if installed.is_err() {
+        eprintln!("A tracing subscriber was already installed");
+    }
+
+    Telemetry { provider }
+}
+
+fn tracer_provider(
+    service: &'static str,
+    endpoint: &str,
+) -> Result<SdkTracerProvider, opentelemetry::trace::TraceError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service)
+                .with_attribute(KeyValue::new("service.namespace", "handshake"))
+                .build(),
+        )
+        .build();
+    global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+/// Span covering one request, opened when it arrives and closed once the
+/// response has been sent
+struct RequestSpan {
+    span: Span,
+    started: Instant,
+}
+
+fn request_span<'a>(request: &'a Request<'_>) -> &'a RequestSpan {
+    request.local_cache(|| RequestSpan {
+        span: Span::none(),
+        started: Instant::now(),
+    })
+}
+
+/// The span opened by [`RequestTracing`] for `request`
+pub(crate) fn current_span<'a>(request: &'a Request<'_>) -> &'a Span {
+    &request_span(request).span
+}
+
+/// Continue the caller's trace if it sent a W3C `traceparent`
+fn parent_context(headers: &HeaderMap<'_>) -> opentelemetry::Context {
+    let carrier: HashMap<String, String> = ["traceparent", "tracestate"]
+        .into_iter()
+        .filter_map(|name| Some((name.to_string(), headers.get_one(name)?.to_string())))
+        .collect();
+    TraceContextPropagator::new().extract(&carrier)
+}
+
+/// Opens a span per request carrying its request id, logs one line when it
+/// completes, and echoes `X-Request-Id` on the response
+pub struct RequestTracing;
+
+#[rocket::async_trait]
+impl Fairing for RequestTracing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request tracing",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let request_id = RequestId::of(request);
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %request.method(),
+            path = %request.uri().path(),
+            route = Empty,
+            status = Empty,
+        );
+        span.set_parent(parent_context(request.headers()));
+
+        request.local_cache(|| RequestSpan {
+            span,
+            started: Instant::now(),
+        });
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let request_id = RequestId::of(request);
+        response.set_header(Header::new(REQUEST_ID_HEADER, request_id.0));
+
+        let RequestSpan { span, started } = request_span(request);
+        let status = response.status().code;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        if let Some(route) = request.route() {
+            span.record("route", route.uri.to_string());
+        }
+        span.record("status", status);
+
+        if status >= 500 {
+            tracing::error!(parent: span, status, latency_ms, "request failed");
+        } else {
+            tracing::info!(parent: span, status, latency_ms, "request completed");
+        }
+    }
+}
+
+/// The current request's id and span, for handlers that call other services
+/// and want those calls to show up in the same trace and logs
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub request_id: RequestId,
+    pub span: Span,
+}
+
+impl RequestContext {
+    /// Headers to put on an outbound call: `X-Request-Id`, plus
+    /// `traceparent` when traces are being exported
+    pub fn headers(&self) -> Vec<(String, String)> {
+        let mut headers = HashMap::new();
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&self.span.context(), &mut headers)
+        });
+        headers.insert(REQUEST_ID_HEADER.to_string(), self.request_id.0.clone());
+        headers.into_iter().collect()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestContext {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestContext {
+            request_id: RequestId::of(request),
+            span: request_span(request).span.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwards_the_request_id() {
+        let context = RequestContext {
+            request_id: RequestId("abc-123".to_string()),
+            span: Span::none(),
+        };
+        assert!(context
+            .headers()
+            .contains(&(REQUEST_ID_HEADER.to_string(), "abc-123".to_string())));
+    }
+}
@@ -0,0 +1,6 @@
+-- Restore required meetup locations
+ALTER TABLE orders ALTER COLUMN seller_location_id SET NOT NULL;
+ALTER TABLE orders ALTER COLUMN buyer_location_id SET NOT NULL;
+
+-- Drop columns
+ALTER TABLE orders DROP COLUMN IF EXISTS quantity;
@@ -0,0 +1,6 @@
+-- Orders can be for several units of a listing
+ALTER TABLE orders ADD COLUMN quantity INTEGER NOT NULL DEFAULT 1 CHECK (quantity >= 1);
+
+-- Service and digital listings don't need a meetup location
+ALTER TABLE orders ALTER COLUMN buyer_location_id DROP NOT NULL;
+ALTER TABLE orders ALTER COLUMN seller_location_id DROP NOT NULL;
@@ -0,0 +1,2 @@
+-- Remember which revision of the listing the buyer ordered
+ALTER TABLE orders ADD COLUMN product_revision INTEGER;
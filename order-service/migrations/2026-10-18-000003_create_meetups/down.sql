@@ -0,0 +1,11 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_orders_meetup_starts_at;
+
+-- Drop columns
+ALTER TABLE orders DROP COLUMN IF EXISTS meetup_reminded_at;
+ALTER TABLE orders DROP COLUMN IF EXISTS meetup_ends_at;
+ALTER TABLE orders DROP COLUMN IF EXISTS meetup_starts_at;
+
+-- Drop tables
+DROP TABLE IF EXISTS meetup_proposals;
+DROP TABLE IF EXISTS availability_windows;
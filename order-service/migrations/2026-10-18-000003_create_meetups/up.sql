@@ -0,0 +1,33 @@
+-- Create availability windows table
+CREATE TABLE availability_windows (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (ends_at > starts_at)
+);
+
+-- Create meetup proposals table
+CREATE TABLE meetup_proposals (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    proposed_by INTEGER NOT NULL,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'declined', 'superseded')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    responded_at TIMESTAMP,
+    CHECK (ends_at > starts_at)
+);
+
+-- The confirmed meetup time lives on the order
+ALTER TABLE orders ADD COLUMN meetup_starts_at TIMESTAMP;
+ALTER TABLE orders ADD COLUMN meetup_ends_at TIMESTAMP;
+ALTER TABLE orders ADD COLUMN meetup_reminded_at TIMESTAMP;
+
+-- Create indexes
+CREATE INDEX idx_availability_windows_user ON availability_windows(user_id, ends_at);
+CREATE INDEX idx_meetup_proposals_order ON meetup_proposals(order_id);
+CREATE INDEX idx_orders_meetup_starts_at ON orders(meetup_starts_at);
@@ -0,0 +1,7 @@
+-- Drop columns
+ALTER TABLE orders DROP COLUMN IF EXISTS handoff_longitude;
+ALTER TABLE orders DROP COLUMN IF EXISTS handoff_latitude;
+ALTER TABLE orders DROP COLUMN IF EXISTS completed_at;
+
+-- Drop tables
+DROP TABLE IF EXISTS handoff_codes;
@@ -0,0 +1,21 @@
+-- Create handoff codes table
+CREATE TABLE handoff_codes (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    code VARCHAR(8) NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    used_at TIMESTAMP,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Where and when the item actually changed hands
+ALTER TABLE orders ADD COLUMN completed_at TIMESTAMP;
+ALTER TABLE orders ADD COLUMN handoff_latitude DOUBLE PRECISION;
+ALTER TABLE orders ADD COLUMN handoff_longitude DOUBLE PRECISION;
+
+-- Create indexes
+-- At most one code per order can be redeemed at any time
+CREATE UNIQUE INDEX idx_handoff_codes_live ON handoff_codes(order_id)
+    WHERE used_at IS NULL AND revoked_at IS NULL;
@@ -0,0 +1,3 @@
+-- Drop tables
+DROP TABLE IF EXISTS payment_webhooks;
+DROP TABLE IF EXISTS payments;
@@ -0,0 +1,35 @@
+-- Create payments table
+CREATE TABLE payments (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL UNIQUE REFERENCES orders(id) ON DELETE CASCADE,
+    provider VARCHAR(20) NOT NULL,
+    -- Our reference for the current attempt, sent to the provider
+    reference VARCHAR(64) UNIQUE,
+    provider_transaction_id VARCHAR(128),
+    payment_method VARCHAR(255),
+    amount BIGINT NOT NULL CHECK (amount >= 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'authorized', 'captured', 'voided', 'refunded', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    failure_reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create payment webhooks table
+CREATE TABLE payment_webhooks (
+    id SERIAL PRIMARY KEY,
+    provider VARCHAR(20) NOT NULL,
+    event_id VARCHAR(128) NOT NULL,
+    payment_id INTEGER REFERENCES payments(id) ON DELETE SET NULL,
+    event_type VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    received_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    processed_at TIMESTAMP,
+    UNIQUE (provider, event_id)
+);
+
+-- Create indexes
+CREATE INDEX idx_payments_status ON payments(status);
+CREATE INDEX idx_payment_webhooks_payment ON payment_webhooks(payment_id);
@@ -0,0 +1,11 @@
+-- Drop tables
+DROP TABLE IF EXISTS reputation_events;
+DROP TABLE IF EXISTS dispute_evidence;
+DROP TABLE IF EXISTS dispute_messages;
+DROP TABLE IF EXISTS disputes;
+
+-- Drop columns
+ALTER TABLE orders DROP COLUMN IF EXISTS cancellation_note;
+ALTER TABLE orders DROP COLUMN IF EXISTS cancellation_reason;
+ALTER TABLE orders DROP COLUMN IF EXISTS cancelled_by;
+ALTER TABLE orders DROP COLUMN IF EXISTS cancelled_at;
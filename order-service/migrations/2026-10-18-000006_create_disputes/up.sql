@@ -0,0 +1,58 @@
+-- Why and by whom an order was cancelled
+ALTER TABLE orders ADD COLUMN cancelled_at TIMESTAMP;
+ALTER TABLE orders ADD COLUMN cancelled_by INTEGER;
+ALTER TABLE orders ADD COLUMN cancellation_reason VARCHAR(40);
+ALTER TABLE orders ADD COLUMN cancellation_note TEXT;
+
+-- Create disputes table
+CREATE TABLE disputes (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL UNIQUE REFERENCES orders(id) ON DELETE CASCADE,
+    raised_by INTEGER NOT NULL,
+    reason VARCHAR(40) NOT NULL,
+    description TEXT NOT NULL,
+    -- Order status when the dispute was raised
+    order_status VARCHAR(50) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'resolved')),
+    outcome VARCHAR(20) CHECK (outcome IN ('buyer_favored', 'seller_favored', 'no_fault')),
+    resolution_note TEXT,
+    resolved_by INTEGER,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP
+);
+
+-- Create dispute messages table
+CREATE TABLE dispute_messages (
+    id SERIAL PRIMARY KEY,
+    dispute_id INTEGER NOT NULL REFERENCES disputes(id) ON DELETE CASCADE,
+    author_id INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create dispute evidence table
+CREATE TABLE dispute_evidence (
+    id SERIAL PRIMARY KEY,
+    dispute_id INTEGER NOT NULL REFERENCES disputes(id) ON DELETE CASCADE,
+    uploaded_by INTEGER NOT NULL,
+    url VARCHAR(500) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create reputation events table
+CREATE TABLE reputation_events (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    order_id INTEGER REFERENCES orders(id) ON DELETE SET NULL,
+    kind VARCHAR(30) NOT NULL,
+    points INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (user_id, order_id, kind)
+);
+
+-- Create indexes
+CREATE INDEX idx_disputes_status ON disputes(status, created_at);
+CREATE INDEX idx_dispute_messages_dispute ON dispute_messages(dispute_id, created_at);
+CREATE INDEX idx_dispute_evidence_dispute ON dispute_evidence(dispute_id);
+CREATE INDEX idx_reputation_events_user ON reputation_events(user_id);
@@ -0,0 +1,3 @@
+-- Drop tables
+DROP TABLE IF EXISTS processed_events;
+DROP TABLE IF EXISTS outbox_events;
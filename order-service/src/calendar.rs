@@ -0,0 +1,118 @@
+use chrono::NaiveDateTime;
+
+/// A single event for an iCalendar (`.ics`) file. Times are UTC.
+pub struct CalendarEvent {
+    pub uid: String,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub summary: String,
+    pub description: String,
+    pub location: String,
+    pub geo: Option<(f64, f64)>,
+}
+
+fn format_time(time: &NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value (RFC 5545 section 3.3.11)
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line so no line exceeds 75 octets (RFC 5545 section 3.1)
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut width = 0;
+    for ch in line.chars() {
+        let len = ch.len_utf8();
+        if width + len > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(ch);
+        width += len;
+    }
+    folded
+}
+
+impl CalendarEvent {
+    pub fn to_ics(&self, stamp: NaiveDateTime) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//Handshake Marketplace//Meetups//EN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", self.uid),
+            format!("DTSTAMP:{}", format_time(&stamp)),
+            format!("DTSTART:{}", format_time(&self.starts_at)),
+            format!("DTEND:{}", format_time(&self.ends_at)),
+            format!("SUMMARY:{}", escape_text(&self.summary)),
+            format!("DESCRIPTION:{}", escape_text(&self.description)),
+            format!("LOCATION:{}", escape_text(&self.location)),
+        ];
+        if let Some((lat, lon)) = self.geo {
+            lines.push(format!("GEO:{:.6};{:.6}", lat, lon));
+        }
+        lines.push("END:VEVENT".to_string());
+        lines.push("END:VCALENDAR".to_string());
+
+        let mut ics: String = lines
+            .iter()
+            .map(|line| fold_line(line))
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        ics.push_str("\r\n");
+        ics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 20)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn renders_a_utc_event() {
+        let event = CalendarEvent {
+            uid: "order-7@handshake".to_string(),
+            starts_at: at(14),
+            ends_at: at(15),
+            summary: "Meetup: Bike, blue".to_string(),
+            description: "Order #7".to_string(),
+            location: "Near Jl. Sudirman; Jakarta".to_string(),
+            geo: Some((-6.2, 106.85)),
+        };
+        let ics = event.to_ics(at(9));
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("DTSTART:20261020T140000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Meetup: Bike\\, blue\r\n"));
+        assert!(ics.contains("LOCATION:Near Jl. Sudirman\\; Jakarta\r\n"));
+        assert!(ics.contains("GEO:-6.200000;106.850000\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let folded = fold_line(&format!("DESCRIPTION:{}", "x".repeat(100)));
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(
+            folded.replace("\r\n ", ""),
+            format!("DESCRIPTION:{}", "x".repeat(100))
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::settings::Settings;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProductInfo {
+    pub id: i32,
+    pub seller_id: i32,
+    pub title: String,
+    pub price: f64,
+    pub status: String,
+    pub quantity: i32,
+    pub listing_type: String,
+    pub requires_meetup: bool,
+    pub revision: i32,
+}
+
+/// A recorded revision of a listing, as served by product-service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListingRevision {
+    pub product_id: i32,
+    pub revision: i32,
+    pub snapshot: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProductSummary {
+    pub id: i32,
+    pub title: String,
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SummariesRequest<'a> {
+    ids: &'a [i32],
+}
+
+#[derive(Debug, Serialize)]
+struct StockRequest {
+    quantity: i32,
+}
+
+#[derive(Debug)]
+pub enum CatalogError {
+    NotFound,
+    /// The listing is unavailable or has too few units left
+    Unavailable,
+    Unreachable(String),
+}
+
+pub async fn fetch_product(
+    settings: &Settings,
+    product_id: i32,
+) -> Result<ProductInfo, CatalogError> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!(
+            "{}/products/{}",
+            settings.product_service_url, product_id
+        ))
+        .send()
+        .await
+        .map_err(|e| CatalogError::Unreachable(format!("Request failed: {}", e)))?;
+
+    match response.status() {
+        reqwest::StatusCode::NOT_FOUND => Err(CatalogError::NotFound),
+        status if !status.is_success() => Err(CatalogError::Unreachable(format!(
+            "Product service returned error ({})",
+            status
+        ))),
+        _ => response
+            .json()
+            .await
+            .map_err(|e| CatalogError::Unreachable(format!("Failed to parse response: {}", e))),
+    }
+}
+
+/// Titles and images for a batch of listings in one request, keyed by id
+pub async fn fetch_summaries(
+    settings: &Settings,
+    ids: &[i32],
+) -> Result<HashMap<i32, ProductSummary>, CatalogError> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!(
+            "{}/products/summaries",
+            settings.product_service_url
+        ))
+        .header("X-Internal-Token", settings.internal_token())
+        .json(&SummariesRequest { ids })
+        .send()
+        .await
+        .map_err(|e| CatalogError::Unreachable(format!("Request failed: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(CatalogError::Unreachable(format!(
+            "Product service returned error ({})",
+            response.status()
+        )));
+    }
+
+    let summaries: Vec<ProductSummary> = response
+        .json()
+        .await
+        .map_err(|e| CatalogError::Unreachable(format!("Failed to parse response: {}", e)))?;
+
+    Ok(summaries.into_iter().map(|s| (s.id, s)).collect())
+}
+
+/// The listing as it was at `revision`, or at `at` when the revision is unknown
+pub async fn fetch_revision(
+    settings: &Settings,
+    product_id: i32,
+    revision: Option<i32>,
+    at: NaiveDateTime,
+) -> Result<ListingRevision, CatalogError> {
+    let query = match revision {
+        Some(revision) => format!("revision={}", revision),
+        None => format!("at={}", at.format("%Y-%m-%dT%H:%M:%S%.f")),
+    };
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!(
+            "{}/products/{}/snapshot?{}",
+            settings.product_service_url, product_id, query
+        ))
+        .header("X-Internal-Token", settings.internal_token())
+        .send()
+        .await
+        .map_err(|e| CatalogError::Unreachable(format!("Request failed: {}", e)))?;
+
+    match response.status() {
+        reqwest::StatusCode::NOT_FOUND => Err(CatalogError::NotFound),
+        status if !status.is_success() => Err(CatalogError::Unreachable(format!(
+            "Product service returned error ({})",
+            status
+        ))),
+        _ => response
+            .json()
+            .await
+            .map_err(|e| CatalogError::Unreachable(format!("Failed to parse response: {}", e))),
+    }
+}
+
+async fn post_stock(
+    settings: &Settings,
+    product_id: i32,
+    action: &str,
+    quantity: i32,
+) -> Result<(), CatalogError> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!(
+            "{}/products/{}/stock/{}",
+            settings.product_service_url, product_id, action
+        ))
+        .header("X-Internal-Token", settings.internal_token())
+        .json(&StockRequest { quantity })
+        .send()
+        .await
+        .map_err(|e| CatalogError::Unreachable(format!("Request failed: {}", e)))?;
+
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        reqwest::StatusCode::NOT_FOUND => Err(CatalogError::NotFound),
+        reqwest::StatusCode::CONFLICT => Err(CatalogError::Unavailable),
+        status => Err(CatalogError::Unreachable(format!(
+            "Product service returned error ({})",
+            status
+        ))),
+    }
+}
+
+/// Take units of a listing out of stock for a new order
+pub async fn reserve_stock(
+    settings: &Settings,
+    product_id: i32,
+    quantity: i32,
+) -> Result<(), CatalogError> {
+    post_stock(settings, product_id, "reserve", quantity).await
+}
+
+/// Return units of a listing to stock, e.g. when an order falls through
+pub async fn release_stock(
+    settings: &Settings,
+    product_id: i32,
+    quantity: i32,
+) -> Result<(), CatalogError> {
+    post_stock(settings, product_id, "release", quantity).await
+}
@@ -1,4 +1,8 @@
-use rocket_sync_db_pools::{database, diesel};
+use handshake_common::db::{DatabaseConfig, DbPool};
 
-#[database("order_db")]
-pub struct DbConn(diesel::PgConnection);
+pub use handshake_common::db::DbConn;
+
+/// The service's connection pool, managed as Rocket state for [`DbConn`]
+pub fn pool(config: &DatabaseConfig) -> DbPool {
+    DbPool::new("order_db", config)
+}
@@ -0,0 +1,391 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{get, post, State};
+use serde::{Deserialize, Serialize};
+
+use handshake_common::error::ApiError;
+use handshake_common::auth::{AdminUser, AuthenticatedUser};
+use crate::catalog;
+use crate::db::DbConn;
+use crate::events;
+use crate::models::{
+    Dispute, DisputeEvidence, DisputeMessage, NewDispute, NewDisputeEvidence, NewDisputeMessage,
+    Order,
+};
+use crate::payments::{self, Payments};
+use crate::reasons::{clean_note, DisputeOutcome, DisputeReason};
+use crate::reputation::{self, ReputationKind};
+use crate::routes::not_a_party;
+use crate::schema::{dispute_evidence, dispute_messages, disputes, orders};
+use crate::settings::Settings;
+
+const MAX_EVIDENCE_URL_LENGTH: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct RaiseDisputeRequest {
+    pub reason: DisputeReason,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisputeMessageRequest {
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisputeEvidenceRequest {
+    /// Link to a photo or document, like listing images
+    pub url: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveDisputeRequest {
+    pub outcome: DisputeOutcome,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DisputeDetail {
+    #[serde(flatten)]
+    pub dispute: Dispute,
+    pub messages: Vec<DisputeMessage>,
+    pub evidence: Vec<DisputeEvidence>,
+}
+
+async fn load_detail(
+    conn: &mut AsyncPgConnection,
+    dispute: Dispute,
+) -> QueryResult<DisputeDetail> {
+    let messages = dispute_messages::table
+        .filter(dispute_messages::dispute_id.eq(dispute.id))
+        .order(dispute_messages::created_at.asc())
+        .load(conn)
+        .await?;
+    let evidence = dispute_evidence::table
+        .filter(dispute_evidence::dispute_id.eq(dispute.id))
+        .order(dispute_evidence::created_at.asc())
+        .load(conn)
+        .await?;
+    Ok(DisputeDetail {
+        dispute,
+        messages,
+        evidence,
+    })
+}
+
+fn dispute_closed() -> ApiError {
+    ApiError::conflict("The dispute is already resolved")
+}
+
+/// The order's dispute, if `user_id` is one of its parties
+async fn load_participant_dispute(
+    conn: &mut AsyncPgConnection,
+    order_id: i32,
+    user_id: i32,
+) -> Result<Dispute, ApiError> {
+    let order: Order = orders::table
+        .find(order_id)
+        .first(conn)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Order not found"))?;
+    let dispute: Option<Dispute> = disputes::table
+        .filter(disputes::order_id.eq(order_id))
+        .first(conn)
+        .await
+        .optional()
+        .map_err(ApiError::internal)?;
+
+    if order.buyer_id != user_id && order.seller_id != user_id {
+        return Err(not_a_party());
+    }
+
+    dispute.ok_or_else(|| ApiError::not_found("This order has no dispute"))
+}
+
+/// Either party flags a problem with an accepted or completed order. The
+/// order is frozen (no handoff, no cancellation) until an admin resolves it.
+#[post("/<id>/dispute", data = "<request>")]
+pub async fn raise_dispute(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<RaiseDisputeRequest>,
+) -> Result<Json<DisputeDetail>, ApiError> {
+    let user_id = auth.user_id;
+    let request = request.into_inner();
+    let description = clean_note(Some(&request.description), true)?.unwrap_or_default();
+
+    let order: Order = orders::table
+        .find(id)
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Order not found"))?;
+
+    if order.buyer_id != user_id && order.seller_id != user_id {
+        return Err(not_a_party());
+    }
+
+    db.transaction(|conn| {
+        async move {
+            let before: Order = orders::table.find(id).for_update().first(conn).await?;
+            if !matches!(before.status.as_str(), "accepted" | "completed") {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+
+            let order: Order = diesel::update(orders::table.find(id))
+                .set(orders::status.eq("disputed"))
+                .get_result(conn)
+                .await?;
+            events::record_status_change(conn, &order).await?;
+
+            let dispute: Dispute = diesel::insert_into(disputes::table)
+                .values(&NewDispute {
+                    order_id: id,
+                    raised_by: user_id,
+                    reason: request.reason.as_str().to_string(),
+                    description,
+                    order_status: before.status,
+                })
+                .get_result(conn)
+                .await?;
+
+            load_detail(conn, dispute).await
+        }
+        .scope_boxed()
+    })
+    .await
+    .map(Json)
+    .map_err(|e| match e {
+        // Also hit when the order was already disputed
+        diesel::result::Error::RollbackTransaction => {
+            ApiError::conflict("Only accepted or completed orders can be disputed")
+        }
+        e => ApiError::internal(e),
+    })
+}
+
+#[get("/<id>/dispute")]
+pub async fn get_order_dispute(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<DisputeDetail>, ApiError> {
+    let dispute = load_participant_dispute(&mut db, id, auth.user_id).await?;
+
+    load_detail(&mut db, dispute)
+        .await
+        .map(Json)
+        .map_err(ApiError::internal)
+}
+
+async fn add_message(
+    conn: &mut AsyncPgConnection,
+    dispute: Dispute,
+    author_id: i32,
+    body: &str,
+) -> Result<Json<DisputeMessage>, ApiError> {
+    if dispute.status != "open" {
+        return Err(dispute_closed());
+    }
+    let body = clean_note(Some(body), true)?.unwrap_or_default();
+
+    diesel::insert_into(dispute_messages::table)
+        .values(&NewDisputeMessage {
+            dispute_id: dispute.id,
+            author_id,
+            body,
+        })
+        .get_result(conn)
+        .await
+        .map(Json)
+    .map_err(ApiError::internal)
+}
+
+#[post("/<id>/dispute/messages", data = "<request>")]
+pub async fn post_dispute_message(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<DisputeMessageRequest>,
+) -> Result<Json<DisputeMessage>, ApiError> {
+    let dispute = load_participant_dispute(&mut db, id, auth.user_id).await?;
+    add_message(&mut db, dispute, auth.user_id, &request.body).await
+}
+
+#[post("/<id>/dispute/evidence", data = "<request>")]
+pub async fn add_dispute_evidence(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<DisputeEvidenceRequest>,
+) -> Result<Json<DisputeEvidence>, ApiError> {
+    let user_id = auth.user_id;
+    let dispute = load_participant_dispute(&mut db, id, user_id).await?;
+    if dispute.status != "open" {
+        return Err(dispute_closed());
+    }
+
+    let request = request.into_inner();
+    let url = request.url.trim().to_string();
+    if !(url.starts_with("https://") || url.starts_with("http://"))
+        || url.len() > MAX_EVIDENCE_URL_LENGTH
+    {
+        return Err(ApiError::unprocessable(format!(
+            "url must be an http(s) link of at most {} characters",
+            MAX_EVIDENCE_URL_LENGTH
+        )));
+    }
+    let description = clean_note(request.description.as_deref(), false)?;
+
+    diesel::insert_into(dispute_evidence::table)
+        .values(&NewDisputeEvidence {
+            dispute_id: dispute.id,
+            uploaded_by: user_id,
+            url,
+            description,
+        })
+        .get_result(&mut db)
+        .await
+        .map(Json)
+    .map_err(ApiError::internal)
+}
+
+#[get("/?<status>")]
+pub async fn dispute_queue(
+    mut db: DbConn,
+    _admin: AdminUser,
+    status: Option<String>,
+) -> Result<Json<Vec<Dispute>>, ApiError> {
+    let status = status.unwrap_or_else(|| "open".to_string());
+
+    disputes::table
+        .filter(disputes::status.eq(&status))
+        .order(disputes::created_at.asc())
+        .load(&mut db)
+        .await
+        .map(Json)
+    .map_err(ApiError::internal)
+}
+
+async fn load_dispute(conn: &mut AsyncPgConnection, id: i32) -> Result<Dispute, ApiError> {
+    disputes::table
+        .find(id)
+        .first(conn)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Dispute not found"))
+}
+
+#[get("/<id>")]
+pub async fn get_dispute(
+    mut db: DbConn,
+    _admin: AdminUser,
+    id: i32,
+) -> Result<Json<DisputeDetail>, ApiError> {
+    let dispute = load_dispute(&mut db, id).await?;
+
+    load_detail(&mut db, dispute)
+        .await
+        .map(Json)
+        .map_err(ApiError::internal)
+}
+
+/// Admins can ask either party for more detail in the same thread
+#[post("/<id>/messages", data = "<request>")]
+pub async fn post_admin_message(
+    mut db: DbConn,
+    admin: AdminUser,
+    id: i32,
+    request: Json<DisputeMessageRequest>,
+) -> Result<Json<DisputeMessage>, ApiError> {
+    let dispute = load_dispute(&mut db, id).await?;
+    add_message(&mut db, dispute, admin.user_id, &request.body).await
+}
+
+/// Close a dispute. The party ruled against loses reputation; the buyer's
+/// payment is refunded unless the seller wins over an item already handed
+/// over, and units that never changed hands go back on the listing.
+#[post("/<id>/resolve", data = "<request>")]
+pub async fn resolve_dispute(
+    mut db: DbConn,
+    payment_provider: &State<Payments>,
+    settings: &State<Settings>,
+    admin: AdminUser,
+    id: i32,
+    request: Json<ResolveDisputeRequest>,
+) -> Result<Json<DisputeDetail>, ApiError> {
+    let admin_id = admin.user_id;
+    let outcome = request.outcome;
+    let note = clean_note(request.note.as_deref(), false)?;
+    load_dispute(&mut db, id).await?;
+
+    let (detail, order, payment) = db
+        .transaction(|conn| {
+            async move {
+                let dispute: Dispute =
+                    diesel::update(disputes::table.find(id).filter(disputes::status.eq("open")))
+                        .set((
+                            disputes::status.eq("resolved"),
+                            disputes::outcome.eq(Some(outcome.as_str())),
+                            disputes::resolution_note.eq(note),
+                            disputes::resolved_by.eq(Some(admin_id)),
+                            disputes::resolved_at.eq(Some(Utc::now().naive_utc())),
+                        ))
+                        .get_result(conn)
+                        .await?;
+
+                let order: Order = diesel::update(
+                    orders::table
+                        .find(dispute.order_id)
+                        .filter(orders::status.eq("disputed")),
+                )
+                .set(orders::status.eq("resolved"))
+                .get_result(conn)
+                .await?;
+                events::record_status_change(conn, &order).await?;
+
+                let at_fault = match outcome {
+                    DisputeOutcome::BuyerFavored => Some(order.seller_id),
+                    DisputeOutcome::SellerFavored => Some(order.buyer_id),
+                    DisputeOutcome::NoFault => None,
+                };
+                if let Some(user_id) = at_fault {
+                    reputation::record(conn, user_id, order.id, ReputationKind::DisputeLost)
+                        .await?;
+                }
+
+                let payment = payments::load_for_order(conn, order.id).await?;
+                let detail = load_detail(conn, dispute).await?;
+                Ok::<_, diesel::result::Error>((detail, order, payment))
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(ApiError::when_missing(Status::Conflict, "The dispute is already resolved"))?;
+
+    let status_before = detail.dispute.order_status.clone();
+    let provider = payment_provider.inner().as_ref();
+
+    if let Some(payment) = payment {
+        let result = if outcome.refunds_buyer(&status_before) {
+            payments::release(&mut db, provider, payment, "dispute resolved").await
+        } else {
+            payments::capture(&mut db, provider, payment).await
+        };
+        if let Err(e) = result {
+            tracing::error!(order_id = order.id, error = %e, "Failed to settle payment");
+        }
+    }
+
+    if status_before != "completed" {
+        if let Err(e) = catalog::release_stock(settings, order.product_id, order.quantity).await {
+            tracing::error!(order_id = order.id, error = ?e, "Failed to release stock");
+        }
+    }
+
+    Ok(Json(detail))
+}
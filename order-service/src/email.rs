@@ -0,0 +1,82 @@
+use handshake_common::metrics;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeetupEmailKind {
+    Confirmed,
+    Reminder,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MeetupEmailRequest {
+    pub to_email: String,
+    pub kind: MeetupEmailKind,
+    pub product_title: String,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub location: String,
+    pub maps_url: Option<String>,
+    pub ics: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmailServiceResponse {
+    success: bool,
+    message: String,
+}
+
+pub async fn send_meetup_email(
+    settings: &Settings,
+    request: &MeetupEmailRequest,
+) -> Result<(), String> {
+    post_to_email_service(settings, "send-meetup-email", request).await
+}
+
+async fn post_to_email_service<T: Serialize>(
+    settings: &Settings,
+    path: &str,
+    request: &T,
+) -> Result<(), String> {
+    metrics::observe_outbound("email-service", post_json(settings, path, request)).await
+}
+
+async fn post_json<T: Serialize>(
+    settings: &Settings,
+    path: &str,
+    request: &T,
+) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/{}", settings.email_service_url, path))
+        .json(request)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to email service: {}", e))?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        let error_body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unable to read error response".to_string());
+        return Err(format!(
+            "Email service returned error ({}): {}",
+            status, error_body
+        ));
+    }
+
+    let email_response: EmailServiceResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse email service response: {}", e))?;
+
+    if !email_response.success {
+        return Err(format!("Email service failed: {}", email_response.message));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use rocket::fairing::AdHoc;
+
+use handshake_common::events::consumer::Handler;
+use handshake_common::events::{self, EventSettings};
+
+use super::{claim, record_status_change, DomainEvent, SOURCE};
+use crate::models::Order;
+use crate::payments::PaymentStatus;
+use crate::reasons::CancellationReason;
+use crate::schema::{orders, payments};
+
+/// Reacts to events published by the other services. Each event's effect is
+/// committed together with its id in `processed_events` before the entry is
+/// acknowledged, so redelivered events are applied only once.
+pub fn consumer(settings: EventSettings) -> AdHoc {
+    events::consumer::consumer(SOURCE, settings, |_| Some(Events))
+}
+
+struct Events;
+
+#[rocket::async_trait]
+impl Handler for Events {
+    async fn handle(
+        &self,
+        conn: &mut AsyncPgConnection,
+        event_id: &str,
+        payload: &str,
+    ) -> Result<(), String> {
+        let event: DomainEvent = match serde_json::from_str(payload) {
+            Ok(event) => event,
+            Err(e) => {
+                // Retrying won't make it readable
+                tracing::warn!(event_id = %event_id, error = %e, "Skipping unreadable event");
+                return Ok(());
+            }
+        };
+
+        match event {
+            DomainEvent::ProductStatusChanged {
+                product_id, status, ..
+            } if status == "removed" => conn
+                .transaction(|conn| {
+                    async {
+                        if claim(conn, event_id, "ProductStatusChanged").await? {
+                            cancel_pending_orders(conn, product_id).await?;
+                        }
+                        Ok::<_, diesel::result::Error>(())
+                    }
+                    .scope_boxed()
+                })
+                .await
+                .map_err(|e| format!("Failed to apply event {}: {}", event_id, e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The listing was taken down, so orders the seller never accepted can't go
+/// ahead. Nothing was held on the buyer's card yet, so their payments are
+/// simply voided. Accepted orders are left for the parties to settle.
+async fn cancel_pending_orders(conn: &mut AsyncPgConnection, product_id: i32) -> QueryResult<()> {
+    let cancelled: Vec<Order> = diesel::update(
+        orders::table
+            .filter(orders::product_id.eq(product_id))
+            .filter(orders::status.eq("pending")),
+    )
+    .set((
+        orders::status.eq("cancelled"),
+        orders::cancelled_at.eq(Some(Utc::now().naive_utc())),
+        orders::cancellation_reason.eq(Some(CancellationReason::ItemUnavailable.as_str())),
+    ))
+    .get_results(conn)
+    .await?;
+
+    let order_ids: Vec<i32> = cancelled.iter().map(|order| order.id).collect();
+    diesel::update(
+        payments::table
+            .filter(payments::order_id.eq_any(&order_ids))
+            .filter(payments::status.eq_any([
+                PaymentStatus::Pending.as_str(),
+                PaymentStatus::Failed.as_str(),
+            ])),
+    )
+    .set((
+        payments::status.eq(PaymentStatus::Voided.as_str()),
+        payments::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .execute(conn)
+    .await?;
+
+    for order in &cancelled {
+        record_status_change(conn, order).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+pub mod consumer;
+
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use handshake_common::events as outbox;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{NewProcessedEvent, Order};
+use crate::schema::processed_events;
+
+/// Name this service publishes under, and its consumer group on the bus
+pub const SOURCE: &str = "order-service";
+
+/// Something that happened in one of the services that the others may react
+/// to. Sent over the bus as `{"type": "OrderPlaced", "order_id": 1, ...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DomainEvent {
+    OrderPlaced {
+        order_id: i32,
+        product_id: i32,
+        buyer_id: i32,
+        seller_id: i32,
+        quantity: i32,
+    },
+    OrderStatusChanged {
+        order_id: i32,
+        product_id: i32,
+        buyer_id: i32,
+        seller_id: i32,
+        quantity: i32,
+        status: String,
+    },
+    /// Published by product-service whenever a listing moves to a new status
+    ProductStatusChanged {
+        product_id: i32,
+        seller_id: i32,
+        previous_status: String,
+        status: String,
+    },
+    /// Anything published by another service that this one doesn't react to
+    #[serde(other)]
+    Other,
+}
+
+impl DomainEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            DomainEvent::OrderPlaced { .. } => "OrderPlaced",
+            DomainEvent::OrderStatusChanged { .. } => "OrderStatusChanged",
+            DomainEvent::ProductStatusChanged { .. } => "ProductStatusChanged",
+            DomainEvent::Other => "Other",
+        }
+    }
+
+    pub fn order_placed(order: &Order) -> Self {
+        DomainEvent::OrderPlaced {
+            order_id: order.id,
+            product_id: order.product_id,
+            buyer_id: order.buyer_id,
+            seller_id: order.seller_id,
+            quantity: order.quantity,
+        }
+    }
+
+    pub fn order_status_changed(order: &Order) -> Self {
+        DomainEvent::OrderStatusChanged {
+            order_id: order.id,
+            product_id: order.product_id,
+            buyer_id: order.buyer_id,
+            seller_id: order.seller_id,
+            quantity: order.quantity,
+            status: order.status.clone(),
+        }
+    }
+}
+
+/// Queue `event` for the bus. Call it inside the transaction that made the
+/// change, so the event is published if and only if the change commits.
+pub async fn record(conn: &mut AsyncPgConnection, event: &DomainEvent) -> QueryResult<()> {
+    outbox::record(conn, event.event_type(), event).await
+}
+
+/// Record an `OrderStatusChanged` for the order's current status
+pub async fn record_status_change(
+    conn: &mut AsyncPgConnection,
+    order: &Order,
+) -> QueryResult<()> {
+    record(conn, &DomainEvent::order_status_changed(order)).await
+}
+
+/// Mark an incoming event as handled. `false` if it already was, in which
+/// case the caller should skip it; run in the same transaction as its effect.
+pub async fn claim(
+    conn: &mut AsyncPgConnection,
+    event_id: &str,
+    event_type: &str,
+) -> QueryResult<bool> {
+    diesel::insert_into(processed_events::table)
+        .values(&NewProcessedEvent {
+            event_id: event_id.to_string(),
+            event_type: event_type.to_string(),
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await
+        .map(|inserted| inserted == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_listing_changes_from_the_catalog() {
+        let event: DomainEvent = serde_json::from_str(
+            r#"{"type": "ProductStatusChanged", "product_id": 4, "seller_id": 2,
+                "previous_status": "active", "status": "removed"}"#,
+        )
+        .unwrap();
+        assert_eq!(event.event_type(), "ProductStatusChanged");
+    }
+
+    #[test]
+    fn ignores_events_it_does_not_consume() {
+        let event: DomainEvent =
+            serde_json::from_str(r#"{"type": "ProductCreated", "product_id": 4}"#).unwrap();
+        assert_eq!(event, DomainEvent::Other);
+    }
+}
@@ -78,6 +78,6 @@ mod tests {
     #[test]
     fn test_haversine_distance() {
         let distance = haversine_distance(-6.2088, 106.8456, -6.9175, 107.6191);
-        assert!(distance > 140.0 && distance < 160.0);
+        assert!(distance > 110.0 && distance < 125.0);
     }
 }
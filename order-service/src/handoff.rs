@@ -0,0 +1,335 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use rand::Rng;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{post, State};
+use serde::{Deserialize, Serialize};
+
+use handshake_common::error::ApiError;
+use handshake_common::auth::AuthenticatedUser;
+use crate::db::DbConn;
+use crate::events;
+use crate::models::{HandoffCode, NewHandoffCode, Order};
+use crate::payments::{self, Payments};
+use crate::reputation::{self, ReputationKind};
+use crate::routes::{build_order_response, load_order_locations, OrderResponse};
+use crate::schema::{handoff_codes, orders};
+use crate::settings::Settings;
+
+const CODE_LENGTH: usize = 8;
+/// No 0/O or 1/I so codes read out loud or typed from a screen don't get mixed up
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const QR_PREFIX: &str = "handshake:handoff";
+/// Wrong guesses allowed before the code stops working and a new one is needed
+const MAX_FAILED_ATTEMPTS: i32 = 5;
+
+#[derive(Debug, Serialize)]
+pub struct HandoffCodeResponse {
+    pub code: String,
+    /// What the buyer's screen encodes as a QR code for the seller to scan
+    pub qr_payload: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompleteHandoffRequest {
+    /// The code as typed, or the scanned QR payload
+    pub code: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+fn qr_payload(order_id: i32, code: &str) -> String {
+    format!("{}:{}:{}", QR_PREFIX, order_id, code)
+}
+
+/// The code a seller submitted for `order_id`, from either a typed code or a
+/// scanned QR payload. `None` if the payload belongs to another order.
+fn submitted_code(order_id: i32, input: &str) -> Option<String> {
+    let input = input.trim();
+    let code = match input.strip_prefix(QR_PREFIX) {
+        Some(rest) => {
+            let (id, code) = rest.strip_prefix(':')?.split_once(':')?;
+            if id.parse::<i32>().ok()? != order_id {
+                return None;
+            }
+            code
+        }
+        None => input,
+    };
+
+    Some(
+        code.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_uppercase())
+            .collect(),
+    )
+}
+
+fn valid_coordinates(latitude: f64, longitude: f64) -> bool {
+    (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)
+}
+
+/// Buyer gets a fresh one-time code to show the seller at the meetup.
+/// Any earlier code for the order stops working.
+#[post("/<id>/handoff/code")]
+pub async fn issue_handoff_code(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<HandoffCodeResponse>, ApiError> {
+    let user_id = auth.user_id;
+    let now = Utc::now().naive_utc();
+    let expires_at = now + settings.handoff_code_ttl();
+    let code = generate_code();
+
+    let issued: HandoffCode = db
+        .transaction(|conn| {
+            async move {
+                let order: Order = orders::table.find(id).for_update().first(conn).await?;
+                if order.buyer_id != user_id {
+                    return Err(HandoffFailure::Forbidden);
+                }
+                if order.status != "accepted" {
+                    return Err(HandoffFailure::NotAccepted);
+                }
+
+                diesel::update(
+                    handoff_codes::table
+                        .filter(handoff_codes::order_id.eq(id))
+                        .filter(handoff_codes::used_at.is_null())
+                        .filter(handoff_codes::revoked_at.is_null()),
+                )
+                .set(handoff_codes::revoked_at.eq(Some(now)))
+                .execute(conn)
+                .await?;
+
+                let issued = diesel::insert_into(handoff_codes::table)
+                    .values(&NewHandoffCode {
+                        order_id: id,
+                        code,
+                        expires_at,
+                    })
+                    .get_result(conn)
+                    .await?;
+                Ok(issued)
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(HandoffFailure::error)?;
+
+    Ok(Json(HandoffCodeResponse {
+        qr_payload: qr_payload(id, &issued.code),
+        code: issued.code,
+        expires_at: issued.expires_at,
+    }))
+}
+
+/// Why a handoff couldn't go through
+enum HandoffFailure {
+    Forbidden,
+    /// Only accepted orders can be handed over; completed ones can't be again
+    NotAccepted,
+    /// No code has been issued, or it was already used or replaced
+    NoLiveCode,
+    Expired,
+    /// Too many wrong guesses; the buyer has to issue a new code
+    Locked,
+    WrongCode(i32),
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for HandoffFailure {
+    fn from(err: diesel::result::Error) -> Self {
+        HandoffFailure::Database(err)
+    }
+}
+
+impl HandoffFailure {
+    fn error(self) -> ApiError {
+        match self {
+            HandoffFailure::Forbidden => {
+                ApiError::forbidden("The buyer issues handoff codes and the seller redeems them")
+            }
+            HandoffFailure::NotAccepted => {
+                ApiError::conflict("Only accepted orders can be handed over")
+            }
+            HandoffFailure::NoLiveCode => {
+                ApiError::conflict("The buyer hasn't issued a handoff code yet")
+            }
+            HandoffFailure::Expired => ApiError::new(
+                Status::Gone,
+                "Handoff code has expired; the buyer needs to issue a new one",
+            ),
+            HandoffFailure::Locked => ApiError::new(
+                Status::TooManyRequests,
+                "Too many wrong codes; the buyer needs to issue a new one",
+            ),
+            HandoffFailure::WrongCode(_) => wrong_code(),
+            HandoffFailure::Database(e) => ApiError::when_missing(Status::NotFound, "Order not found")(e),
+        }
+    }
+}
+
+fn wrong_code() -> ApiError {
+    ApiError::unprocessable("Handoff code is incorrect").with_code("wrong_handoff_code")
+}
+
+/// Seller redeems the buyer's code at the meetup, which completes the order
+/// and captures the held payment. The code is burned in the same transaction,
+/// so it can't be replayed.
+#[post("/<id>/handoff", data = "<request>")]
+pub async fn complete_handoff(
+    mut db: DbConn,
+    payment_provider: &State<Payments>,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<CompleteHandoffRequest>,
+) -> Result<Json<OrderResponse>, ApiError> {
+    let user_id = auth.user_id;
+    let request = request.into_inner();
+
+    let location = match (request.latitude, request.longitude) {
+        (Some(lat), Some(lon)) if valid_coordinates(lat, lon) => Some((lat, lon)),
+        (None, None) => None,
+        _ => {
+            return Err(ApiError::unprocessable(
+                "latitude and longitude must be a valid pair",
+            ))
+        }
+    };
+    let submitted = submitted_code(id, &request.code);
+    let now = Utc::now().naive_utc();
+
+    let result = db
+        .transaction(|conn| {
+            async move {
+                let order: Order = orders::table.find(id).for_update().first(conn).await?;
+                if order.seller_id != user_id {
+                    return Err(HandoffFailure::Forbidden);
+                }
+                if order.status != "accepted" {
+                    return Err(HandoffFailure::NotAccepted);
+                }
+
+                let live: HandoffCode = handoff_codes::table
+                    .filter(handoff_codes::order_id.eq(id))
+                    .filter(handoff_codes::used_at.is_null())
+                    .filter(handoff_codes::revoked_at.is_null())
+                    .for_update()
+                    .first(conn)
+                    .await
+                    .optional()?
+                    .ok_or(HandoffFailure::NoLiveCode)?;
+
+                if live.expires_at <= now {
+                    return Err(HandoffFailure::Expired);
+                }
+                if live.failed_attempts >= MAX_FAILED_ATTEMPTS {
+                    return Err(HandoffFailure::Locked);
+                }
+                if submitted.as_deref() != Some(live.code.as_str()) {
+                    return Err(HandoffFailure::WrongCode(live.id));
+                }
+
+                diesel::update(handoff_codes::table.find(live.id))
+                    .set(handoff_codes::used_at.eq(Some(now)))
+                    .execute(conn)
+                    .await?;
+
+                let order: Order =
+                    diesel::update(orders::table.find(id).filter(orders::status.eq("accepted")))
+                        .set((
+                            orders::status.eq("completed"),
+                            orders::completed_at.eq(Some(now)),
+                            orders::handoff_latitude.eq(location.map(|(lat, _)| lat)),
+                            orders::handoff_longitude.eq(location.map(|(_, lon)| lon)),
+                        ))
+                        .get_result(conn)
+                        .await?;
+
+                reputation::record(conn, order.buyer_id, id, ReputationKind::OrderCompleted)
+                    .await?;
+                reputation::record(conn, order.seller_id, id, ReputationKind::OrderCompleted)
+                    .await?;
+                events::record_status_change(conn, &order).await?;
+
+                let (buyer, seller) = load_order_locations(conn, &order).await?;
+                let payment = payments::load_for_order(conn, id).await?;
+                Ok((order, buyer, seller, payment))
+            }
+            .scope_boxed()
+        })
+        .await;
+
+    let (order, buyer_location, seller_location, payment) = match result {
+        Ok(completed) => completed,
+        Err(HandoffFailure::WrongCode(code_id)) => {
+            // Counted outside the rolled-back transaction so guesses add up
+            diesel::update(handoff_codes::table.find(code_id))
+                .set(handoff_codes::failed_attempts.eq(handoff_codes::failed_attempts + 1))
+                .execute(&mut db)
+                .await
+                .map_err(ApiError::internal)?;
+            return Err(wrong_code());
+        }
+        Err(failure) => return Err(failure.error()),
+    };
+
+    if let Some(payment) = payment {
+        let provider = payment_provider.inner().as_ref();
+        // The order is complete either way; the capture job retries failures
+        if let Err(e) = payments::capture(&mut db, provider, payment).await {
+            tracing::error!(order_id = order.id, error = %e, "Failed to capture payment");
+        }
+    }
+
+    Ok(Json(build_order_response(
+        order,
+        buyer_location,
+        seller_location,
+        user_id,
+        &settings.fuzz(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_codes_use_the_unambiguous_alphabet() {
+        let code = generate_code();
+        assert_eq!(code.len(), CODE_LENGTH);
+        assert!(code.bytes().all(|b| CODE_ALPHABET.contains(&b)));
+    }
+
+    #[test]
+    fn accepts_typed_codes_loosely() {
+        assert_eq!(
+            submitted_code(7, " abcd-efgh "),
+            Some("ABCDEFGH".to_string())
+        );
+    }
+
+    #[test]
+    fn accepts_qr_payload_for_the_same_order_only() {
+        let payload = qr_payload(7, "ABCDEFGH");
+        assert_eq!(submitted_code(7, &payload), Some("ABCDEFGH".to_string()));
+        assert_eq!(submitted_code(8, &payload), None);
+        assert_eq!(submitted_code(7, "handshake:handoff:seven:ABCDEFGH"), None);
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use rocket::fairing::AdHoc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use handshake_common::db::DbPool;
+use handshake_common::jobs::spawn_periodic;
+
+use crate::email::MeetupEmailKind;
+use crate::meetups::send_meetup_emails;
+use crate::models::{Order, Payment};
+use crate::payments::{self, PaymentStatus, Payments};
+use crate::routes::load_order_locations;
+use crate::schema::{orders, payments as payments_table};
+use crate::settings::Settings;
+
+/// Periodically emails both parties a reminder ahead of a confirmed meetup
+pub fn meetup_reminders() -> AdHoc {
+    AdHoc::on_liftoff("Meetup reminders job", |rocket| {
+        Box::pin(async move {
+            let pool = match rocket.state::<DbPool>() {
+                Some(pool) => pool.clone(),
+                None => {
+                    tracing::warn!("Meetup reminders job disabled: database pool unavailable");
+                    return;
+                }
+            };
+            let Some(settings) = rocket.state::<Settings>().cloned().map(Arc::new) else {
+                tracing::warn!("Meetup reminders job disabled: settings unavailable");
+                return;
+            };
+            let period = Duration::from_secs(settings.meetup_reminder_interval_secs);
+
+            spawn_periodic("Meetup reminders job", pool, period, move |mut conn| {
+                let settings = settings.clone();
+                async move { run_meetup_reminders(&settings, &mut conn).await }
+            });
+        })
+    })
+}
+
+async fn run_meetup_reminders(
+    settings: &Settings,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), String> {
+    let now = Utc::now().naive_utc();
+    let horizon = now + ChronoDuration::hours(settings.meetup_reminder_hours);
+
+    let due: Vec<Order> = orders::table
+        .filter(orders::meetup_starts_at.gt(now))
+        .filter(orders::meetup_starts_at.le(horizon))
+        .filter(orders::meetup_reminded_at.is_null())
+        .filter(orders::status.eq_any(["pending", "accepted"]))
+        .load(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for order in due {
+        let (buyer_location, seller_location) = load_order_locations(conn, &order)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = send_meetup_emails(
+            settings,
+            &order,
+            buyer_location,
+            seller_location,
+            MeetupEmailKind::Reminder,
+        )
+        .await
+        {
+            tracing::error!(order_id = order.id, error = %e, "Failed to send meetup reminder");
+            continue;
+        }
+
+        // Only mark the meetup we reminded about, in case it was rescheduled meanwhile
+        diesel::update(
+            orders::table
+                .find(order.id)
+                .filter(orders::meetup_starts_at.eq(order.meetup_starts_at)),
+        )
+        .set(orders::meetup_reminded_at.eq(Some(Utc::now().naive_utc())))
+        .execute(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Periodically retries capturing payments that are still only authorized
+/// although their order was completed, e.g. because the provider was
+/// unreachable at handoff
+pub fn payment_captures() -> AdHoc {
+    AdHoc::on_liftoff("Payment captures job", |rocket| {
+        Box::pin(async move {
+            let pool = match rocket.state::<DbPool>() {
+                Some(pool) => pool.clone(),
+                None => {
+                    tracing::warn!("Payment captures job disabled: database pool unavailable");
+                    return;
+                }
+            };
+            let Some(provider) = rocket.state::<Payments>().cloned() else {
+                tracing::warn!("Payment captures job disabled: payment provider unavailable");
+                return;
+            };
+            let Some(settings) = rocket.state::<Settings>() else {
+                tracing::warn!("Payment captures job disabled: settings unavailable");
+                return;
+            };
+            let period = Duration::from_secs(settings.payment_capture_interval_secs);
+
+            spawn_periodic("Payment captures job", pool, period, move |mut conn| {
+                let provider = provider.clone();
+                async move { run_payment_captures(&provider, &mut conn).await }
+            });
+        })
+    })
+}
+
+async fn run_payment_captures(
+    provider: &Payments,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), String> {
+    let due: Vec<Payment> = payments_table::table
+        .inner_join(orders::table)
+        .filter(payments_table::status.eq(PaymentStatus::Authorized.as_str()))
+        .filter(orders::status.eq("completed"))
+        .select(payments_table::all_columns)
+        .load(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for payment in due {
+        let order_id = payment.order_id;
+        // Left authorized with the failure recorded; the next run tries again
+        if let Err(e) = payments::capture(conn, provider.as_ref(), payment).await {
+            tracing::error!(order_id, error = %e, "Failed to capture payment");
+        }
+    }
+
+    Ok(())
+}
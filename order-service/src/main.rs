@@ -1,108 +1,121 @@
-pub mod auth;
+pub mod calendar;
+pub mod catalog;
 pub mod db;
+pub mod disputes;
+pub mod email;
+pub mod events;
 pub mod geolocation;
+pub mod handoff;
 pub mod health;
+pub mod jobs;
+pub mod meetups;
+pub mod metrics;
 pub mod models;
 pub mod nominatim;
+pub mod payments;
+pub mod reasons;
+pub mod reputation;
 pub mod routes;
 pub mod schema;
+pub mod settings;
+pub mod users;
 
-use diesel::{Connection, PgConnection};
-use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use rocket::fairing::{Fairing, Info, Kind};
-use rocket::figment::value::{Map, Value};
-use rocket::http::Header;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
+use handshake_common::config;
+use handshake_common::metrics::RequestMetrics;
+use handshake_common::telemetry::{self, RequestTracing};
 use rocket::routes;
-use rocket::{Request, Response};
 
-use rocket_cors::CorsOptions;
+use crate::settings::Settings;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 
-fn run_migrations(connection: &mut PgConnection) {
-    match connection.run_pending_migrations(MIGRATIONS) {
-        Ok(migrations) => {
-            if migrations.is_empty() {
-                println!("No pending migrations to run.");
-            } else {
-                println!("Successfully ran {} migrations:", migrations.len());
-                for migration in migrations {
-                    println!("  - {}", migration);
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Error running migrations: {}", e);
-            std::process::exit(1);
-        }
-    }
-}
-
-pub struct CORS;
-
-#[rocket::async_trait]
-impl Fairing for CORS {
-    fn info(&self) -> Info {
-        Info {
-            name: "Add CORS headers to responses",
-            kind: Kind::Response,
-        }
-    }
-
-    async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
-        response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
-        response.set_header(Header::new(
-            "Access-Control-Allow-Methods",
-            "POST, GET, PUT, DELETE, OPTIONS",
-        ));
-        response.set_header(Header::new(
-            "Access-Control-Allow-Headers",
-            "*, Authorization",
-        ));
-        response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
-    }
-}
-
+#[allow(clippy::result_large_err)]
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
-    dotenv::dotenv().ok();
-
-    let database_url =
-        std::env::var("DATABASE_URL").expect("DATABASE_URL must be set (e.g. in .env)");
-
-    // Run database migrations on startup
-    println!("Running database migrations...");
-    let mut connection = PgConnection::establish(&database_url)
-        .unwrap_or_else(|e| {
-            eprintln!("Error connecting to database for migrations: {}", e);
-            std::process::exit(1);
-        });
-    run_migrations(&mut connection);
-
-    let mut db: Map<String, Value> = Map::new();
-    db.insert("url".to_string(), database_url.into());
-
-    let mut databases: Map<String, Value> = Map::new();
-    databases.insert("order_db".to_string(), db.into());
-
-    let figment = rocket::Config::figment().merge(("databases", databases));
-
-    let cors = CorsOptions::default().to_cors().unwrap();
-
-    let _rocket = rocket::custom(figment)
-        // .attach(CORS)
-        .attach(cors)
-        .attach(db::DbConn::fairing())
+    config::load_env();
+    let _telemetry = telemetry::init("order-service");
+    let settings: Settings = config::load();
+    metrics::register();
+
+    handshake_common::db::run_migrations(&settings.database.url, MIGRATIONS);
+
+    let pool = db::pool(&settings.database);
+
+    let _rocket = rocket::build()
+        .attach(config::cors(settings.app_url.as_deref()))
+        .attach(RequestTracing)
+        .attach(RequestMetrics)
+        .manage(pool)
+        .attach(handshake_common::metrics::pool_metrics())
+        .manage(payments::provider(&settings))
+        .attach(jobs::meetup_reminders())
+        .attach(jobs::payment_captures())
+        .attach(handshake_common::events::relay::relay(
+            events::SOURCE,
+            settings.events.clone(),
+        ))
+        .attach(events::consumer::consumer(settings.events.clone()))
+        .manage(settings.auth.clone())
+        .manage(settings)
+        .register("/", handshake_common::error::catchers())
         .mount("/", routes![health::live, health::ready])
+        .mount("/", handshake_common::metrics::routes())
         .mount(
             "/orders",
-            routes![routes::create_order, routes::get_order, routes::my_orders,],
+            routes![
+                routes::create_order,
+                routes::get_order,
+                routes::my_orders,
+                routes::accept_order,
+                routes::cancel_order,
+                routes::get_order_listing,
+                meetups::list_proposals,
+                meetups::propose_meetup,
+                meetups::confirm_meetup,
+                meetups::decline_meetup,
+                meetups::meetup_calendar,
+                handoff::issue_handoff_code,
+                handoff::complete_handoff,
+                payments::webhooks::get_order_payment,
+                payments::webhooks::update_payment_method,
+                disputes::raise_dispute,
+                disputes::get_order_dispute,
+                disputes::post_dispute_message,
+                disputes::add_dispute_evidence,
+            ],
+        )
+        .mount(
+            "/disputes",
+            routes![
+                disputes::dispute_queue,
+                disputes::get_dispute,
+                disputes::post_admin_message,
+                disputes::resolve_dispute,
+            ],
+        )
+        .mount("/reputation", routes![reputation::user_reputation])
+        .mount("/payments", routes![payments::webhooks::payment_webhook])
+        .mount(
+            "/availability",
+            routes![
+                meetups::user_availability,
+                meetups::create_availability,
+                meetups::delete_availability,
+            ],
         )
         .mount(
             "/geocode",
             routes![routes::geocode_address, routes::reverse_geocode,],
         )
-        .mount("/locations", routes![routes::upsert_my_location,])
+        .mount(
+            "/locations",
+            routes![
+                routes::get_my_location,
+                routes::get_public_location,
+                routes::upsert_my_location,
+            ],
+        )
         .launch()
         .await?;
 
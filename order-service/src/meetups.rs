@@ -0,0 +1,505 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::Json;
+use rocket::{delete, get, post, State};
+use serde::Deserialize;
+
+use handshake_common::error::ApiError;
+use handshake_common::auth::AuthenticatedUser;
+use crate::calendar::CalendarEvent;
+use crate::catalog;
+use crate::db::DbConn;
+use crate::email::{send_meetup_email, MeetupEmailKind, MeetupEmailRequest};
+use crate::models::{
+    AvailabilityWindow, Location, MeetupProposal, NewAvailabilityWindow, NewMeetupProposal, Order,
+};
+use crate::routes::{build_order_response, load_order_locations, not_a_party, OrderResponse};
+use crate::schema::{availability_windows, meetup_proposals, orders};
+use crate::settings::Settings;
+use crate::users;
+
+const DEFAULT_MEETUP_MINUTES: i64 = 30;
+const MIN_MEETUP_MINUTES: i64 = 15;
+const MAX_MEETUP_MINUTES: i64 = 240;
+const MAX_WINDOW_HOURS: i64 = 24;
+
+/// Order statuses in which a meetup can still be scheduled
+const SCHEDULABLE_STATUSES: [&str; 2] = ["pending", "accepted"];
+
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityRequest {
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProposeMeetupRequest {
+    pub starts_at: NaiveDateTime,
+    pub duration_minutes: Option<i64>,
+}
+
+fn is_participant(order: &Order, user_id: i32) -> bool {
+    order.buyer_id == user_id || order.seller_id == user_id
+}
+
+fn meetup_in_past() -> ApiError {
+    ApiError::unprocessable("The meetup must start in the future")
+}
+
+async fn load_order(conn: &mut AsyncPgConnection, id: i32) -> Result<Order, ApiError> {
+    orders::table
+        .find(id)
+        .first::<Order>(conn)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Order not found"))
+}
+
+/// A seller's upcoming availability, for buyers picking a slot
+#[get("/users/<user_id>")]
+pub async fn user_availability(
+    mut db: DbConn,
+    _auth: AuthenticatedUser,
+    user_id: i32,
+) -> Result<Json<Vec<AvailabilityWindow>>, ApiError> {
+    let now = Utc::now().naive_utc();
+
+    availability_windows::table
+        .filter(availability_windows::user_id.eq(user_id))
+        .filter(availability_windows::ends_at.gt(now))
+        .order(availability_windows::starts_at.asc())
+        .load::<AvailabilityWindow>(&mut db)
+        .await
+        .map(Json)
+    .map_err(ApiError::internal)
+}
+
+#[post("/", data = "<request>")]
+pub async fn create_availability(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    request: Json<AvailabilityRequest>,
+) -> Result<Json<AvailabilityWindow>, ApiError> {
+    let now = Utc::now().naive_utc();
+    let AvailabilityRequest { starts_at, ends_at } = request.into_inner();
+
+    if ends_at <= starts_at
+        || ends_at <= now
+        || ends_at - starts_at > Duration::hours(MAX_WINDOW_HOURS)
+    {
+        return Err(ApiError::unprocessable(format!(
+            "A window must end in the future, after it starts, and last at most {} hours",
+            MAX_WINDOW_HOURS
+        )));
+    }
+
+    let new_window = NewAvailabilityWindow {
+        user_id: auth.user_id,
+        starts_at,
+        ends_at,
+    };
+
+    diesel::insert_into(availability_windows::table)
+        .values(&new_window)
+        .get_result::<AvailabilityWindow>(&mut db)
+        .await
+        .map(Json)
+    .map_err(ApiError::internal)
+}
+
+#[delete("/<id>")]
+pub async fn delete_availability(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Status, ApiError> {
+    let deleted = diesel::delete(
+        availability_windows::table
+            .find(id)
+            .filter(availability_windows::user_id.eq(auth.user_id)),
+    )
+    .execute(&mut db)
+    .await
+    .map_err(ApiError::internal)?;
+
+    if deleted == 0 {
+        return Err(ApiError::not_found("Availability window not found"));
+    }
+
+    Ok(Status::NoContent)
+}
+
+#[get("/<id>/meetup/proposals")]
+pub async fn list_proposals(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<Vec<MeetupProposal>>, ApiError> {
+    let order = load_order(&mut db, id).await?;
+    if !is_participant(&order, auth.user_id) {
+        return Err(not_a_party());
+    }
+
+    meetup_proposals::table
+        .filter(meetup_proposals::order_id.eq(id))
+        .order(meetup_proposals::created_at.desc())
+        .load::<MeetupProposal>(&mut db)
+        .await
+        .map(Json)
+    .map_err(ApiError::internal)
+}
+
+/// Propose a meetup slot. A buyer's slot has to fall inside one of the
+/// seller's availability windows once the seller has published any.
+#[post("/<id>/meetup/proposals", data = "<request>")]
+pub async fn propose_meetup(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<ProposeMeetupRequest>,
+) -> Result<Json<MeetupProposal>, ApiError> {
+    let user_id = auth.user_id;
+    let minutes = request.duration_minutes.unwrap_or(DEFAULT_MEETUP_MINUTES);
+    let starts_at = request.starts_at;
+    let now = Utc::now().naive_utc();
+
+    if !(MIN_MEETUP_MINUTES..=MAX_MEETUP_MINUTES).contains(&minutes) {
+        return Err(ApiError::unprocessable(format!(
+            "duration_minutes must be between {} and {}",
+            MIN_MEETUP_MINUTES, MAX_MEETUP_MINUTES
+        )));
+    }
+    if starts_at <= now {
+        return Err(meetup_in_past());
+    }
+    let ends_at = starts_at + Duration::minutes(minutes);
+
+    let order = load_order(&mut db, id).await?;
+    if !is_participant(&order, user_id) {
+        return Err(not_a_party());
+    }
+    if !SCHEDULABLE_STATUSES.contains(&order.status.as_str()) {
+        return Err(ApiError::conflict(
+            "Only pending or accepted orders can be scheduled",
+        ));
+    }
+
+    if user_id == order.buyer_id {
+        let windows: Vec<AvailabilityWindow> = availability_windows::table
+            .filter(availability_windows::user_id.eq(order.seller_id))
+            .filter(availability_windows::ends_at.gt(now))
+            .load(&mut db)
+            .await
+            .map_err(ApiError::internal)?;
+
+        let fits = windows
+            .iter()
+            .any(|w| w.starts_at <= starts_at && ends_at <= w.ends_at);
+        if !windows.is_empty() && !fits {
+            return Err(ApiError::unprocessable(
+                "The other party isn't available at that time",
+            ));
+        }
+    }
+
+    diesel::insert_into(meetup_proposals::table)
+        .values(&NewMeetupProposal {
+            order_id: id,
+            proposed_by: user_id,
+            starts_at,
+            ends_at,
+        })
+        .get_result::<MeetupProposal>(&mut db)
+        .await
+        .map(Json)
+        .map_err(ApiError::internal)
+}
+
+/// The other party accepts a proposal, which becomes the order's meetup time
+#[post("/<id>/meetup/proposals/<proposal_id>/confirm")]
+pub async fn confirm_meetup(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+    proposal_id: i32,
+) -> Result<Json<OrderResponse>, ApiError> {
+    let user_id = auth.user_id;
+    let proposal = load_proposal_to_answer(&mut db, id, proposal_id, user_id).await?;
+    let now = Utc::now().naive_utc();
+
+    if proposal.starts_at <= now {
+        return Err(meetup_in_past());
+    }
+
+    let (order, buyer_location, seller_location) = db
+        .transaction(|conn| {
+            async move {
+                let proposal: MeetupProposal = diesel::update(
+                    meetup_proposals::table
+                        .find(proposal_id)
+                        .filter(meetup_proposals::status.eq("pending")),
+                )
+                .set((
+                    meetup_proposals::status.eq("accepted"),
+                    meetup_proposals::responded_at.eq(Some(now)),
+                ))
+                .get_result(conn)
+                .await?;
+
+                diesel::update(
+                    meetup_proposals::table
+                        .filter(meetup_proposals::order_id.eq(id))
+                        .filter(meetup_proposals::status.eq("pending")),
+                )
+                .set((
+                    meetup_proposals::status.eq("superseded"),
+                    meetup_proposals::responded_at.eq(Some(now)),
+                ))
+                .execute(conn)
+                .await?;
+
+                let order: Order = diesel::update(
+                    orders::table
+                        .find(id)
+                        .filter(orders::status.eq_any(SCHEDULABLE_STATUSES)),
+                )
+                .set((
+                    orders::meetup_starts_at.eq(Some(proposal.starts_at)),
+                    orders::meetup_ends_at.eq(Some(proposal.ends_at)),
+                    orders::meetup_reminded_at.eq(None::<NaiveDateTime>),
+                ))
+                .get_result(conn)
+                .await?;
+
+                let (buyer, seller) = load_order_locations(conn, &order).await?;
+                Ok::<_, diesel::result::Error>((order, buyer, seller))
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(ApiError::when_missing(
+            Status::Conflict,
+            "The proposal or order can no longer be scheduled",
+        ))?;
+
+    {
+        let (order, buyer_location, seller_location) = (
+            order.clone(),
+            buyer_location.clone(),
+            seller_location.clone(),
+        );
+        let settings = settings.inner().clone();
+        rocket::tokio::spawn(async move {
+            if let Err(e) = send_meetup_emails(
+                &settings,
+                &order,
+                buyer_location,
+                seller_location,
+                MeetupEmailKind::Confirmed,
+            )
+            .await
+            {
+                tracing::error!(order_id = order.id, error = %e, "Failed to send meetup confirmation");
+            }
+        });
+    }
+
+    Ok(Json(build_order_response(
+        order,
+        buyer_location,
+        seller_location,
+        user_id,
+        &settings.fuzz(),
+    )))
+}
+
+#[post("/<id>/meetup/proposals/<proposal_id>/decline")]
+pub async fn decline_meetup(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+    proposal_id: i32,
+) -> Result<Json<MeetupProposal>, ApiError> {
+    load_proposal_to_answer(&mut db, id, proposal_id, auth.user_id).await?;
+    let now = Utc::now().naive_utc();
+
+    diesel::update(
+        meetup_proposals::table
+            .find(proposal_id)
+            .filter(meetup_proposals::status.eq("pending")),
+    )
+    .set((
+        meetup_proposals::status.eq("declined"),
+        meetup_proposals::responded_at.eq(Some(now)),
+    ))
+    .get_result::<MeetupProposal>(&mut db)
+    .await
+    .map(Json)
+    .map_err(ApiError::when_missing(
+        Status::Conflict,
+        "The proposal has already been answered",
+    ))
+}
+
+/// A pending proposal on the order that `user_id` is allowed to answer:
+/// only the participant who didn't propose it may confirm or decline.
+async fn load_proposal_to_answer(
+    conn: &mut AsyncPgConnection,
+    order_id: i32,
+    proposal_id: i32,
+    user_id: i32,
+) -> Result<MeetupProposal, ApiError> {
+    let order = load_order(conn, order_id).await?;
+    if !is_participant(&order, user_id) {
+        return Err(not_a_party());
+    }
+
+    let proposal: MeetupProposal = meetup_proposals::table
+        .find(proposal_id)
+        .filter(meetup_proposals::order_id.eq(order_id))
+        .first(conn)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Proposal not found"))?;
+
+    if proposal.proposed_by == user_id {
+        return Err(ApiError::forbidden("You can't answer your own proposal"));
+    }
+    if proposal.status != "pending" {
+        return Err(ApiError::conflict("The proposal has already been answered"));
+    }
+
+    Ok(proposal)
+}
+
+/// The confirmed meetup as an iCalendar file
+#[get("/<id>/meetup.ics")]
+pub async fn meetup_calendar(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<(ContentType, String), ApiError> {
+    let user_id = auth.user_id;
+    let order = load_order(&mut db, id).await?;
+    if !is_participant(&order, user_id) {
+        return Err(not_a_party());
+    }
+
+    let (buyer_location, seller_location) = load_order_locations(&mut db, &order)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let product_title = catalog::fetch_summaries(settings, &[order.product_id])
+        .await
+        .ok()
+        .and_then(|mut summaries| summaries.remove(&order.product_id))
+        .map(|summary| summary.title);
+
+    let response = build_order_response(
+        order,
+        buyer_location,
+        seller_location,
+        user_id,
+        &settings.fuzz(),
+    );
+    let event = meetup_event(&response, product_title.as_deref())
+        .ok_or_else(|| ApiError::not_found("No meetup has been scheduled"))?;
+
+    Ok((
+        ContentType::new("text", "calendar"),
+        event.to_ics(Utc::now().naive_utc()),
+    ))
+}
+
+/// Where to meet, as the viewer of `order` is allowed to see it
+fn meetup_place(order: &OrderResponse) -> (String, Option<(f64, f64)>) {
+    match &order.midpoint_info {
+        Some(info) => {
+            let (lat, lon) = (info.midpoint.latitude, info.midpoint.longitude);
+            (
+                format!("Meetup point near {:.5}, {:.5}", lat, lon),
+                Some((lat, lon)),
+            )
+        }
+        None => ("To be arranged".to_string(), None),
+    }
+}
+
+/// Calendar event for an order's confirmed meetup, if one is set
+fn meetup_event(order: &OrderResponse, product_title: Option<&str>) -> Option<CalendarEvent> {
+    let (starts_at, ends_at) = (order.meetup_starts_at?, order.meetup_ends_at?);
+    let (location, geo) = meetup_place(order);
+    let title = product_title.unwrap_or("your order");
+
+    Some(CalendarEvent {
+        uid: format!("order-{}@handshake", order.id),
+        starts_at,
+        ends_at,
+        summary: format!("Handshake meetup: {}", title),
+        description: format!("Handover for order #{} ({})", order.id, title),
+        location,
+        geo,
+    })
+}
+
+/// Email both parties about the order's meetup, each with the location and
+/// calendar file they are allowed to see
+pub async fn send_meetup_emails(
+    settings: &Settings,
+    order: &Order,
+    buyer_location: Option<Location>,
+    seller_location: Option<Location>,
+    kind: MeetupEmailKind,
+) -> Result<(), String> {
+    let contacts = users::fetch_contacts(settings, &[order.buyer_id, order.seller_id]).await?;
+    let product_title = catalog::fetch_summaries(settings, &[order.product_id])
+        .await
+        .ok()
+        .and_then(|mut summaries| summaries.remove(&order.product_id))
+        .map(|summary| summary.title);
+    let stamp = Utc::now().naive_utc();
+    let fuzz = settings.fuzz();
+
+    for viewer_id in [order.buyer_id, order.seller_id] {
+        let Some(contact) = contacts.get(&viewer_id) else {
+            continue;
+        };
+
+        let response = build_order_response(
+            order.clone(),
+            buyer_location.clone(),
+            seller_location.clone(),
+            viewer_id,
+            &fuzz,
+        );
+        let Some(event) = meetup_event(&response, product_title.as_deref()) else {
+            return Ok(());
+        };
+        let maps_url = event.geo.map(|(lat, lon)| {
+            format!(
+                "https://www.openstreetmap.org/?mlat={}&mlon={}#map=17/{}/{}",
+                lat, lon, lat, lon
+            )
+        });
+
+        let request = MeetupEmailRequest {
+            to_email: contact.email.clone(),
+            kind,
+            product_title: product_title
+                .clone()
+                .unwrap_or_else(|| format!("Order #{}", order.id)),
+            starts_at: event.starts_at.format("%a %d %b %Y, %H:%M UTC").to_string(),
+            ends_at: event.ends_at.format("%H:%M UTC").to_string(),
+            location: event.location.clone(),
+            maps_url,
+            ics: Some(event.to_ics(stamp)),
+        };
+
+        send_meetup_email(settings, &request).await?;
+    }
+
+    Ok(())
+}
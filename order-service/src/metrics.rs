@@ -0,0 +1,11 @@
+use handshake_common::metrics::{counter, IntCounter};
+use std::sync::LazyLock;
+
+pub static ORDERS_CREATED: LazyLock<IntCounter> =
+    LazyLock::new(|| counter("orders_created_total", "Orders placed by buyers"));
+
+/// Register the counters at startup so they read 0 instead of being
+/// missing until the first event
+pub fn register() {
+    LazyLock::force(&ORDERS_CREATED);
+}
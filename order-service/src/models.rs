@@ -28,10 +28,23 @@ pub struct Order {
     pub product_id: i32,
     pub buyer_id: i32,
     pub seller_id: i32,
-    pub buyer_location_id: i32,
-    pub seller_location_id: i32,
+    pub buyer_location_id: Option<i32>,
+    pub seller_location_id: Option<i32>,
     pub status: String,
     pub created_at: NaiveDateTime,
+    pub quantity: i32,
+    pub product_revision: Option<i32>,
+    pub meetup_starts_at: Option<NaiveDateTime>,
+    pub meetup_ends_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub meetup_reminded_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+    pub handoff_latitude: Option<f64>,
+    pub handoff_longitude: Option<f64>,
+    pub cancelled_at: Option<NaiveDateTime>,
+    pub cancelled_by: Option<i32>,
+    pub cancellation_reason: Option<String>,
+    pub cancellation_note: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Insertable)]
@@ -40,6 +53,190 @@ pub struct NewOrder {
     pub product_id: i32,
     pub buyer_id: i32,
     pub seller_id: i32,
-    pub buyer_location_id: i32,
-    pub seller_location_id: i32,
+    pub buyer_location_id: Option<i32>,
+    pub seller_location_id: Option<i32>,
+    pub quantity: i32,
+    pub product_revision: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable)]
+#[diesel(table_name = crate::schema::availability_windows)]
+pub struct AvailabilityWindow {
+    pub id: i32,
+    pub user_id: i32,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::availability_windows)]
+pub struct NewAvailabilityWindow {
+    pub user_id: i32,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable)]
+#[diesel(table_name = crate::schema::meetup_proposals)]
+pub struct MeetupProposal {
+    pub id: i32,
+    pub order_id: i32,
+    pub proposed_by: i32,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub responded_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::meetup_proposals)]
+pub struct NewMeetupProposal {
+    pub order_id: i32,
+    pub proposed_by: i32,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(table_name = crate::schema::handoff_codes)]
+pub struct HandoffCode {
+    pub id: i32,
+    pub order_id: i32,
+    pub code: String,
+    pub expires_at: NaiveDateTime,
+    pub failed_attempts: i32,
+    pub used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::handoff_codes)]
+pub struct NewHandoffCode {
+    pub order_id: i32,
+    pub code: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Queryable, Identifiable)]
+#[diesel(table_name = crate::schema::payments)]
+pub struct Payment {
+    pub id: i32,
+    pub order_id: i32,
+    pub provider: String,
+    pub reference: Option<String>,
+    #[serde(skip_serializing)]
+    pub provider_transaction_id: Option<String>,
+    #[serde(skip_serializing)]
+    pub payment_method: Option<String>,
+    pub amount: i64,
+    pub currency: String,
+    pub status: String,
+    pub attempts: i32,
+    pub failure_reason: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::payments)]
+pub struct NewPayment {
+    pub order_id: i32,
+    pub provider: String,
+    pub payment_method: Option<String>,
+    pub amount: i64,
+    pub currency: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::payment_webhooks)]
+pub struct NewPaymentWebhook {
+    pub provider: String,
+    pub event_id: String,
+    pub payment_id: Option<i32>,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Queryable, Identifiable)]
+#[diesel(table_name = crate::schema::disputes)]
+pub struct Dispute {
+    pub id: i32,
+    pub order_id: i32,
+    pub raised_by: i32,
+    pub reason: String,
+    pub description: String,
+    pub order_status: String,
+    pub status: String,
+    pub outcome: Option<String>,
+    pub resolution_note: Option<String>,
+    pub resolved_by: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::disputes)]
+pub struct NewDispute {
+    pub order_id: i32,
+    pub raised_by: i32,
+    pub reason: String,
+    pub description: String,
+    pub order_status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Queryable, Identifiable)]
+#[diesel(table_name = crate::schema::dispute_messages)]
+pub struct DisputeMessage {
+    pub id: i32,
+    pub dispute_id: i32,
+    pub author_id: i32,
+    pub body: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::dispute_messages)]
+pub struct NewDisputeMessage {
+    pub dispute_id: i32,
+    pub author_id: i32,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Queryable, Identifiable)]
+#[diesel(table_name = crate::schema::dispute_evidence)]
+pub struct DisputeEvidence {
+    pub id: i32,
+    pub dispute_id: i32,
+    pub uploaded_by: i32,
+    pub url: String,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::dispute_evidence)]
+pub struct NewDisputeEvidence {
+    pub dispute_id: i32,
+    pub uploaded_by: i32,
+    pub url: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::reputation_events)]
+pub struct NewReputationEvent {
+    pub user_id: i32,
+    pub order_id: Option<i32>,
+    pub kind: String,
+    pub points: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::processed_events)]
+pub struct NewProcessedEvent {
+    pub event_id: String,
+    pub event_type: String,
 }
@@ -1,5 +1,9 @@
+use handshake_common::metrics;
+use handshake_common::telemetry::RequestContext;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::env;
+
+use crate::settings::Settings;
 
 #[derive(Debug, Deserialize)]
 struct NominatimResponse {
@@ -15,28 +19,41 @@ pub struct GeocodeResult {
     pub address: String,
 }
 
-pub async fn geocode(address: &str) -> Result<GeocodeResult, String> {
-    let nominatim_url =
-        env::var("NOMINATIM_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+/// GET `url` from Nominatim and parse the JSON body
+async fn fetch<T: DeserializeOwned>(ctx: &RequestContext, url: &str) -> Result<T, String> {
+    metrics::observe_outbound("nominatim", async {
+        let client = reqwest::Client::new();
+        let mut builder = client
+            .get(url)
+            .header("User-Agent", "Handshake-Marketplace/1.0");
+        for (name, value) in ctx.headers() {
+            builder = builder.header(name, value);
+        }
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    })
+    .await
+}
 
-    let client = reqwest::Client::new();
+pub async fn geocode(
+    ctx: &RequestContext,
+    settings: &Settings,
+    address: &str,
+) -> Result<GeocodeResult, String> {
     let url = format!(
         "{}/search?q={}&format=json&limit=1",
-        nominatim_url,
+        settings.nominatim_url,
         urlencoding::encode(address)
     );
 
-    let response = client
-        .get(&url)
-        .header("User-Agent", "Handshake-Marketplace/1.0")
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-
-    let results: Vec<NominatimResponse> = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    let results: Vec<NominatimResponse> = fetch(ctx, &url).await?;
 
     if let Some(result) = results.first() {
         Ok(GeocodeResult {
@@ -49,27 +66,18 @@ pub async fn geocode(address: &str) -> Result<GeocodeResult, String> {
     }
 }
 
-pub async fn reverse_geocode_from_coord(lat: f64, lon: f64) -> Result<GeocodeResult, String> {
-    let nominatim_url =
-        env::var("NOMINATIM_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
-
-    let client = reqwest::Client::new();
+pub async fn reverse_geocode_from_coord(
+    ctx: &RequestContext,
+    settings: &Settings,
+    lat: f64,
+    lon: f64,
+) -> Result<GeocodeResult, String> {
     let url = format!(
         "{}/reverse?lat={}&lon={}&format=json",
-        nominatim_url, lat, lon
+        settings.nominatim_url, lat, lon
     );
 
-    let response = client
-        .get(&url)
-        .header("User-Agent", "Handshake-Marketplace/1.0")
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-
-    let result: NominatimResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    let result: NominatimResponse = fetch(ctx, &url).await?;
 
     Ok(GeocodeResult {
         latitude: result.lat.parse().map_err(|_| "Invalid latitude")?,
@@ -0,0 +1,174 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::{
+    Authorization, AuthorizeRequest, PaymentError, PaymentProvider, PaymentStatus, WebhookEvent,
+};
+use crate::models::Payment;
+
+/// Payment method that the fake provider always declines
+pub const DECLINED_METHOD: &str = "fake_declined";
+
+/// In-process provider for development and tests. Keeps its own ledger so
+/// out-of-order calls (capturing a voided hold, say) fail like a real gateway.
+#[derive(Default)]
+pub struct FakeProvider {
+    ledger: Mutex<HashMap<String, PaymentStatus>>,
+}
+
+/// Webhook body the fake provider accepts, unsigned
+#[derive(Deserialize)]
+struct FakeWebhook {
+    event_id: String,
+    reference: String,
+    status: String,
+}
+
+impl FakeProvider {
+    fn transition(
+        &self,
+        transaction_id: Option<&str>,
+        from: PaymentStatus,
+        to: PaymentStatus,
+    ) -> Result<(), PaymentError> {
+        let transaction_id =
+            transaction_id.ok_or_else(|| PaymentError::Declined("no transaction".to_string()))?;
+        let mut ledger = self.ledger.lock().unwrap();
+        match ledger.get(transaction_id) {
+            Some(status) if *status == from => {
+                ledger.insert(transaction_id.to_string(), to);
+                Ok(())
+            }
+            other => Err(PaymentError::Declined(format!(
+                "transaction is {:?}, expected {:?}",
+                other, from
+            ))),
+        }
+    }
+
+    #[cfg(test)]
+    fn status_of(&self, transaction_id: &str) -> Option<PaymentStatus> {
+        self.ledger.lock().unwrap().get(transaction_id).copied()
+    }
+}
+
+#[rocket::async_trait]
+impl PaymentProvider for FakeProvider {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    async fn authorize(
+        &self,
+        request: &AuthorizeRequest<'_>,
+    ) -> Result<Authorization, PaymentError> {
+        if request.payment_method == Some(DECLINED_METHOD) {
+            return Err(PaymentError::Declined("card declined".to_string()));
+        }
+
+        let transaction_id = format!("fake-{}", request.reference);
+        self.ledger
+            .lock()
+            .unwrap()
+            .insert(transaction_id.clone(), PaymentStatus::Authorized);
+        Ok(Authorization { transaction_id })
+    }
+
+    async fn capture(&self, payment: &Payment) -> Result<(), PaymentError> {
+        self.transition(
+            payment.provider_transaction_id.as_deref(),
+            PaymentStatus::Authorized,
+            PaymentStatus::Captured,
+        )
+    }
+
+    async fn void(&self, payment: &Payment) -> Result<(), PaymentError> {
+        self.transition(
+            payment.provider_transaction_id.as_deref(),
+            PaymentStatus::Authorized,
+            PaymentStatus::Voided,
+        )
+    }
+
+    async fn refund(&self, payment: &Payment, _reason: &str) -> Result<(), PaymentError> {
+        self.transition(
+            payment.provider_transaction_id.as_deref(),
+            PaymentStatus::Captured,
+            PaymentStatus::Refunded,
+        )
+    }
+
+    fn parse_webhook(&self, body: &str) -> Result<WebhookEvent, PaymentError> {
+        let payload: serde_json::Value =
+            serde_json::from_str(body).map_err(|e| PaymentError::InvalidWebhook(e.to_string()))?;
+        let webhook: FakeWebhook = serde_json::from_value(payload.clone())
+            .map_err(|e| PaymentError::InvalidWebhook(e.to_string()))?;
+
+        Ok(WebhookEvent {
+            event_id: webhook.event_id,
+            event_type: webhook.status.clone(),
+            reference: webhook.reference,
+            status: PaymentStatus::parse(&webhook.status),
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn payment(transaction_id: &str) -> Payment {
+        let now = Utc::now().naive_utc();
+        Payment {
+            id: 1,
+            order_id: 1,
+            provider: "fake".to_string(),
+            reference: Some("handshake-1-1".to_string()),
+            provider_transaction_id: Some(transaction_id.to_string()),
+            payment_method: None,
+            amount: 100_000,
+            currency: "IDR".to_string(),
+            status: "authorized".to_string(),
+            attempts: 1,
+            failure_reason: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn request(payment_method: Option<&str>) -> AuthorizeRequest<'_> {
+        AuthorizeRequest {
+            reference: "handshake-1-1",
+            amount: 100_000,
+            currency: "IDR",
+            payment_method,
+        }
+    }
+
+    #[rocket::async_test]
+    async fn authorizes_captures_and_refunds() {
+        let provider = FakeProvider::default();
+        let auth = provider.authorize(&request(None)).await.unwrap();
+        let payment = payment(&auth.transaction_id);
+
+        provider.capture(&payment).await.unwrap();
+        assert!(provider.void(&payment).await.is_err());
+        provider.refund(&payment, "dispute").await.unwrap();
+        assert_eq!(
+            provider.status_of(&auth.transaction_id),
+            Some(PaymentStatus::Refunded)
+        );
+    }
+
+    #[rocket::async_test]
+    async fn declines_the_test_card() {
+        let provider = FakeProvider::default();
+        assert!(matches!(
+            provider.authorize(&request(Some(DECLINED_METHOD))).await,
+            Err(PaymentError::Declined(_))
+        ));
+    }
+}
@@ -0,0 +1,261 @@
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+
+use super::{
+    Authorization, AuthorizeRequest, PaymentError, PaymentProvider, PaymentStatus, WebhookEvent,
+};
+use crate::models::Payment;
+
+/// Midtrans Core API, using card pre-authorization for the escrow hold
+pub struct Midtrans {
+    api_url: String,
+    server_key: String,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransactionResponse {
+    status_code: String,
+    status_message: Option<String>,
+    transaction_id: Option<String>,
+    transaction_status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Notification {
+    order_id: String,
+    status_code: String,
+    gross_amount: String,
+    signature_key: String,
+    transaction_id: String,
+    transaction_status: String,
+}
+
+/// Payment status for a Midtrans `transaction_status`
+fn status_for(transaction_status: &str) -> Option<PaymentStatus> {
+    match transaction_status {
+        "authorize" => Some(PaymentStatus::Authorized),
+        "capture" | "settlement" => Some(PaymentStatus::Captured),
+        "cancel" => Some(PaymentStatus::Voided),
+        "refund" | "partial_refund" => Some(PaymentStatus::Refunded),
+        "deny" | "expire" | "failure" => Some(PaymentStatus::Failed),
+        _ => None,
+    }
+}
+
+/// `signature_key` Midtrans puts on every notification
+fn signature(order_id: &str, status_code: &str, gross_amount: &str, server_key: &str) -> String {
+    let mut hasher = Sha512::new();
+    hasher.update(order_id.as_bytes());
+    hasher.update(status_code.as_bytes());
+    hasher.update(gross_amount.as_bytes());
+    hasher.update(server_key.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+impl Midtrans {
+    pub fn new(api_url: &str, server_key: &str) -> Self {
+        Midtrans {
+            api_url: api_url.to_string(),
+            server_key: server_key.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn post(
+        &self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<TransactionResponse, PaymentError> {
+        let response = self
+            .client
+            .post(format!("{}/v2/{}", self.api_url, path))
+            .basic_auth(&self.server_key, Some(""))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| PaymentError::Unreachable(format!("Request failed: {}", e)))?;
+
+        if response.status().is_server_error() {
+            return Err(PaymentError::Unreachable(format!(
+                "Midtrans returned error ({})",
+                response.status()
+            )));
+        }
+
+        // Midtrans reports most failures in the body with an HTTP 200
+        let transaction: TransactionResponse = response
+            .json()
+            .await
+            .map_err(|e| PaymentError::Unreachable(format!("Failed to parse response: {}", e)))?;
+
+        if !transaction.status_code.starts_with('2') {
+            return Err(PaymentError::Declined(
+                transaction
+                    .status_message
+                    .unwrap_or_else(|| format!("status {}", transaction.status_code)),
+            ));
+        }
+
+        Ok(transaction)
+    }
+
+    fn transaction_id(payment: &Payment) -> Result<&str, PaymentError> {
+        payment
+            .provider_transaction_id
+            .as_deref()
+            .ok_or_else(|| PaymentError::Declined("payment was never authorized".to_string()))
+    }
+}
+
+#[rocket::async_trait]
+impl PaymentProvider for Midtrans {
+    fn name(&self) -> &'static str {
+        "midtrans"
+    }
+
+    async fn authorize(
+        &self,
+        request: &AuthorizeRequest<'_>,
+    ) -> Result<Authorization, PaymentError> {
+        let token_id = request
+            .payment_method
+            .ok_or_else(|| PaymentError::Declined("no card on file".to_string()))?;
+
+        let transaction = self
+            .post(
+                "charge",
+                json!({
+                    "payment_type": "credit_card",
+                    "transaction_details": {
+                        "order_id": request.reference,
+                        "gross_amount": request.amount,
+                    },
+                    "credit_card": {
+                        "token_id": token_id,
+                        "type": "authorize",
+                    },
+                }),
+            )
+            .await?;
+
+        match (
+            transaction.transaction_status.as_deref(),
+            transaction.transaction_id,
+        ) {
+            (Some("authorize"), Some(transaction_id)) => Ok(Authorization { transaction_id }),
+            (status, _) => Err(PaymentError::Declined(format!(
+                "transaction status {}",
+                status.unwrap_or("unknown")
+            ))),
+        }
+    }
+
+    async fn capture(&self, payment: &Payment) -> Result<(), PaymentError> {
+        self.post(
+            "capture",
+            json!({
+                "transaction_id": Self::transaction_id(payment)?,
+                "gross_amount": payment.amount,
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn void(&self, payment: &Payment) -> Result<(), PaymentError> {
+        let path = format!("{}/cancel", Self::transaction_id(payment)?);
+        self.post(&path, json!({})).await.map(|_| ())
+    }
+
+    async fn refund(&self, payment: &Payment, reason: &str) -> Result<(), PaymentError> {
+        let path = format!("{}/refund", Self::transaction_id(payment)?);
+        self.post(
+            &path,
+            json!({
+                "refund_key": format!("{}-refund", payment.reference.as_deref().unwrap_or_default()),
+                "amount": payment.amount,
+                "reason": reason,
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    fn parse_webhook(&self, body: &str) -> Result<WebhookEvent, PaymentError> {
+        let payload: serde_json::Value =
+            serde_json::from_str(body).map_err(|e| PaymentError::InvalidWebhook(e.to_string()))?;
+        let notification: Notification = serde_json::from_value(payload.clone())
+            .map_err(|e| PaymentError::InvalidWebhook(e.to_string()))?;
+
+        let expected = signature(
+            &notification.order_id,
+            &notification.status_code,
+            &notification.gross_amount,
+            &self.server_key,
+        );
+        // Compared in constant time so the expected key can't be guessed byte
+        // by byte from response timings
+        let matches: bool = expected
+            .as_bytes()
+            .ct_eq(notification.signature_key.as_bytes())
+            .into();
+        if self.server_key.is_empty() || !matches {
+            return Err(PaymentError::InvalidWebhook("bad signature".to_string()));
+        }
+
+        Ok(WebhookEvent {
+            // Midtrans resends the same notification until it gets a 200
+            event_id: format!(
+                "{}:{}",
+                notification.transaction_id, notification.transaction_status
+            ),
+            status: status_for(&notification.transaction_status),
+            event_type: notification.transaction_status,
+            reference: notification.order_id,
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider() -> Midtrans {
+        Midtrans {
+            api_url: "http://localhost".to_string(),
+            server_key: "SB-Mid-server-test".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn notification(signature_key: &str) -> String {
+        json!({
+            "order_id": "handshake-7-1",
+            "status_code": "200",
+            "gross_amount": "150000.00",
+            "signature_key": signature_key,
+            "transaction_id": "abc-123",
+            "transaction_status": "capture",
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn accepts_signed_notifications() {
+        let key = signature("handshake-7-1", "200", "150000.00", "SB-Mid-server-test");
+        let event = provider().parse_webhook(&notification(&key)).unwrap();
+        assert_eq!(event.reference, "handshake-7-1");
+        assert_eq!(event.event_id, "abc-123:capture");
+        assert_eq!(event.status, Some(PaymentStatus::Captured));
+    }
+
+    #[test]
+    fn rejects_forged_notifications() {
+        let key = signature("handshake-7-1", "200", "1.00", "SB-Mid-server-test");
+        assert!(provider().parse_webhook(&notification(&key)).is_err());
+    }
+}
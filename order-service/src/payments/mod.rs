@@ -0,0 +1,318 @@
+pub mod fake;
+pub mod midtrans;
+pub mod webhooks;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::models::Payment;
+use crate::schema::payments;
+use crate::settings::Settings;
+
+/// Where a payment is in the escrow lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentStatus {
+    /// Created with the order; nothing held on the buyer's card yet
+    Pending,
+    /// Funds held when the seller accepted the order
+    Authorized,
+    /// Funds taken when the item was handed over
+    Captured,
+    /// Hold released without taking any money
+    Voided,
+    Refunded,
+    Failed,
+}
+
+impl PaymentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Authorized => "authorized",
+            PaymentStatus::Captured => "captured",
+            PaymentStatus::Voided => "voided",
+            PaymentStatus::Refunded => "refunded",
+            PaymentStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(PaymentStatus::Pending),
+            "authorized" => Some(PaymentStatus::Authorized),
+            "captured" => Some(PaymentStatus::Captured),
+            "voided" => Some(PaymentStatus::Voided),
+            "refunded" => Some(PaymentStatus::Refunded),
+            "failed" => Some(PaymentStatus::Failed),
+            _ => None,
+        }
+    }
+
+    /// Whether a payment may move from `self` to `next`. Provider webhooks can
+    /// arrive late or out of order, so they must never move a payment backwards.
+    pub fn can_become(&self, next: PaymentStatus) -> bool {
+        use PaymentStatus::*;
+        matches!(
+            (self, next),
+            (Pending | Failed | Voided, Authorized)
+                | (Pending | Authorized, Failed)
+                | (Authorized, Captured | Voided)
+                | (Captured, Refunded)
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum PaymentError {
+    /// The provider refused, e.g. insufficient funds or a bad card token
+    Declined(String),
+    /// A webhook whose signature or body couldn't be verified
+    InvalidWebhook(String),
+    Unreachable(String),
+}
+
+impl std::fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaymentError::Declined(reason) => write!(f, "declined: {}", reason),
+            PaymentError::InvalidWebhook(reason) => write!(f, "invalid webhook: {}", reason),
+            PaymentError::Unreachable(reason) => write!(f, "provider unreachable: {}", reason),
+        }
+    }
+}
+
+/// What to put on hold with the provider
+pub struct AuthorizeRequest<'a> {
+    pub reference: &'a str,
+    pub amount: i64,
+    pub currency: &'a str,
+    /// Provider token for the buyer's card, collected by the frontend
+    pub payment_method: Option<&'a str>,
+}
+
+/// A hold placed by the provider
+pub struct Authorization {
+    pub transaction_id: String,
+}
+
+/// A verified provider notification
+pub struct WebhookEvent {
+    /// Unique per provider so redelivered events are only applied once
+    pub event_id: String,
+    pub event_type: String,
+    pub reference: String,
+    /// The payment status this event reports, if it maps to one
+    pub status: Option<PaymentStatus>,
+    pub payload: serde_json::Value,
+}
+
+#[rocket::async_trait]
+pub trait PaymentProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn authorize(
+        &self,
+        request: &AuthorizeRequest<'_>,
+    ) -> Result<Authorization, PaymentError>;
+
+    async fn capture(&self, payment: &Payment) -> Result<(), PaymentError>;
+
+    /// Release an authorization that was never captured
+    async fn void(&self, payment: &Payment) -> Result<(), PaymentError>;
+
+    async fn refund(&self, payment: &Payment, reason: &str) -> Result<(), PaymentError>;
+
+    fn parse_webhook(&self, body: &str) -> Result<WebhookEvent, PaymentError>;
+}
+
+/// The configured provider, shared as Rocket managed state
+pub type Payments = Arc<dyn PaymentProvider>;
+
+/// The `payment_provider` setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// Approves everything without moving money; debug builds only
+    #[default]
+    Fake,
+    Midtrans,
+}
+
+/// The provider chosen in the settings
+pub fn provider(settings: &Settings) -> Payments {
+    match settings.payment_provider {
+        ProviderKind::Fake => Arc::new(fake::FakeProvider::default()),
+        ProviderKind::Midtrans => Arc::new(midtrans::Midtrans::new(
+            &settings.midtrans_api_url,
+            settings.midtrans_server_key.as_deref().unwrap_or_default(),
+        )),
+    }
+}
+
+/// Amount in whole currency units, as IDR has no minor unit in practice
+pub fn order_amount(price: f64, quantity: i32) -> i64 {
+    (price * f64::from(quantity)).round() as i64
+}
+
+pub async fn load_for_order(
+    conn: &mut AsyncPgConnection,
+    order_id: i32,
+) -> QueryResult<Option<Payment>> {
+    payments::table
+        .filter(payments::order_id.eq(order_id))
+        .first(conn)
+        .await
+        .optional()
+}
+
+async fn set_status(
+    conn: &mut AsyncPgConnection,
+    payment_id: i32,
+    status: PaymentStatus,
+    failure_reason: Option<String>,
+) -> Result<Payment, String> {
+    diesel::update(payments::table.find(payment_id))
+        .set((
+            payments::status.eq(status.as_str()),
+            payments::failure_reason.eq(failure_reason),
+            payments::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result(conn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Put the order amount on hold. Each attempt gets a fresh reference since
+/// providers won't reuse one for a second charge.
+pub async fn authorize(
+    conn: &mut AsyncPgConnection,
+    provider: &dyn PaymentProvider,
+    payment: Payment,
+) -> Result<Payment, PaymentError> {
+    let payment_id = payment.id;
+    let reference = format!("handshake-{}-{}", payment.order_id, payment.attempts + 1);
+
+    let payment: Payment = diesel::update(payments::table.find(payment_id))
+        .set((
+            payments::attempts.eq(payments::attempts + 1),
+            payments::reference.eq(Some(&reference)),
+            payments::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result(conn)
+        .await
+        .map_err(|e| PaymentError::Unreachable(e.to_string()))?;
+
+    let result = provider
+        .authorize(&AuthorizeRequest {
+            reference: &reference,
+            amount: payment.amount,
+            currency: &payment.currency,
+            payment_method: payment.payment_method.as_deref(),
+        })
+        .await;
+
+    match result {
+        Ok(authorization) => diesel::update(payments::table.find(payment_id))
+            .set((
+                payments::status.eq(PaymentStatus::Authorized.as_str()),
+                payments::provider_transaction_id.eq(Some(authorization.transaction_id)),
+                payments::failure_reason.eq(None::<String>),
+                payments::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn)
+            .await
+            .map_err(|e| PaymentError::Unreachable(e.to_string())),
+        Err(e) => {
+            if let Err(db_err) =
+                set_status(conn, payment_id, PaymentStatus::Failed, Some(e.to_string())).await
+            {
+                tracing::error!(payment_id, error = %db_err, "Failed to record payment failure");
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Take the held funds once the item has changed hands
+pub async fn capture(
+    conn: &mut AsyncPgConnection,
+    provider: &dyn PaymentProvider,
+    payment: Payment,
+) -> Result<Payment, PaymentError> {
+    if PaymentStatus::parse(&payment.status) != Some(PaymentStatus::Authorized) {
+        return Ok(payment);
+    }
+
+    match provider.capture(&payment).await {
+        Ok(()) => set_status(conn, payment.id, PaymentStatus::Captured, None)
+            .await
+            .map_err(PaymentError::Unreachable),
+        Err(e) => {
+            // Left authorized so the capture can be retried or settled by webhook
+            let _ = set_status(
+                conn,
+                payment.id,
+                PaymentStatus::Authorized,
+                Some(e.to_string()),
+            )
+            .await;
+            Err(e)
+        }
+    }
+}
+
+/// Give the buyer their money back: release the hold if it was never
+/// captured, refund it otherwise. Anything else has nothing to return.
+pub async fn release(
+    conn: &mut AsyncPgConnection,
+    provider: &dyn PaymentProvider,
+    payment: Payment,
+    reason: &str,
+) -> Result<Payment, PaymentError> {
+    let (result, next) = match PaymentStatus::parse(&payment.status) {
+        Some(PaymentStatus::Authorized) => (provider.void(&payment).await, PaymentStatus::Voided),
+        Some(PaymentStatus::Captured) => (
+            provider.refund(&payment, reason).await,
+            PaymentStatus::Refunded,
+        ),
+        Some(PaymentStatus::Pending) | Some(PaymentStatus::Failed) => {
+            (Ok(()), PaymentStatus::Voided)
+        }
+        _ => return Ok(payment),
+    };
+
+    match result {
+        Ok(()) => set_status(conn, payment.id, next, None)
+            .await
+            .map_err(PaymentError::Unreachable),
+        Err(e) => {
+            let current = PaymentStatus::parse(&payment.status).unwrap_or(PaymentStatus::Pending);
+            let _ = set_status(conn, payment.id, current, Some(e.to_string())).await;
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhooks_cannot_move_a_payment_backwards() {
+        assert!(PaymentStatus::Pending.can_become(PaymentStatus::Authorized));
+        assert!(PaymentStatus::Authorized.can_become(PaymentStatus::Captured));
+        assert!(PaymentStatus::Captured.can_become(PaymentStatus::Refunded));
+        assert!(!PaymentStatus::Captured.can_become(PaymentStatus::Authorized));
+        assert!(!PaymentStatus::Refunded.can_become(PaymentStatus::Captured));
+        assert!(!PaymentStatus::Voided.can_become(PaymentStatus::Captured));
+    }
+
+    #[test]
+    fn rounds_order_amounts_to_whole_units() {
+        assert_eq!(order_amount(150_000.0, 2), 300_000);
+        assert_eq!(order_amount(19_999.5, 1), 20_000);
+    }
+}
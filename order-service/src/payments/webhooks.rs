@@ -0,0 +1,165 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{get, post, put, State};
+use serde::Deserialize;
+
+use super::{PaymentStatus, Payments};
+use handshake_common::error::ApiError;
+use handshake_common::auth::AuthenticatedUser;
+use crate::db::DbConn;
+use crate::models::{NewPaymentWebhook, Order, Payment};
+use crate::routes::not_a_party;
+use crate::schema::{orders, payment_webhooks, payments};
+
+#[derive(Debug, Deserialize)]
+pub struct PaymentMethodRequest {
+    pub payment_method: String,
+}
+
+/// Provider notifications. Every event is stored; redeliveries are
+/// acknowledged without being applied twice.
+#[post("/webhooks/<provider_name>", data = "<body>")]
+pub async fn payment_webhook(
+    mut db: DbConn,
+    provider: &State<Payments>,
+    provider_name: &str,
+    body: String,
+) -> Result<Status, ApiError> {
+    if provider_name != provider.name() {
+        return Err(ApiError::not_found("Unknown payment provider"));
+    }
+
+    let event = provider
+        .parse_webhook(&body)
+        .map_err(|e| ApiError::bad_request("Invalid payment notification").with_source(e))?;
+    let provider_name = provider.name().to_string();
+
+    db.transaction(|conn| {
+        async move {
+            let payment: Option<Payment> = payments::table
+                .filter(payments::reference.eq(&event.reference))
+                .for_update()
+                .first(conn)
+                .await
+                .optional()?;
+
+            let inserted = diesel::insert_into(payment_webhooks::table)
+                .values(&NewPaymentWebhook {
+                    provider: provider_name.clone(),
+                    event_id: event.event_id.clone(),
+                    payment_id: payment.as_ref().map(|p| p.id),
+                    event_type: event.event_type.clone(),
+                    payload: event.payload.clone(),
+                })
+                .on_conflict((payment_webhooks::provider, payment_webhooks::event_id))
+                .do_nothing()
+                .execute(conn)
+                .await?;
+
+            if inserted == 0 {
+                return Ok(());
+            }
+
+            if let (Some(payment), Some(next)) = (payment, event.status) {
+                let current = PaymentStatus::parse(&payment.status);
+                if current.is_some_and(|current| current.can_become(next)) {
+                    diesel::update(payments::table.find(payment.id))
+                        .set((
+                            payments::status.eq(next.as_str()),
+                            payments::updated_at.eq(Utc::now().naive_utc()),
+                        ))
+                        .execute(conn)
+                        .await?;
+                }
+            }
+
+            diesel::update(
+                payment_webhooks::table
+                    .filter(payment_webhooks::provider.eq(&provider_name))
+                    .filter(payment_webhooks::event_id.eq(&event.event_id)),
+            )
+            .set(payment_webhooks::processed_at.eq(Some(Utc::now().naive_utc())))
+            .execute(conn)
+            .await?;
+
+            Ok::<_, diesel::result::Error>(())
+        }
+        .scope_boxed()
+    })
+    .await
+    .map_err(ApiError::internal)?;
+
+    Ok(Status::Ok)
+}
+
+fn no_payment() -> ApiError {
+    ApiError::not_found("This order has no payment")
+}
+
+async fn load_order_payment(
+    conn: &mut AsyncPgConnection,
+    order_id: i32,
+) -> Result<(Order, Option<Payment>), ApiError> {
+    let order: Order = orders::table
+        .find(order_id)
+        .first(conn)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Order not found"))?;
+    let payment = super::load_for_order(conn, order_id)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok((order, payment))
+}
+
+/// Payment state for an order, for either party
+#[get("/<id>/payment")]
+pub async fn get_order_payment(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<Payment>, ApiError> {
+    let (order, payment) = load_order_payment(&mut db, id).await?;
+    if order.buyer_id != auth.user_id && order.seller_id != auth.user_id {
+        return Err(not_a_party());
+    }
+
+    payment.map(Json).ok_or_else(no_payment)
+}
+
+/// Buyer swaps in another card, e.g. after the first one was declined
+#[put("/<id>/payment/method", data = "<request>")]
+pub async fn update_payment_method(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<PaymentMethodRequest>,
+) -> Result<Json<Payment>, ApiError> {
+    let (order, payment) = load_order_payment(&mut db, id).await?;
+    if order.buyer_id != auth.user_id {
+        return Err(ApiError::forbidden("Only the buyer can change the payment method"));
+    }
+    let payment = payment.ok_or_else(no_payment)?;
+    if !matches!(
+        PaymentStatus::parse(&payment.status),
+        Some(PaymentStatus::Pending | PaymentStatus::Failed)
+    ) {
+        return Err(ApiError::conflict(
+            "The payment method can only change before the payment is authorized",
+        ));
+    }
+
+    let payment_method = request.into_inner().payment_method;
+    diesel::update(payments::table.find(payment.id))
+        .set((
+            payments::payment_method.eq(Some(payment_method)),
+            payments::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result::<Payment>(&mut db)
+        .await
+        .map(Json)
+    .map_err(ApiError::internal)
+}
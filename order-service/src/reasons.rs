@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+use handshake_common::error::ApiError;
+
+pub const MAX_NOTE_LENGTH: usize = 2000;
+
+/// Why an order was called off. Required on every cancellation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancellationReason {
+    ChangedMind,
+    FoundElsewhere,
+    ItemUnavailable,
+    PriceDisagreement,
+    ScheduleConflict,
+    CounterpartyUnresponsive,
+    SafetyConcern,
+    /// Needs a note explaining what happened
+    Other,
+}
+
+impl CancellationReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CancellationReason::ChangedMind => "changed_mind",
+            CancellationReason::FoundElsewhere => "found_elsewhere",
+            CancellationReason::ItemUnavailable => "item_unavailable",
+            CancellationReason::PriceDisagreement => "price_disagreement",
+            CancellationReason::ScheduleConflict => "schedule_conflict",
+            CancellationReason::CounterpartyUnresponsive => "counterparty_unresponsive",
+            CancellationReason::SafetyConcern => "safety_concern",
+            CancellationReason::Other => "other",
+        }
+    }
+}
+
+/// What went wrong, as given by whoever raised the dispute
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeReason {
+    ItemNotAsDescribed,
+    ItemDamaged,
+    NoShow,
+    PaymentIssue,
+    SafetyConcern,
+    Other,
+}
+
+impl DisputeReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisputeReason::ItemNotAsDescribed => "item_not_as_described",
+            DisputeReason::ItemDamaged => "item_damaged",
+            DisputeReason::NoShow => "no_show",
+            DisputeReason::PaymentIssue => "payment_issue",
+            DisputeReason::SafetyConcern => "safety_concern",
+            DisputeReason::Other => "other",
+        }
+    }
+}
+
+/// An admin's ruling on a dispute
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeOutcome {
+    /// The seller was at fault; the buyer gets their money back
+    BuyerFavored,
+    /// The buyer was at fault; the seller keeps any payment for a handed-over item
+    SellerFavored,
+    /// Nobody is penalised; the buyer gets their money back
+    NoFault,
+}
+
+impl DisputeOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisputeOutcome::BuyerFavored => "buyer_favored",
+            DisputeOutcome::SellerFavored => "seller_favored",
+            DisputeOutcome::NoFault => "no_fault",
+        }
+    }
+
+    /// Whether the buyer is paid back, given the order's status when the
+    /// dispute was raised. Money only goes to the seller for an item that
+    /// was actually handed over.
+    pub fn refunds_buyer(&self, order_status: &str) -> bool {
+        !(*self == DisputeOutcome::SellerFavored && order_status == "completed")
+    }
+}
+
+/// Trim an optional free-text note. 422 if it's too long, or missing when
+/// `required` (reason `other`).
+pub fn clean_note(note: Option<&str>, required: bool) -> Result<Option<String>, ApiError> {
+    let note = note.map(str::trim).filter(|n| !n.is_empty());
+    match note {
+        Some(n) if n.chars().count() > MAX_NOTE_LENGTH => Err(ApiError::unprocessable(format!(
+            "Must be at most {} characters",
+            MAX_NOTE_LENGTH
+        ))),
+        None if required => Err(ApiError::unprocessable("A note is required")),
+        note => Ok(note.map(str::to_string)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reason_codes_round_trip_through_json() {
+        let reason: CancellationReason = serde_json::from_str("\"schedule_conflict\"").unwrap();
+        assert_eq!(reason.as_str(), "schedule_conflict");
+        assert!(serde_json::from_str::<CancellationReason>("\"bored\"").is_err());
+    }
+
+    #[test]
+    fn other_needs_a_note() {
+        assert!(clean_note(Some("   "), true).is_err());
+        assert_eq!(
+            clean_note(Some(" moved away "), true),
+            Ok(Some("moved away".to_string()))
+        );
+        assert_eq!(clean_note(None, false), Ok(None));
+        assert!(clean_note(Some(&"x".repeat(MAX_NOTE_LENGTH + 1)), false).is_err());
+    }
+
+    #[test]
+    fn sellers_only_keep_payment_for_handed_over_items() {
+        assert!(!DisputeOutcome::SellerFavored.refunds_buyer("completed"));
+        assert!(DisputeOutcome::SellerFavored.refunds_buyer("accepted"));
+        assert!(DisputeOutcome::NoFault.refunds_buyer("completed"));
+    }
+}
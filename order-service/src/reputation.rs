@@ -0,0 +1,115 @@
+use diesel::dsl::{count_star, sql};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Nullable};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use rocket::get;
+use rocket::serde::json::Json;
+use serde::Serialize;
+
+use handshake_common::error::ApiError;
+use handshake_common::auth::AuthenticatedUser;
+use crate::db::DbConn;
+use crate::models::NewReputationEvent;
+use crate::schema::{orders, reputation_events};
+
+/// Something that happened on an order that counts for or against a user
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReputationKind {
+    /// The item changed hands with a valid handoff code
+    OrderCompleted,
+    /// An admin ruled against the user in a dispute
+    DisputeLost,
+}
+
+impl ReputationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReputationKind::OrderCompleted => "order_completed",
+            ReputationKind::DisputeLost => "dispute_lost",
+        }
+    }
+
+    pub fn points(&self) -> i32 {
+        match self {
+            ReputationKind::OrderCompleted => 1,
+            ReputationKind::DisputeLost => -5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReputationResponse {
+    pub user_id: i32,
+    pub score: i64,
+    pub completed_orders: i64,
+    pub disputes_lost: i64,
+    pub cancellations: i64,
+}
+
+/// Record an event for `user_id`. Each kind counts at most once per order.
+pub async fn record(
+    conn: &mut AsyncPgConnection,
+    user_id: i32,
+    order_id: i32,
+    kind: ReputationKind,
+) -> QueryResult<()> {
+    diesel::insert_into(reputation_events::table)
+        .values(&NewReputationEvent {
+            user_id,
+            order_id: Some(order_id),
+            kind: kind.as_str().to_string(),
+            points: kind.points(),
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await
+        .map(|_| ())
+}
+
+async fn count_kind(
+    conn: &mut AsyncPgConnection,
+    user_id: i32,
+    kind: ReputationKind,
+) -> QueryResult<i64> {
+    reputation_events::table
+        .filter(reputation_events::user_id.eq(user_id))
+        .filter(reputation_events::kind.eq(kind.as_str()))
+        .select(count_star())
+        .first(conn)
+        .await
+}
+
+#[get("/users/<user_id>")]
+pub async fn user_reputation(
+    mut db: DbConn,
+    _auth: AuthenticatedUser,
+    user_id: i32,
+) -> Result<Json<ReputationResponse>, ApiError> {
+    let score: Option<i64> = reputation_events::table
+        .filter(reputation_events::user_id.eq(user_id))
+        // diesel 2.2's `dsl::sum` is an ambiguous glob re-export on newer compilers
+        .select(sql::<Nullable<BigInt>>("SUM(points)"))
+        .first(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+    let cancellations: i64 = orders::table
+        .filter(orders::cancelled_by.eq(user_id))
+        .select(count_star())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+    let completed_orders = count_kind(&mut db, user_id, ReputationKind::OrderCompleted)
+        .await
+        .map_err(ApiError::internal)?;
+    let disputes_lost = count_kind(&mut db, user_id, ReputationKind::DisputeLost)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(ReputationResponse {
+        user_id,
+        score: score.unwrap_or(0),
+        completed_orders,
+        disputes_lost,
+        cancellations,
+    }))
+}
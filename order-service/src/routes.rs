@@ -1,21 +1,48 @@
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{get, post, put};
+use rocket::{get, post, put, FromFormField, State};
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
-use crate::auth::AuthenticatedUser;
+use handshake_common::error::ApiError;
+use handshake_common::telemetry::RequestContext;
+use handshake_common::auth::{AuthenticatedUser, InternalService};
+use crate::catalog::{self, CatalogError, ListingRevision};
 use crate::db::DbConn;
+use crate::events::{self, DomainEvent};
+use crate::metrics;
 use crate::geolocation::{calculate_midpoint, MidpointResult};
-use crate::models::{Location, NewLocation, NewOrder, Order};
+use crate::models::{Location, NewLocation, NewOrder, NewPayment, Order};
 use crate::nominatim::{geocode, reverse_geocode_from_coord, GeocodeResult};
-use crate::schema::{locations, orders};
+use handshake_common::pagination::{clamp_limit, Cursor, Page};
+use crate::payments::{self, PaymentError, Payments};
+use handshake_common::privacy::FuzzConfig;
+use crate::reasons::{clean_note, CancellationReason};
+use crate::schema::{locations, orders, payments as payments_table};
+use crate::settings::Settings;
+use crate::users;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateOrderRequest {
     pub product_id: i32,
-    pub seller_id: i32,
-    pub buyer_location: LocationInput,
+    /// Optional; the seller is taken from the listing and must match if given
+    pub seller_id: Option<i32>,
+    /// Required for listings that need an in-person meetup
+    pub buyer_location: Option<LocationInput>,
+    pub quantity: Option<i32>,
+    /// Provider token for the buyer's card; authorized when the seller accepts
+    pub payment_method: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelOrderRequest {
+    pub reason: CancellationReason,
+    /// Required when the reason is `other`
+    pub note: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -41,9 +68,16 @@ pub struct OrderResponse {
     pub buyer_id: i32,
     pub seller_id: i32,
     pub status: String,
-    pub buyer_location: LocationResponse,
-    pub seller_location: LocationResponse,
-    pub midpoint_info: MidpointResult,
+    pub quantity: i32,
+    pub buyer_location: Option<LocationResponse>,
+    pub seller_location: Option<LocationResponse>,
+    pub midpoint_info: Option<MidpointResult>,
+    pub meetup_starts_at: Option<NaiveDateTime>,
+    pub meetup_ends_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+    pub cancelled_by: Option<i32>,
+    pub cancellation_reason: Option<String>,
+    pub cancellation_note: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -51,6 +85,16 @@ pub struct LocationResponse {
     pub latitude: f64,
     pub longitude: f64,
     pub address: String,
+    /// True when the coordinates were fuzzed to protect the owner's privacy
+    pub approximate: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicLocationResponse {
+    pub user_id: i32,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub approximate: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,235 +108,798 @@ pub struct ReverseGeocodeRequest {
     pub longitude: f64,
 }
 
+/// Whether the buyer and seller may see each other's exact pickup location
+fn reveals_exact_location(status: &str) -> bool {
+    matches!(status, "accepted" | "completed" | "disputed")
+}
+
+/// A location as the viewer is allowed to see it: exact for its owner or
+/// once the order has been accepted, fuzzed for everyone else.
+fn location_for_viewer(
+    location: Location,
+    viewer_id: i32,
+    revealed: bool,
+    fuzz: &FuzzConfig,
+) -> LocationResponse {
+    if location.user_id == viewer_id || revealed || fuzz.is_exact() {
+        return LocationResponse {
+            latitude: location.latitude,
+            longitude: location.longitude,
+            address: location.address,
+            approximate: false,
+        };
+    }
+
+    let (latitude, longitude) = fuzz.fuzz(location.user_id, location.latitude, location.longitude);
+    LocationResponse {
+        latitude,
+        longitude,
+        address: "Approximate area (exact location shared once the order is accepted)".to_string(),
+        approximate: true,
+    }
+}
+
+pub(crate) fn build_order_response(
+    order: Order,
+    buyer_location: Option<Location>,
+    seller_location: Option<Location>,
+    viewer_id: i32,
+    fuzz: &FuzzConfig,
+) -> OrderResponse {
+    let revealed = reveals_exact_location(&order.status);
+    let buyer_location =
+        buyer_location.map(|location| location_for_viewer(location, viewer_id, revealed, fuzz));
+    let seller_location =
+        seller_location.map(|location| location_for_viewer(location, viewer_id, revealed, fuzz));
+
+    // Computed from what the viewer can see so the midpoint can't be used to
+    // work back to the counterparty's exact location
+    let midpoint_info = match (&buyer_location, &seller_location) {
+        (Some(buyer), Some(seller)) => Some(calculate_midpoint(
+            buyer.latitude,
+            buyer.longitude,
+            seller.latitude,
+            seller.longitude,
+        )),
+        _ => None,
+    };
+
+    OrderResponse {
+        id: order.id,
+        product_id: order.product_id,
+        buyer_id: order.buyer_id,
+        seller_id: order.seller_id,
+        status: order.status,
+        quantity: order.quantity,
+        buyer_location,
+        seller_location,
+        midpoint_info,
+        meetup_starts_at: order.meetup_starts_at,
+        meetup_ends_at: order.meetup_ends_at,
+        completed_at: order.completed_at,
+        cancelled_by: order.cancelled_by,
+        cancellation_reason: order.cancellation_reason,
+        cancellation_note: order.cancellation_note,
+    }
+}
+
+pub(crate) async fn load_order_locations(
+    conn: &mut AsyncPgConnection,
+    order: &Order,
+) -> QueryResult<(Option<Location>, Option<Location>)> {
+    let buyer = match order.buyer_location_id {
+        Some(id) => Some(locations::table.find(id).first(conn).await?),
+        None => None,
+    };
+    let seller = match order.seller_location_id {
+        Some(id) => Some(locations::table.find(id).first(conn).await?),
+        None => None,
+    };
+    Ok((buyer, seller))
+}
+
+fn payment_error(err: PaymentError) -> ApiError {
+    match err {
+        PaymentError::Declined(reason) => ApiError::new(
+            Status::PaymentRequired,
+            format!("Payment was declined: {}", reason),
+        )
+        .with_code("payment_declined"),
+        PaymentError::InvalidWebhook(e) => {
+            ApiError::bad_request("Invalid payment notification").with_source(e)
+        }
+        PaymentError::Unreachable(e) => ApiError::upstream("Payment provider", e),
+    }
+}
+
+pub(crate) fn not_a_party() -> ApiError {
+    ApiError::forbidden("Only the buyer and seller can do this")
+}
+
+pub(crate) fn catalog_error(err: CatalogError) -> ApiError {
+    match err {
+        CatalogError::NotFound => ApiError::not_found("Product not found"),
+        CatalogError::Unavailable => {
+            ApiError::conflict("Product is not available in that quantity")
+        }
+        CatalogError::Unreachable(e) => ApiError::upstream("Product service", e),
+    }
+}
+
 #[post("/", data = "<request>")]
 pub async fn create_order(
-    db: DbConn,
+    mut db: DbConn,
+    payment_provider: &State<Payments>,
+    settings: &State<Settings>,
     auth: AuthenticatedUser,
     request: Json<CreateOrderRequest>,
-) -> Result<Json<OrderResponse>, Status> {
+) -> Result<Json<OrderResponse>, ApiError> {
     let buyer_id = auth.user_id;
-    let seller_id = request.seller_id;
     let product_id = request.product_id;
+    let quantity = request.quantity.unwrap_or(1);
     let buyer_loc_input = request.buyer_location.clone();
+    let payment_method = request.payment_method.clone();
+    let provider_name = payment_provider.name().to_string();
 
-    // Create buyer location
-    let buyer_location: Location = db
-        .run(move |conn| {
-            diesel::insert_into(locations::table)
-                .values(&NewLocation {
-                    user_id: buyer_id,
-                    latitude: buyer_loc_input.latitude,
-                    longitude: buyer_loc_input.longitude,
-                    address: buyer_loc_input.address.clone(),
-                })
-                .get_result(conn)
-        })
+    if quantity < 1 {
+        return Err(ApiError::bad_request("quantity must be at least 1"));
+    }
+
+    let product = catalog::fetch_product(settings, product_id)
         .await
-        .map_err(|_| Status::InternalServerError)?;
-
-    // Get or create seller location (default for now)
-    let seller_location: Location = db
-        .run(move |conn| {
-            locations::table
-                .filter(locations::user_id.eq(seller_id))
-                .first(conn)
-                .or_else(|_| {
-                    diesel::insert_into(locations::table)
-                        .values(&NewLocation {
-                            user_id: seller_id,
-                            latitude: -6.2088, // Default Jakarta coordinates
-                            longitude: 106.8456,
-                            address: "Jakarta, Indonesia (Default - seller should update)"
-                                .to_string(),
-                        })
-                        .get_result(conn)
-                })
-        })
+        .map_err(catalog_error)?;
+    let seller_id = product.seller_id;
+    let product_revision = product.revision;
+    let amount = payments::order_amount(product.price, quantity);
+
+    if request.seller_id.is_some_and(|id| id != seller_id) {
+        return Err(ApiError::bad_request("seller_id does not match the listing"));
+    }
+    if seller_id == buyer_id {
+        return Err(ApiError::bad_request("You can't order your own listing"));
+    }
+
+    if product.requires_meetup && buyer_loc_input.is_none() {
+        return Err(ApiError::unprocessable(
+            "buyer_location is required for items handed over in person",
+        ));
+    }
+
+    // Take the units out of stock first so two buyers can't both get the last one
+    catalog::reserve_stock(settings, product_id, quantity)
         .await
-        .map_err(|_| Status::InternalServerError)?;
-
-    // Create the order
-    let buyer_loc_id = buyer_location.id;
-    let seller_loc_id = seller_location.id;
-
-    let order: Order = db
-        .run(move |conn| {
-            diesel::insert_into(orders::table)
-                .values(&NewOrder {
-                    product_id,
-                    buyer_id,
-                    seller_id,
-                    buyer_location_id: buyer_loc_id,
-                    seller_location_id: seller_loc_id,
-                })
-                .get_result(conn)
+        .map_err(catalog_error)?;
+
+    let result = db
+        .transaction(|conn| {
+            async move {
+                let (buyer_location, seller_location) = match buyer_loc_input {
+                    Some(input) => {
+                        let buyer: Location = diesel::insert_into(locations::table)
+                            .values(&NewLocation {
+                                user_id: buyer_id,
+                                latitude: input.latitude,
+                                longitude: input.longitude,
+                                address: input.address,
+                            })
+                            .get_result(conn)
+                            .await?;
+
+                        // Get or create seller location (default for now)
+                        let existing: Option<Location> = locations::table
+                            .filter(locations::user_id.eq(seller_id))
+                            .first(conn)
+                            .await
+                            .optional()?;
+                        let seller: Location = match existing {
+                            Some(seller) => seller,
+                            None => {
+                                diesel::insert_into(locations::table)
+                                    .values(&NewLocation {
+                                        user_id: seller_id,
+                                        latitude: -6.2088, // Default Jakarta coordinates
+                                        longitude: 106.8456,
+                                        address:
+                                            "Jakarta, Indonesia (Default - seller should update)"
+                                                .to_string(),
+                                    })
+                                    .get_result(conn)
+                                    .await?
+                            }
+                        };
+
+                        (Some(buyer), Some(seller))
+                    }
+                    None => (None, None),
+                };
+
+                let order: Order = diesel::insert_into(orders::table)
+                    .values(&NewOrder {
+                        product_id,
+                        buyer_id,
+                        seller_id,
+                        buyer_location_id: buyer_location.as_ref().map(|l| l.id),
+                        seller_location_id: seller_location.as_ref().map(|l| l.id),
+                        quantity,
+                        product_revision: Some(product_revision),
+                    })
+                    .get_result(conn)
+                    .await?;
+
+                diesel::insert_into(payments_table::table)
+                    .values(&NewPayment {
+                        order_id: order.id,
+                        provider: provider_name,
+                        payment_method,
+                        amount,
+                        currency: settings.payment_currency.clone(),
+                    })
+                    .execute(conn)
+                    .await?;
+
+                events::record(conn, &DomainEvent::order_placed(&order)).await?;
+
+                Ok::<_, diesel::result::Error>((order, buyer_location, seller_location))
+            }
+            .scope_boxed()
         })
-        .await
-        .map_err(|_| Status::InternalServerError)?;
-
-    // Calculate midpoint
-    let midpoint_info = calculate_midpoint(
-        buyer_location.latitude,
-        buyer_location.longitude,
-        seller_location.latitude,
-        seller_location.longitude,
-    );
+        .await;
 
-    Ok(Json(OrderResponse {
-        id: order.id,
-        product_id: order.product_id,
-        buyer_id: order.buyer_id,
-        seller_id: order.seller_id,
-        status: order.status,
-        buyer_location: LocationResponse {
-            latitude: buyer_location.latitude,
-            longitude: buyer_location.longitude,
-            address: buyer_location.address,
-        },
-        seller_location: LocationResponse {
-            latitude: seller_location.latitude,
-            longitude: seller_location.longitude,
-            address: seller_location.address,
-        },
-        midpoint_info,
-    }))
+    let (order, buyer_location, seller_location) = match result {
+        Ok(created) => created,
+        Err(e) => {
+            // Give the reserved units back; the order never existed
+            let _ = catalog::release_stock(settings, product_id, quantity).await;
+            return Err(ApiError::internal(e));
+        }
+    };
+    metrics::ORDERS_CREATED.inc();
+
+    Ok(Json(build_order_response(
+        order,
+        buyer_location,
+        seller_location,
+        buyer_id,
+        &settings.fuzz(),
+    )))
 }
 
 #[get("/<id>")]
 pub async fn get_order(
-    db: DbConn,
+    mut db: DbConn,
+    settings: &State<Settings>,
     auth: AuthenticatedUser,
     id: i32,
-) -> Result<Json<OrderResponse>, Status> {
+) -> Result<Json<OrderResponse>, ApiError> {
     let user_id = auth.user_id;
 
-    let order: Order = db
-        .run(move |conn| orders::table.find(id).first(conn))
+    let order: Order = orders::table
+        .find(id)
+        .first(&mut db)
         .await
-        .map_err(|_| Status::NotFound)?;
+        .map_err(ApiError::when_missing(Status::NotFound, "Order not found"))?;
 
     // Verify user is buyer or seller
     if order.buyer_id != user_id && order.seller_id != user_id {
-        return Err(Status::Forbidden);
+        return Err(not_a_party());
     }
 
-    let buyer_loc_id = order.buyer_location_id;
-    let seller_loc_id = order.seller_location_id;
+    let (buyer_location, seller_location) = load_order_locations(&mut db, &order)
+        .await
+        .map_err(ApiError::internal)?;
 
-    let (buyer_location, seller_location): (Location, Location) = db
-        .run(move |conn| {
-            let buyer = locations::table.find(buyer_loc_id).first(conn)?;
-            let seller = locations::table.find(seller_loc_id).first(conn)?;
-            Ok::<_, diesel::result::Error>((buyer, seller))
-        })
+    Ok(Json(build_order_response(
+        order,
+        buyer_location,
+        seller_location,
+        user_id,
+        &settings.fuzz(),
+    )))
+}
+
+/// Seller accepts a pending order, revealing exact pickup locations to both
+/// parties. The buyer's payment is put on hold first; a declined card leaves
+/// the order pending with 402 Payment Required.
+#[post("/<id>/accept")]
+pub async fn accept_order(
+    mut db: DbConn,
+    payment_provider: &State<Payments>,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<OrderResponse>, ApiError> {
+    let user_id = auth.user_id;
+
+    let order: Order = orders::table
+        .find(id)
+        .first(&mut db)
         .await
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(ApiError::when_missing(Status::NotFound, "Order not found"))?;
 
-    let midpoint_info = calculate_midpoint(
-        buyer_location.latitude,
-        buyer_location.longitude,
-        seller_location.latitude,
-        seller_location.longitude,
-    );
+    if order.seller_id != user_id {
+        return Err(ApiError::forbidden("Only the seller can accept this order"));
+    }
 
-    Ok(Json(OrderResponse {
-        id: order.id,
-        product_id: order.product_id,
-        buyer_id: order.buyer_id,
-        seller_id: order.seller_id,
-        status: order.status,
-        buyer_location: LocationResponse {
-            latitude: buyer_location.latitude,
-            longitude: buyer_location.longitude,
-            address: buyer_location.address,
-        },
-        seller_location: LocationResponse {
-            latitude: seller_location.latitude,
-            longitude: seller_location.longitude,
-            address: seller_location.address,
-        },
-        midpoint_info,
-    }))
+    if order.status != "pending" {
+        return Err(ApiError::conflict("Only pending orders can be accepted"));
+    }
+
+    let payment = payments::load_for_order(&mut db, id)
+        .await
+        .map_err(ApiError::internal)?;
+
+    // Orders placed before payments existed have nothing to authorize
+    let payment = match payment {
+        Some(payment) if payment.status != "authorized" => Some(
+            payments::authorize(&mut db, payment_provider.inner().as_ref(), payment)
+                .await
+                .map_err(payment_error)?,
+        ),
+        other => other,
+    };
+
+    let accepted = db
+        .transaction(|conn| {
+            async move {
+                let order: Order = diesel::update(
+                    orders::table
+                        .find(id)
+                        .filter(orders::status.eq("pending")),
+                )
+                .set(orders::status.eq("accepted"))
+                .get_result(conn)
+                .await?;
+                events::record_status_change(conn, &order).await?;
+                let (buyer, seller) = load_order_locations(conn, &order).await?;
+                Ok::<_, diesel::result::Error>((order, buyer, seller))
+            }
+            .scope_boxed()
+        })
+        .await;
+
+    let (order, buyer_location, seller_location) = match accepted {
+        Ok(accepted) => accepted,
+        Err(_) => {
+            // Lost a race with a cancellation; don't leave the buyer's funds held
+            if let Some(payment) = payment {
+                let _ = payments::release(
+                    &mut db,
+                    payment_provider.inner().as_ref(),
+                    payment,
+                    "order no longer pending",
+                )
+                .await;
+            }
+            return Err(ApiError::conflict("Only pending orders can be accepted"));
+        }
+    };
+
+    Ok(Json(build_order_response(
+        order,
+        buyer_location,
+        seller_location,
+        user_id,
+        &settings.fuzz(),
+    )))
 }
 
-#[get("/my-orders")]
-pub async fn my_orders(db: DbConn, auth: AuthenticatedUser) -> Result<Json<Vec<Order>>, Status> {
+/// Either party backs out before the handoff, giving a reason code. Any held
+/// or captured payment is returned to the buyer, and product-service puts the
+/// stock back on the listing when it sees the cancellation on the event bus.
+#[post("/<id>/cancel", data = "<request>")]
+pub async fn cancel_order(
+    mut db: DbConn,
+    payment_provider: &State<Payments>,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<CancelOrderRequest>,
+) -> Result<Json<OrderResponse>, ApiError> {
     let user_id = auth.user_id;
+    let reason = request.reason;
+    let note = clean_note(request.note.as_deref(), reason == CancellationReason::Other)?;
+
+    let order: Order = orders::table
+        .find(id)
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Order not found"))?;
+
+    if order.buyer_id != user_id && order.seller_id != user_id {
+        return Err(not_a_party());
+    }
 
-    let user_orders: Vec<Order> = db
-        .run(move |conn| {
-            orders::table
-                .filter(
-                    orders::buyer_id
-                        .eq(user_id)
-                        .or(orders::seller_id.eq(user_id)),
+    let (order, buyer_location, seller_location, payment) = db
+        .transaction(|conn| {
+            async move {
+                let order: Order = diesel::update(
+                    orders::table
+                        .find(id)
+                        .filter(orders::status.eq_any(["pending", "accepted"])),
                 )
-                .order(orders::created_at.desc())
-                .load(conn)
+                .set((
+                    orders::status.eq("cancelled"),
+                    orders::cancelled_at.eq(Some(chrono::Utc::now().naive_utc())),
+                    orders::cancelled_by.eq(Some(user_id)),
+                    orders::cancellation_reason.eq(Some(reason.as_str())),
+                    orders::cancellation_note.eq(note),
+                ))
+                .get_result(conn)
+                .await?;
+                events::record_status_change(conn, &order).await?;
+                let (buyer, seller) = load_order_locations(conn, &order).await?;
+                let payment = payments::load_for_order(conn, id).await?;
+                Ok::<_, diesel::result::Error>((order, buyer, seller, payment))
+            }
+            .scope_boxed()
         })
         .await
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(ApiError::when_missing(
+            Status::Conflict,
+            "Only pending or accepted orders can be cancelled",
+        ))?;
+
+    if let Some(payment) = payment {
+        if let Err(e) = payments::release(
+            &mut db,
+            payment_provider.inner().as_ref(),
+            payment,
+            reason.as_str(),
+        )
+        .await
+        {
+            tracing::error!(order_id = order.id, error = %e, "Failed to return payment");
+        }
+    }
+
+    Ok(Json(build_order_response(
+        order,
+        buyer_location,
+        seller_location,
+        user_id,
+        &settings.fuzz(),
+    )))
+}
+
+/// The listing as it was when the order was placed, so both parties can see
+/// what was agreed even if the seller has since edited or deleted it
+#[get("/<id>/listing")]
+pub async fn get_order_listing(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<ListingRevision>, ApiError> {
+    let user_id = auth.user_id;
+
+    let order: Order = orders::table
+        .find(id)
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Order not found"))?;
+
+    if order.buyer_id != user_id && order.seller_id != user_id {
+        return Err(not_a_party());
+    }
+
+    catalog::fetch_revision(settings, order.product_id, order.product_revision, order.created_at)
+        .await
+        .map(Json)
+        .map_err(catalog_error)
+}
+
+/// Which side of an order the current user is on
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, FromFormField)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderRole {
+    Buyer,
+    Seller,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderListItem {
+    #[serde(flatten)]
+    pub order: OrderResponse,
+    pub created_at: NaiveDateTime,
+    pub role: OrderRole,
+    pub product_title: Option<String>,
+    pub product_thumbnail: Option<String>,
+    pub counterparty_id: i32,
+    pub counterparty_name: Option<String>,
+}
+
+/// Parse a `from`/`to` filter given as a date (`2026-10-18`) or a date-time.
+/// A bare date used as an upper bound covers that whole day.
+fn parse_date_bound(value: &str, end_of_day: bool) -> Option<NaiveDateTime> {
+    if let Ok(datetime) = value.parse::<NaiveDateTime>() {
+        return Some(datetime);
+    }
+
+    let date = value.parse::<NaiveDate>().ok()?;
+    if end_of_day {
+        date.and_hms_micro_opt(23, 59, 59, 999_999)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    }
+}
+
+#[get("/my-orders?<role>&<status>&<from>&<to>&<limit>&<cursor>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn my_orders(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    role: Option<OrderRole>,
+    status: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+) -> Result<Json<Page<OrderListItem>>, ApiError> {
+    let user_id = auth.user_id;
+    let limit = clamp_limit(limit);
+    let after = match cursor {
+        Some(ref value) => Some(Cursor::<NaiveDateTime>::decode(value)
+            .ok_or_else(|| ApiError::bad_request("Invalid cursor"))?),
+        None => None,
+    };
+    let from = match from {
+        Some(ref value) => Some(parse_date_bound(value, false)
+            .ok_or_else(|| ApiError::bad_request("from must be a date or timestamp"))?),
+        None => None,
+    };
+    let to = match to {
+        Some(ref value) => Some(parse_date_bound(value, true)
+            .ok_or_else(|| ApiError::bad_request("to must be a date or timestamp"))?),
+        None => None,
+    };
+
+    let filtered = || {
+        let mut query = orders::table.into_boxed();
+
+        query = match role {
+            Some(OrderRole::Buyer) => query.filter(orders::buyer_id.eq(user_id)),
+            Some(OrderRole::Seller) => query.filter(orders::seller_id.eq(user_id)),
+            None => query.filter(
+                orders::buyer_id
+                    .eq(user_id)
+                    .or(orders::seller_id.eq(user_id)),
+            ),
+        };
+        if let Some(ref status) = status {
+            query = query.filter(orders::status.eq(status.clone()));
+        }
+        if let Some(from) = from {
+            query = query.filter(orders::created_at.ge(from));
+        }
+        if let Some(to) = to {
+            query = query.filter(orders::created_at.le(to));
+        }
+
+        query
+    };
+
+    let total: i64 = filtered()
+        .count()
+        .get_result(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let mut query = filtered();
+    if let Some(c) = after {
+        query = query.filter(
+            orders::created_at
+                .lt(c.key)
+                .or(orders::created_at.eq(c.key).and(orders::id.lt(c.id))),
+        );
+    }
+
+    let rows: Vec<Order> = query
+        .order((orders::created_at.desc(), orders::id.desc()))
+        .limit(limit + 1)
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let page = Page::from_rows(rows, limit, total, |order| {
+        Cursor::new(order.created_at, order.id).encode()
+    });
+
+    // All locations on the page in one query
+    let location_ids: Vec<i32> = page
+        .items
+        .iter()
+        .flat_map(|o| [o.buyer_location_id, o.seller_location_id])
+        .flatten()
+        .collect();
+    let locations: HashMap<i32, Location> = locations::table
+        .filter(locations::id.eq_any(location_ids))
+        .load::<Location>(&mut db)
+        .await
+        .map_err(ApiError::internal)?
+        .into_iter()
+        .map(|l| (l.id, l))
+        .collect();
 
-    Ok(Json(user_orders))
+    // One batched call per service for the whole page
+    let mut product_ids: Vec<i32> = page.items.iter().map(|o| o.product_id).collect();
+    product_ids.sort_unstable();
+    product_ids.dedup();
+    let mut counterparty_ids: Vec<i32> = page
+        .items
+        .iter()
+        .map(|o| if o.buyer_id == user_id { o.seller_id } else { o.buyer_id })
+        .collect();
+    counterparty_ids.sort_unstable();
+    counterparty_ids.dedup();
+
+    let (products, names) = rocket::tokio::join!(
+        catalog::fetch_summaries(settings, &product_ids),
+        users::fetch_names(settings, &counterparty_ids),
+    );
+    // Listings still render without the extra details if a service is down
+    let products = products.unwrap_or_else(|e| {
+        tracing::warn!(error = ?e, "Could not fetch product summaries");
+        HashMap::new()
+    });
+    let names = names.unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "Could not fetch counterparty names");
+        HashMap::new()
+    });
+    let fuzz = settings.fuzz();
+
+    Ok(Json(page.map(|order| {
+        let role = if order.buyer_id == user_id {
+            OrderRole::Buyer
+        } else {
+            OrderRole::Seller
+        };
+        let counterparty_id = match role {
+            OrderRole::Buyer => order.seller_id,
+            OrderRole::Seller => order.buyer_id,
+        };
+        let product = products.get(&order.product_id);
+        let created_at = order.created_at;
+        let buyer_location = order
+            .buyer_location_id
+            .and_then(|id| locations.get(&id).cloned());
+        let seller_location = order
+            .seller_location_id
+            .and_then(|id| locations.get(&id).cloned());
+
+        OrderListItem {
+            order: build_order_response(order, buyer_location, seller_location, user_id, &fuzz),
+            created_at,
+            role,
+            product_title: product.map(|p| p.title.clone()),
+            product_thumbnail: product.and_then(|p| p.image_url.clone()),
+            counterparty_id,
+            counterparty_name: names.get(&counterparty_id).cloned(),
+        }
+    })))
 }
 
 #[post("/address", data = "<request>")]
-pub async fn geocode_address(request: Json<GeocodeRequest>) -> Result<Json<GeocodeResult>, Status> {
-    geocode(&request.address)
+pub async fn geocode_address(
+    ctx: RequestContext,
+    settings: &State<Settings>,
+    request: Json<GeocodeRequest>,
+) -> Result<Json<GeocodeResult>, ApiError> {
+    geocode(&ctx, settings, &request.address)
         .await
         .map(Json)
-        .map_err(|_| Status::NotFound)
+        .map_err(|e| ApiError::not_found("Address not found").with_source(e))
 }
 
 #[post("/reverse", data = "<request>")]
 pub async fn reverse_geocode(
+    ctx: RequestContext,
+    settings: &State<Settings>,
     request: Json<ReverseGeocodeRequest>,
-) -> Result<Json<GeocodeResult>, Status> {
-    reverse_geocode_from_coord(request.latitude, request.longitude)
+) -> Result<Json<GeocodeResult>, ApiError> {
+    reverse_geocode_from_coord(&ctx, settings, request.latitude, request.longitude)
         .await
         .map(Json)
-        .map_err(|_| Status::NotFound)
+        .map_err(|e| ApiError::not_found("No address at that location").with_source(e))
+}
+
+#[get("/me")]
+pub async fn get_my_location(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+) -> Result<Json<LocationUpsertResponse>, ApiError> {
+    let user_id = auth.user_id;
+
+    let location: Location = locations::table
+        .filter(locations::user_id.eq(user_id))
+        .order(locations::id.desc())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "No saved location"))?;
+
+    Ok(Json(LocationUpsertResponse {
+        id: location.id,
+        user_id: location.user_id,
+        latitude: location.latitude,
+        longitude: location.longitude,
+        address: location.address,
+    }))
+}
+
+/// Fuzzed location of a user, for product-service to show a seller's area on
+/// their listings. Internal only: it would otherwise place any user, buyers
+/// included, to within the fuzz radius.
+#[get("/users/<user_id>")]
+pub async fn get_public_location(
+    mut db: DbConn,
+    _service: InternalService,
+    settings: &State<Settings>,
+    user_id: i32,
+) -> Result<Json<PublicLocationResponse>, ApiError> {
+    let location: Location = locations::table
+        .filter(locations::user_id.eq(user_id))
+        .order(locations::id.desc())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "No saved location"))?;
+
+    let fuzz = settings.fuzz();
+    let (latitude, longitude) = fuzz.fuzz(user_id, location.latitude, location.longitude);
+
+    Ok(Json(PublicLocationResponse {
+        user_id,
+        latitude,
+        longitude,
+        approximate: !fuzz.is_exact(),
+    }))
 }
 
 #[put("/me", data = "<request>")]
 pub async fn upsert_my_location(
-    db: DbConn,
+    mut db: DbConn,
     auth: AuthenticatedUser,
     request: Json<LocationInput>,
-) -> Result<Json<LocationUpsertResponse>, Status> {
+) -> Result<Json<LocationUpsertResponse>, ApiError> {
     let user_id = auth.user_id;
     let input = request.into_inner();
 
     let location: Location = db
-        .run(move |conn| {
-            // Try update first
-            let updated = diesel::update(locations::table.filter(locations::user_id.eq(user_id)))
-                .set((
-                    locations::latitude.eq(input.latitude),
-                    locations::longitude.eq(input.longitude),
-                    locations::address.eq(input.address.clone()),
-                ))
-                .execute(conn)?;
-
-            if updated > 0 {
-                // Fetch the updated row
-                locations::table
-                    .filter(locations::user_id.eq(user_id))
-                    .first::<Location>(conn)
-            } else {
-                // Insert if no existing row
-                diesel::insert_into(locations::table)
-                    .values(&NewLocation {
-                        user_id,
-                        latitude: input.latitude,
-                        longitude: input.longitude,
-                        address: input.address,
-                    })
-                    .get_result::<Location>(conn)
+        .transaction(|conn| {
+            async move {
+                // Try update first
+                let updated =
+                    diesel::update(locations::table.filter(locations::user_id.eq(user_id)))
+                        .set((
+                            locations::latitude.eq(input.latitude),
+                            locations::longitude.eq(input.longitude),
+                            locations::address.eq(input.address.clone()),
+                        ))
+                        .execute(conn)
+                        .await?;
+
+                if updated > 0 {
+                    // Fetch the updated row
+                    locations::table
+                        .filter(locations::user_id.eq(user_id))
+                        .first::<Location>(conn)
+                        .await
+                } else {
+                    // Insert if no existing row
+                    diesel::insert_into(locations::table)
+                        .values(&NewLocation {
+                            user_id,
+                            latitude: input.latitude,
+                            longitude: input.longitude,
+                            address: input.address,
+                        })
+                        .get_result::<Location>(conn)
+                        .await
+                }
             }
+            .scope_boxed()
         })
         .await
-        .map_err(|_| Status::InternalServerError)?;
+        .map_err(ApiError::internal)?;
 
     Ok(Json(LocationUpsertResponse {
         id: location.id,
@@ -302,3 +909,20 @@ pub async fn upsert_my_location(
         address: location.address,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_bounds_cover_whole_days() {
+        let from = parse_date_bound("2026-10-18", false).unwrap();
+        let to = parse_date_bound("2026-10-18", true).unwrap();
+        assert_eq!(from.to_string(), "2026-10-18 00:00:00");
+        assert_eq!(to.to_string(), "2026-10-18 23:59:59.999999");
+
+        let exact = parse_date_bound("2026-10-18T12:30:00", true).unwrap();
+        assert_eq!(exact.to_string(), "2026-10-18 12:30:00");
+        assert!(parse_date_bound("yesterday", false).is_none());
+    }
+}
@@ -1,5 +1,72 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    availability_windows (id) {
+        id -> Int4,
+        user_id -> Int4,
+        starts_at -> Timestamp,
+        ends_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    dispute_evidence (id) {
+        id -> Int4,
+        dispute_id -> Int4,
+        uploaded_by -> Int4,
+        #[max_length = 500]
+        url -> Varchar,
+        description -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    dispute_messages (id) {
+        id -> Int4,
+        dispute_id -> Int4,
+        author_id -> Int4,
+        body -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    disputes (id) {
+        id -> Int4,
+        order_id -> Int4,
+        raised_by -> Int4,
+        #[max_length = 40]
+        reason -> Varchar,
+        description -> Text,
+        #[max_length = 50]
+        order_status -> Varchar,
+        #[max_length = 20]
+        status -> Varchar,
+        #[max_length = 20]
+        outcome -> Nullable<Varchar>,
+        resolution_note -> Nullable<Text>,
+        resolved_by -> Nullable<Int4>,
+        created_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    handoff_codes (id) {
+        id -> Int4,
+        order_id -> Int4,
+        #[max_length = 8]
+        code -> Varchar,
+        expires_at -> Timestamp,
+        failed_attempts -> Int4,
+        used_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     locations (id) {
         id -> Int4,
@@ -10,18 +77,129 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    meetup_proposals (id) {
+        id -> Int4,
+        order_id -> Int4,
+        proposed_by -> Int4,
+        starts_at -> Timestamp,
+        ends_at -> Timestamp,
+        #[max_length = 20]
+        status -> Varchar,
+        created_at -> Timestamp,
+        responded_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     orders (id) {
         id -> Int4,
         product_id -> Int4,
         buyer_id -> Int4,
         seller_id -> Int4,
-        buyer_location_id -> Int4,
-        seller_location_id -> Int4,
+        buyer_location_id -> Nullable<Int4>,
+        seller_location_id -> Nullable<Int4>,
         #[max_length = 50]
         status -> Varchar,
         created_at -> Timestamp,
+        quantity -> Int4,
+        product_revision -> Nullable<Int4>,
+        meetup_starts_at -> Nullable<Timestamp>,
+        meetup_ends_at -> Nullable<Timestamp>,
+        meetup_reminded_at -> Nullable<Timestamp>,
+        completed_at -> Nullable<Timestamp>,
+        handoff_latitude -> Nullable<Float8>,
+        handoff_longitude -> Nullable<Float8>,
+        cancelled_at -> Nullable<Timestamp>,
+        cancelled_by -> Nullable<Int4>,
+        #[max_length = 40]
+        cancellation_reason -> Nullable<Varchar>,
+        cancellation_note -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    payment_webhooks (id) {
+        id -> Int4,
+        #[max_length = 20]
+        provider -> Varchar,
+        #[max_length = 128]
+        event_id -> Varchar,
+        payment_id -> Nullable<Int4>,
+        #[max_length = 64]
+        event_type -> Varchar,
+        payload -> Jsonb,
+        received_at -> Timestamp,
+        processed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    payments (id) {
+        id -> Int4,
+        order_id -> Int4,
+        #[max_length = 20]
+        provider -> Varchar,
+        #[max_length = 64]
+        reference -> Nullable<Varchar>,
+        #[max_length = 128]
+        provider_transaction_id -> Nullable<Varchar>,
+        #[max_length = 255]
+        payment_method -> Nullable<Varchar>,
+        amount -> Int8,
+        #[max_length = 3]
+        currency -> Varchar,
+        #[max_length = 20]
+        status -> Varchar,
+        attempts -> Int4,
+        failure_reason -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    processed_events (event_id) {
+        #[max_length = 100]
+        event_id -> Varchar,
+        #[max_length = 64]
+        event_type -> Varchar,
+        processed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    reputation_events (id) {
+        id -> Int4,
+        user_id -> Int4,
+        order_id -> Nullable<Int4>,
+        #[max_length = 30]
+        kind -> Varchar,
+        points -> Int4,
+        created_at -> Timestamp,
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(locations, orders,);
+diesel::joinable!(dispute_evidence -> disputes (dispute_id));
+diesel::joinable!(dispute_messages -> disputes (dispute_id));
+diesel::joinable!(disputes -> orders (order_id));
+diesel::joinable!(handoff_codes -> orders (order_id));
+diesel::joinable!(meetup_proposals -> orders (order_id));
+diesel::joinable!(payment_webhooks -> payments (payment_id));
+diesel::joinable!(payments -> orders (order_id));
+diesel::joinable!(reputation_events -> orders (order_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    availability_windows,
+    dispute_evidence,
+    dispute_messages,
+    disputes,
+    handoff_codes,
+    locations,
+    meetup_proposals,
+    orders,
+    payment_webhooks,
+    payments,
+    processed_events,
+    reputation_events,
+);
@@ -0,0 +1,176 @@
+use chrono::Duration;
+use handshake_common::auth::AuthConfig;
+use handshake_common::config::{self, check_positive, check_url, Validate};
+use handshake_common::db::DatabaseConfig;
+use handshake_common::error::FieldError;
+use handshake_common::events::EventSettings;
+use handshake_common::privacy::{FuzzConfig, LocationPrivacy};
+use serde::Deserialize;
+
+use crate::payments::ProviderKind;
+
+/// Everything order-service reads from `Rocket.toml` and the environment,
+/// loaded once in `main` and managed as Rocket state
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub database: DatabaseConfig,
+    #[serde(default = "default_auth_service_url")]
+    pub auth_service_url: String,
+    #[serde(default = "default_product_service_url")]
+    pub product_service_url: String,
+    #[serde(default = "default_email_service_url")]
+    pub email_service_url: String,
+    #[serde(default = "default_nominatim_url")]
+    pub nominatim_url: String,
+    /// Public URL of the frontend, the only origin besides the local dev
+    /// server allowed to call in
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub app_url: Option<String>,
+    #[serde(flatten)]
+    pub location: LocationPrivacy,
+    #[serde(default)]
+    pub payment_provider: ProviderKind,
+    /// ISO 4217 code stored on every payment
+    #[serde(default = "default_payment_currency")]
+    pub payment_currency: String,
+    #[serde(default = "default_midtrans_api_url")]
+    pub midtrans_api_url: String,
+    /// Required when `payment_provider` is `midtrans`
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub midtrans_server_key: Option<String>,
+    /// How long a handoff code can be used after it is issued
+    #[serde(default = "default_handoff_code_ttl_minutes")]
+    pub handoff_code_ttl_minutes: i64,
+    /// How far ahead of a meetup both parties are reminded
+    #[serde(default = "default_meetup_reminder_hours")]
+    pub meetup_reminder_hours: i64,
+    #[serde(default = "default_meetup_reminder_interval_secs")]
+    pub meetup_reminder_interval_secs: u64,
+    /// How often captures that failed at handoff are retried
+    #[serde(default = "default_payment_capture_interval_secs")]
+    pub payment_capture_interval_secs: u64,
+    #[serde(flatten)]
+    pub events: EventSettings,
+    #[serde(flatten)]
+    pub auth: AuthConfig,
+}
+
+fn default_auth_service_url() -> String {
+    "http://localhost:8001".to_string()
+}
+
+fn default_product_service_url() -> String {
+    "http://localhost:8002".to_string()
+}
+
+fn default_email_service_url() -> String {
+    "http://localhost:8004".to_string()
+}
+
+fn default_nominatim_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_payment_currency() -> String {
+    "IDR".to_string()
+}
+
+fn default_midtrans_api_url() -> String {
+    "https://api.sandbox.midtrans.com".to_string()
+}
+
+fn default_handoff_code_ttl_minutes() -> i64 {
+    15
+}
+
+fn default_meetup_reminder_hours() -> i64 {
+    24
+}
+
+fn default_meetup_reminder_interval_secs() -> u64 {
+    300
+}
+
+fn default_payment_capture_interval_secs() -> u64 {
+    600
+}
+
+impl Settings {
+    /// Sent as `X-Internal-Token` on calls to auth- and product-service
+    pub fn internal_token(&self) -> &str {
+        self.auth.internal_api_token.as_deref().unwrap_or_default()
+    }
+
+    pub fn handoff_code_ttl(&self) -> Duration {
+        Duration::minutes(self.handoff_code_ttl_minutes)
+    }
+
+    pub fn fuzz(&self) -> FuzzConfig {
+        self.location.fuzz(&self.auth)
+    }
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = self.auth.validate();
+        problems.extend(self.database.validate());
+        problems.extend(check_url("auth_service_url", &self.auth_service_url));
+        problems.extend(check_url("product_service_url", &self.product_service_url));
+        problems.extend(check_url("email_service_url", &self.email_service_url));
+        problems.extend(check_url("nominatim_url", &self.nominatim_url));
+        if let Some(app_url) = &self.app_url {
+            problems.extend(check_url("app_url", app_url));
+        }
+        problems.extend(self.location.validate());
+        problems.extend(check_url("midtrans_api_url", &self.midtrans_api_url));
+        match self.payment_provider {
+            ProviderKind::Fake if !cfg!(debug_assertions) => problems.push(FieldError::new(
+                "payment_provider",
+                "is the fake provider; set PAYMENT_PROVIDER=midtrans",
+            )),
+            ProviderKind::Midtrans
+                if self
+                    .midtrans_server_key
+                    .as_deref()
+                    .unwrap_or_default()
+                    .is_empty() =>
+            {
+                problems.push(FieldError::new(
+                    "midtrans_server_key",
+                    "is required when payment_provider is midtrans",
+                ))
+            }
+            _ => {}
+        }
+        if self.payment_currency.len() != 3
+            || !self
+                .payment_currency
+                .bytes()
+                .all(|b| b.is_ascii_uppercase())
+        {
+            problems.push(FieldError::new(
+                "payment_currency",
+                "must be a three-letter ISO 4217 code",
+            ));
+        }
+        problems.extend(check_positive(
+            "handoff_code_ttl_minutes",
+            self.handoff_code_ttl_minutes,
+        ));
+        problems.extend(check_positive(
+            "meetup_reminder_hours",
+            self.meetup_reminder_hours,
+        ));
+        problems.extend(check_positive(
+            "meetup_reminder_interval_secs",
+            self.meetup_reminder_interval_secs,
+        ));
+        problems.extend(check_positive(
+            "payment_capture_interval_secs",
+            self.payment_capture_interval_secs,
+        ));
+        problems.extend(self.events.validate());
+        problems
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::settings::Settings;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserContact {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LookupRequest<'a> {
+    ids: &'a [i32],
+}
+
+/// Names and emails for a batch of users from auth-service, keyed by id
+pub async fn fetch_contacts(
+    settings: &Settings,
+    ids: &[i32],
+) -> Result<HashMap<i32, UserContact>, String> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/users/lookup", settings.auth_service_url))
+        .header("X-Internal-Token", settings.internal_token())
+        .json(&LookupRequest { ids })
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Auth service returned error ({})", response.status()));
+    }
+
+    let users: Vec<UserContact> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(users.into_iter().map(|u| (u.id, u)).collect())
+}
+
+/// Display names for a batch of users, keyed by id
+pub async fn fetch_names(settings: &Settings, ids: &[i32]) -> Result<HashMap<i32, String>, String> {
+    let contacts = fetch_contacts(settings, ids).await?;
+    Ok(contacts.into_iter().map(|(id, c)| (id, c.name)).collect())
+}
@@ -0,0 +1,8 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_moderation_actions_product;
+DROP INDEX IF EXISTS idx_product_reports_status;
+DROP INDEX IF EXISTS idx_product_reports_product;
+
+-- Drop tables
+DROP TABLE IF EXISTS moderation_actions;
+DROP TABLE IF EXISTS product_reports;
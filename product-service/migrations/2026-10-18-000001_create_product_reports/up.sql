@@ -0,0 +1,28 @@
+-- Create product reports table
+CREATE TABLE product_reports (
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    reporter_id INTEGER NOT NULL,
+    reason VARCHAR(50) NOT NULL,
+    details TEXT,
+    status VARCHAR(50) NOT NULL DEFAULT 'open',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP,
+    resolved_by INTEGER,
+    UNIQUE (product_id, reporter_id)
+);
+
+-- Create moderation audit trail table
+CREATE TABLE moderation_actions (
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    moderator_id INTEGER,
+    action VARCHAR(50) NOT NULL,
+    note TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes
+CREATE INDEX idx_product_reports_product ON product_reports(product_id);
+CREATE INDEX idx_product_reports_status ON product_reports(status);
+CREATE INDEX idx_moderation_actions_product ON moderation_actions(product_id);
@@ -0,0 +1,6 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_favorites_product;
+DROP INDEX IF EXISTS idx_favorites_user;
+
+-- Drop tables
+DROP TABLE IF EXISTS favorites;
@@ -0,0 +1,6 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_saved_searches_active;
+DROP INDEX IF EXISTS idx_saved_searches_user;
+
+-- Drop tables
+DROP TABLE IF EXISTS saved_searches;
@@ -0,0 +1,23 @@
+-- Create saved searches table
+CREATE TABLE saved_searches (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    user_email VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    category_slug VARCHAR(100),
+    keywords VARCHAR(255),
+    min_price DOUBLE PRECISION,
+    max_price DOUBLE PRECISION,
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION,
+    max_distance_km DOUBLE PRECISION,
+    frequency VARCHAR(20) NOT NULL DEFAULT 'daily',
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    unsubscribe_token VARCHAR(64) UNIQUE NOT NULL,
+    last_notified_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes
+CREATE INDEX idx_saved_searches_user ON saved_searches(user_id);
+CREATE INDEX idx_saved_searches_active ON saved_searches(active);
@@ -0,0 +1,7 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_products_geohash;
+
+-- Drop columns
+ALTER TABLE products DROP COLUMN IF EXISTS geohash;
+ALTER TABLE products DROP COLUMN IF EXISTS longitude;
+ALTER TABLE products DROP COLUMN IF EXISTS latitude;
@@ -0,0 +1,7 @@
+-- Add approximate pickup location to products
+ALTER TABLE products ADD COLUMN latitude DOUBLE PRECISION;
+ALTER TABLE products ADD COLUMN longitude DOUBLE PRECISION;
+ALTER TABLE products ADD COLUMN geohash VARCHAR(12);
+
+-- Prefix searches on geohash need the pattern operator class
+CREATE INDEX idx_products_geohash ON products(geohash varchar_pattern_ops);
@@ -0,0 +1,9 @@
+-- Allow categories to be nested and ordered
+ALTER TABLE categories ADD COLUMN parent_id INTEGER REFERENCES categories(id) ON DELETE RESTRICT;
+ALTER TABLE categories ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
+
+-- Keep the seeded categories in their original order
+UPDATE categories SET sort_order = id;
+
+-- Create indexes
+CREATE INDEX idx_categories_parent ON categories(parent_id);
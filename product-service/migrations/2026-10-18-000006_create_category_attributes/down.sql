@@ -0,0 +1,9 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_products_attributes;
+DROP INDEX IF EXISTS idx_category_attributes_category;
+
+-- Drop columns
+ALTER TABLE products DROP COLUMN IF EXISTS attributes;
+
+-- Drop tables
+DROP TABLE IF EXISTS category_attributes;
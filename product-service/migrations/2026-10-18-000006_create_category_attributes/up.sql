@@ -0,0 +1,33 @@
+-- Create category attribute schema table
+CREATE TABLE category_attributes (
+    id SERIAL PRIMARY KEY,
+    category_id INTEGER NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+    key VARCHAR(50) NOT NULL,
+    label VARCHAR(100) NOT NULL,
+    value_type VARCHAR(20) NOT NULL,
+    options JSONB,
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (category_id, key)
+);
+
+-- Store validated attribute values on products
+ALTER TABLE products ADD COLUMN attributes JSONB NOT NULL DEFAULT '{}';
+
+-- Insert default attribute schemas
+INSERT INTO category_attributes (category_id, key, label, value_type, options, required, sort_order)
+SELECT id, 'brand', 'Brand', 'text', NULL, FALSE, 1 FROM categories WHERE slug = 'electronics'
+UNION ALL
+SELECT id, 'model', 'Model', 'text', NULL, FALSE, 2 FROM categories WHERE slug = 'electronics'
+UNION ALL
+SELECT id, 'storage_gb', 'Storage (GB)', 'number', NULL, FALSE, 3 FROM categories WHERE slug = 'electronics'
+UNION ALL
+SELECT id, 'size', 'Size', 'enum', '["XS", "S", "M", "L", "XL", "XXL"]', FALSE, 1 FROM categories WHERE slug = 'fashion'
+UNION ALL
+SELECT id, 'color', 'Color', 'text', NULL, FALSE, 2 FROM categories WHERE slug = 'fashion'
+UNION ALL
+SELECT id, 'author', 'Author', 'text', NULL, FALSE, 1 FROM categories WHERE slug = 'books';
+
+-- Create indexes
+CREATE INDEX idx_category_attributes_category ON category_attributes(category_id);
+CREATE INDEX idx_products_attributes ON products USING GIN (attributes jsonb_path_ops);
@@ -0,0 +1,8 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_products_listing_type;
+DROP INDEX IF EXISTS idx_products_condition;
+
+-- Drop columns
+ALTER TABLE products DROP COLUMN IF EXISTS listing_type;
+ALTER TABLE products DROP COLUMN IF EXISTS quantity;
+ALTER TABLE products DROP COLUMN IF EXISTS condition;
@@ -0,0 +1,14 @@
+-- Add item condition, stock quantity and listing type to products
+ALTER TABLE products ADD COLUMN condition VARCHAR(20) NOT NULL DEFAULT 'used';
+ALTER TABLE products ADD COLUMN quantity INTEGER NOT NULL DEFAULT 1 CHECK (quantity >= 0);
+ALTER TABLE products ADD COLUMN listing_type VARCHAR(20) NOT NULL DEFAULT 'physical';
+
+-- Existing listings in the non-physical categories don't need a meetup
+UPDATE products SET listing_type = 'service'
+WHERE category_id IN (SELECT id FROM categories WHERE slug = 'services');
+UPDATE products SET listing_type = 'digital'
+WHERE category_id IN (SELECT id FROM categories WHERE slug = 'digital-products');
+
+-- Create indexes
+CREATE INDEX idx_products_condition ON products(condition);
+CREATE INDEX idx_products_listing_type ON products(listing_type);
@@ -0,0 +1,11 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_products_status_expires_at;
+
+-- Drop constraints
+ALTER TABLE products DROP CONSTRAINT IF EXISTS products_status_check;
+
+-- Drop columns
+ALTER TABLE products DROP COLUMN IF EXISTS renew_token;
+ALTER TABLE products DROP COLUMN IF EXISTS expiry_reminded_at;
+ALTER TABLE products DROP COLUMN IF EXISTS expires_at;
+ALTER TABLE products DROP COLUMN IF EXISTS seller_email;
@@ -0,0 +1,14 @@
+-- Listings expire after a while unless the seller renews them
+ALTER TABLE products ADD COLUMN seller_email VARCHAR(255);
+ALTER TABLE products ADD COLUMN expires_at TIMESTAMP NOT NULL DEFAULT (NOW() + INTERVAL '30 days');
+ALTER TABLE products ADD COLUMN expiry_reminded_at TIMESTAMP;
+ALTER TABLE products ADD COLUMN renew_token VARCHAR(64) UNIQUE;
+
+-- Only known statuses from here on
+UPDATE products SET status = 'active'
+WHERE status NOT IN ('draft', 'active', 'reserved', 'sold', 'expired', 'removed', 'hidden');
+ALTER TABLE products ADD CONSTRAINT products_status_check
+    CHECK (status IN ('draft', 'active', 'reserved', 'sold', 'expired', 'removed', 'hidden'));
+
+-- Create indexes
+CREATE INDEX idx_products_status_expires_at ON products(status, expires_at);
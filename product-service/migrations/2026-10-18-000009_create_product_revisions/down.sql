@@ -0,0 +1,10 @@
+-- Drop indexes
+DROP INDEX IF EXISTS idx_products_deleted_at;
+DROP INDEX IF EXISTS idx_product_revisions_product_id;
+
+-- Drop tables
+DROP TABLE IF EXISTS product_revisions;
+
+-- Drop columns
+ALTER TABLE products DROP COLUMN IF EXISTS revision;
+ALTER TABLE products DROP COLUMN IF EXISTS deleted_at;
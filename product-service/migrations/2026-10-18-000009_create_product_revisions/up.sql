@@ -0,0 +1,37 @@
+-- Products are soft-deleted so orders can keep referring to them
+ALTER TABLE products ADD COLUMN deleted_at TIMESTAMP;
+ALTER TABLE products ADD COLUMN revision INTEGER NOT NULL DEFAULT 1;
+
+-- Create product revisions table
+CREATE TABLE product_revisions (
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    editor_id INTEGER NOT NULL,
+    changed_fields JSONB NOT NULL DEFAULT '[]',
+    snapshot JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE(product_id, revision)
+);
+
+-- Existing listings start their history as they are now
+INSERT INTO product_revisions (product_id, revision, editor_id, snapshot, created_at)
+SELECT id, 1, seller_id, jsonb_build_object(
+    'category_id', category_id,
+    'title', title,
+    'description', description,
+    'price', price,
+    'image_url', image_url,
+    'status', status,
+    'attributes', attributes,
+    'condition', condition,
+    'quantity', quantity,
+    'listing_type', listing_type,
+    'latitude', latitude,
+    'longitude', longitude
+), created_at
+FROM products;
+
+-- Create indexes
+CREATE INDEX idx_product_revisions_product_id ON product_revisions(product_id, created_at);
+CREATE INDEX idx_products_deleted_at ON products(deleted_at);
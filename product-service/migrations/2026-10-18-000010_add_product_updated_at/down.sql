@@ -0,0 +1,6 @@
+-- Drop triggers
+DROP TRIGGER IF EXISTS products_set_updated_at ON products;
+DROP FUNCTION IF EXISTS set_products_updated_at();
+
+-- Drop columns
+ALTER TABLE products DROP COLUMN IF EXISTS updated_at;
@@ -0,0 +1,15 @@
+-- Track the last modification of each listing for optimistic concurrency
+ALTER TABLE products ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+UPDATE products SET updated_at = created_at;
+
+-- Any write to a listing, by a seller or a background job, moves updated_at
+CREATE OR REPLACE FUNCTION set_products_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = clock_timestamp();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER products_set_updated_at
+    BEFORE UPDATE ON products
+    FOR EACH ROW EXECUTE FUNCTION set_products_updated_at();
@@ -0,0 +1,2 @@
+-- Drop columns
+ALTER TABLE saved_searches DROP COLUMN IF EXISTS notified_through;
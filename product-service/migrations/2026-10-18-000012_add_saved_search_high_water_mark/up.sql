@@ -0,0 +1,3 @@
+-- Newest listing already sent in a digest, so the next digest starts after it
+ALTER TABLE saved_searches ADD COLUMN notified_through TIMESTAMP NOT NULL DEFAULT NOW();
+UPDATE saved_searches SET notified_through = last_notified_at;
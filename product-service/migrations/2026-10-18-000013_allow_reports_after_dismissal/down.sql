@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_product_reports_open_reporter;
+DELETE FROM product_reports a USING product_reports b
+    WHERE a.product_id = b.product_id AND a.reporter_id = b.reporter_id AND a.id < b.id;
+ALTER TABLE product_reports ADD CONSTRAINT product_reports_product_id_reporter_id_key UNIQUE (product_id, reporter_id);
@@ -0,0 +1,4 @@
+-- A reporter may have one open report per listing; once it is resolved
+-- they can report the listing again
+ALTER TABLE product_reports DROP CONSTRAINT product_reports_product_id_reporter_id_key;
+CREATE UNIQUE INDEX idx_product_reports_open_reporter ON product_reports(product_id, reporter_id) WHERE status = 'open';
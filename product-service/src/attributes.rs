@@ -0,0 +1,206 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::models::CategoryAttribute;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    Text,
+    Number,
+    Boolean,
+    Enum,
+}
+
+impl ValueType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(ValueType::Text),
+            "number" => Some(ValueType::Number),
+            "boolean" => Some(ValueType::Boolean),
+            "enum" => Some(ValueType::Enum),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttributeError {
+    pub key: String,
+    pub message: String,
+}
+
+impl AttributeError {
+    fn new(key: &str, message: &str) -> Self {
+        AttributeError {
+            key: key.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+fn enum_options(attribute: &CategoryAttribute) -> Vec<&str> {
+    attribute
+        .options
+        .as_ref()
+        .and_then(|o| o.as_array())
+        .map(|o| o.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default()
+}
+
+fn check_value(attribute: &CategoryAttribute, value: &Value) -> Result<Value, &'static str> {
+    match ValueType::parse(&attribute.value_type) {
+        Some(ValueType::Text) => match value.as_str().map(str::trim) {
+            Some(text) if !text.is_empty() => Ok(Value::String(text.to_string())),
+            _ => Err("must be a non-empty string"),
+        },
+        Some(ValueType::Number) => match value.as_f64() {
+            Some(n) if n.is_finite() => Ok(value.clone()),
+            _ => Err("must be a number"),
+        },
+        Some(ValueType::Boolean) => match value {
+            Value::Bool(_) => Ok(value.clone()),
+            _ => Err("must be true or false"),
+        },
+        Some(ValueType::Enum) => match value.as_str() {
+            Some(option) if enum_options(attribute).contains(&option) => Ok(value.clone()),
+            _ => Err("must be one of the allowed options"),
+        },
+        None => Err("has an unsupported type"),
+    }
+}
+
+/// Validate product attribute values against a category's attribute schema.
+/// Returns the normalized values, or every problem found.
+pub fn validate(schema: &[CategoryAttribute], values: &Value) -> Result<Value, Vec<AttributeError>> {
+    let empty = Map::new();
+    let values = match values {
+        Value::Object(map) => map,
+        Value::Null => &empty,
+        _ => return Err(vec![AttributeError::new("attributes", "must be an object")]),
+    };
+
+    let mut errors = Vec::new();
+    let mut normalized = Map::new();
+
+    for key in values.keys() {
+        if !schema.iter().any(|a| &a.key == key) {
+            errors.push(AttributeError::new(key, "is not defined for this category"));
+        }
+    }
+
+    for attribute in schema {
+        match values.get(&attribute.key) {
+            Some(Value::Null) | None => {
+                if attribute.required {
+                    errors.push(AttributeError::new(&attribute.key, "is required"));
+                }
+            }
+            Some(value) => match check_value(attribute, value) {
+                Ok(value) => {
+                    normalized.insert(attribute.key.clone(), value);
+                }
+                Err(message) => errors.push(AttributeError::new(&attribute.key, message)),
+            },
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(Value::Object(normalized))
+    } else {
+        Err(errors)
+    }
+}
+
+/// Build a JSON object for a `@>` containment filter from `attr.<key>=<value>`
+/// query parameters, typing each value according to its attribute definition.
+pub fn filter_object(
+    definitions: &[CategoryAttribute],
+    filters: &HashMap<String, String>,
+) -> Result<Value, Vec<AttributeError>> {
+    let mut object = Map::new();
+    let mut errors = Vec::new();
+
+    for (key, raw) in filters {
+        let Some(attribute) = definitions.iter().find(|a| &a.key == key) else {
+            errors.push(AttributeError::new(key, "is not a known attribute"));
+            continue;
+        };
+
+        let value = match ValueType::parse(&attribute.value_type) {
+            Some(ValueType::Number) => raw
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number),
+            Some(ValueType::Boolean) => raw.parse::<bool>().ok().map(Value::Bool),
+            _ => Some(Value::String(raw.clone())),
+        };
+
+        match value.and_then(|v| check_value(attribute, &v).ok()) {
+            Some(value) => {
+                object.insert(key.clone(), value);
+            }
+            None => errors.push(AttributeError::new(key, "has an invalid filter value")),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(Value::Object(object))
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn attribute(key: &str, value_type: &str, required: bool) -> CategoryAttribute {
+        CategoryAttribute {
+            id: 1,
+            category_id: 1,
+            key: key.to_string(),
+            label: key.to_string(),
+            value_type: value_type.to_string(),
+            options: (value_type == "enum").then(|| json!(["S", "M", "L"])),
+            required,
+            sort_order: 0,
+        }
+    }
+
+    #[test]
+    fn validate_accepts_and_trims_valid_values() {
+        let schema = vec![attribute("brand", "text", true), attribute("size", "enum", false)];
+
+        let result = validate(&schema, &json!({"brand": " Apple ", "size": "M"}));
+        assert_eq!(result, Ok(json!({"brand": "Apple", "size": "M"})));
+    }
+
+    #[test]
+    fn validate_reports_each_problem() {
+        let schema = vec![
+            attribute("brand", "text", true),
+            attribute("storage_gb", "number", false),
+            attribute("size", "enum", false),
+        ];
+
+        let errors = validate(&schema, &json!({"storage_gb": "big", "size": "XXXL", "color": "red"}))
+            .unwrap_err();
+        let keys: Vec<&str> = errors.iter().map(|e| e.key.as_str()).collect();
+
+        assert_eq!(errors.len(), 4);
+        for key in ["brand", "storage_gb", "size", "color"] {
+            assert!(keys.contains(&key));
+        }
+    }
+
+    #[test]
+    fn filter_object_types_values() {
+        let definitions = vec![attribute("storage_gb", "number", false)];
+        let filters = HashMap::from([("storage_gb".to_string(), "256".to_string())]);
+
+        assert_eq!(filter_object(&definitions, &filters), Ok(json!({"storage_gb": 256.0})));
+    }
+}
@@ -0,0 +1,143 @@
+use handshake_common::metrics;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FavoriteAlertType {
+    PriceDrop,
+    Reserved,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FavoriteAlertRequest {
+    pub to_email: String,
+    pub alert_type: FavoriteAlertType,
+    pub product_id: i32,
+    pub product_title: String,
+    pub product_url: String,
+    pub old_price: f64,
+    pub new_price: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigestItem {
+    pub title: String,
+    pub price: f64,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SavedSearchDigestRequest {
+    pub to_email: String,
+    pub search_name: String,
+    pub unsubscribe_url: String,
+    pub products: Vec<DigestItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListingExpiryReminderRequest {
+    pub to_email: String,
+    pub product_title: String,
+    pub product_url: String,
+    pub renew_url: String,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmailServiceResponse {
+    success: bool,
+    message: String,
+}
+
+/// Public URL of a listing on the frontend, used in email links
+pub fn product_url(settings: &Settings, product_id: i32) -> String {
+    format!(
+        "{}/product/{}",
+        settings.frontend_url().trim_end_matches('/'),
+        product_id
+    )
+}
+
+/// One-click unsubscribe link for a saved search, served by this service
+pub fn saved_search_unsubscribe_url(settings: &Settings, token: &str) -> String {
+    format!(
+        "{}/saved-searches/unsubscribe/{}",
+        settings.product_service_url.trim_end_matches('/'),
+        token
+    )
+}
+
+/// One-click renewal link for a listing, served by this service
+pub fn listing_renew_url(settings: &Settings, token: &str) -> String {
+    format!(
+        "{}/products/renew/{}",
+        settings.product_service_url.trim_end_matches('/'),
+        token
+    )
+}
+
+pub async fn send_listing_expiry_reminder(
+    settings: &Settings,
+    request: &ListingExpiryReminderRequest,
+) -> Result<(), String> {
+    post_to_email_service(settings, "send-listing-expiry-reminder", request).await
+}
+
+pub async fn send_saved_search_digest(
+    settings: &Settings,
+    request: &SavedSearchDigestRequest,
+) -> Result<(), String> {
+    post_to_email_service(settings, "send-saved-search-digest", request).await
+}
+
+pub async fn send_favorite_alert(
+    settings: &Settings,
+    request: &FavoriteAlertRequest,
+) -> Result<(), String> {
+    post_to_email_service(settings, "send-favorite-alert", request).await
+}
+
+async fn post_to_email_service<T: Serialize>(
+    settings: &Settings,
+    path: &str,
+    request: &T,
+) -> Result<(), String> {
+    metrics::observe_outbound("email-service", post_json(settings, path, request)).await
+}
+
+async fn post_json<T: Serialize>(settings: &Settings, path: &str, request: &T) -> Result<(), String> {
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/{}", settings.email_service_url, path))
+        .json(request)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to email service: {}", e))?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        let error_body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unable to read error response".to_string());
+        return Err(format!(
+            "Email service returned error ({}): {}",
+            status, error_body
+        ));
+    }
+
+    let email_response: EmailServiceResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse email service response: {}", e))?;
+
+    if !email_response.success {
+        return Err(format!("Email service failed: {}", email_response.message));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use chrono::NaiveDateTime;
+use rocket::http::Header;
+use rocket::request::{self, FromRequest, Request};
+use rocket::outcome::Outcome;
+use rocket::Responder;
+
+/// Entity tag for a listing at a given modification time
+pub fn for_product(id: i32, updated_at: NaiveDateTime) -> String {
+    format!("\"{}-{}\"", id, updated_at.and_utc().timestamp_micros())
+}
+
+/// The request's `If-Match` header, if any
+pub struct IfMatch(Option<String>);
+
+impl IfMatch {
+    /// Whether a write against the entity currently tagged `etag` may proceed
+    pub fn allows(&self, etag: &str) -> bool {
+        match self.0 {
+            None => true,
+            Some(ref header) => header.split(',').map(str::trim).any(|candidate| {
+                candidate == "*" || candidate.trim_start_matches("W/") == etag
+            }),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(IfMatch(
+            request.headers().get_one("If-Match").map(str::to_string),
+        ))
+    }
+}
+
+/// A response carrying an `ETag` header
+#[derive(Responder)]
+pub struct Tagged<R> {
+    inner: R,
+    etag: Header<'static>,
+}
+
+impl<R> Tagged<R> {
+    pub fn new(inner: R, etag: String) -> Self {
+        Tagged {
+            inner,
+            etag: Header::new("ETag", etag),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_match_compares_tags() {
+        let etag = "\"7-1000\"";
+        assert!(IfMatch(None).allows(etag));
+        assert!(IfMatch(Some("*".to_string())).allows(etag));
+        assert!(IfMatch(Some("\"7-999\", \"7-1000\"".to_string())).allows(etag));
+        assert!(IfMatch(Some("W/\"7-1000\"".to_string())).allows(etag));
+        assert!(!IfMatch(Some("\"7-999\"".to_string())).allows(etag));
+    }
+}
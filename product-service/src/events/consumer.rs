@@ -0,0 +1,96 @@
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use rocket::fairing::AdHoc;
+
+use handshake_common::events::consumer::Handler;
+use handshake_common::events::{self, EventSettings};
+
+use super::{claim, record_status_change, DomainEvent, SOURCE};
+use crate::models::Product;
+use crate::routes::stock::return_units;
+use crate::schema::products;
+
+/// Reacts to events published by the other services. Each event's effect is
+/// committed together with its id in `processed_events` before the entry is
+/// acknowledged, so redelivered events are applied only once.
+pub fn consumer(settings: EventSettings) -> AdHoc {
+    events::consumer::consumer(SOURCE, settings, |_| Some(Events))
+}
+
+struct Events;
+
+#[rocket::async_trait]
+impl Handler for Events {
+    async fn handle(
+        &self,
+        conn: &mut AsyncPgConnection,
+        event_id: &str,
+        payload: &str,
+    ) -> Result<(), String> {
+        let event: DomainEvent = match serde_json::from_str(payload) {
+            Ok(event) => event,
+            Err(e) => {
+                // Retrying won't make it readable
+                tracing::warn!(event_id = %event_id, error = %e, "Skipping unreadable event");
+                return Ok(());
+            }
+        };
+
+        match event {
+            DomainEvent::OrderStatusChanged {
+                product_id, status, ..
+            } if status == "completed" => conn
+                .transaction(|conn| {
+                    async {
+                        if claim(conn, event_id, "OrderStatusChanged").await? {
+                            sell_reserved(conn, product_id).await?;
+                        }
+                        Ok::<_, diesel::result::Error>(())
+                    }
+                    .scope_boxed()
+                })
+                .await
+                .map_err(|e| format!("Failed to apply event {}: {}", event_id, e)),
+            DomainEvent::OrderStatusChanged {
+                product_id,
+                quantity,
+                status,
+                ..
+            } if status == "cancelled" => conn
+                .transaction(|conn| {
+                    async {
+                        if claim(conn, event_id, "OrderStatusChanged").await? {
+                            // A listing deleted since has no stock to return to
+                            return_units(conn, product_id, quantity).await.optional()?;
+                        }
+                        Ok::<_, diesel::result::Error>(())
+                    }
+                    .scope_boxed()
+                })
+                .await
+                .map_err(|e| format!("Failed to apply event {}: {}", event_id, e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An order for the listing was handed over. If it held the last units, the
+/// listing is now sold rather than waiting to be released.
+async fn sell_reserved(conn: &mut AsyncPgConnection, product_id: i32) -> QueryResult<()> {
+    let sold: Option<Product> = diesel::update(
+        products::table
+            .find(product_id)
+            .filter(products::status.eq("reserved"))
+            .filter(products::quantity.eq(0)),
+    )
+    .set(products::status.eq("sold"))
+    .get_result(conn)
+    .await
+    .optional()?;
+
+    match sold {
+        Some(product) => record_status_change(conn, "reserved", &product).await,
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,146 @@
+pub mod consumer;
+
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use handshake_common::events as outbox;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{NewProcessedEvent, Product};
+use crate::schema::processed_events;
+
+/// Name this service publishes under, and its consumer group on the bus
+pub const SOURCE: &str = "product-service";
+
+/// Something that happened in one of the services that the others may react
+/// to. Sent over the bus as `{"type": "ProductCreated", "product_id": 1, ...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DomainEvent {
+    ProductCreated {
+        product_id: i32,
+        seller_id: i32,
+        category_id: i32,
+        title: String,
+        price: f64,
+        quantity: i32,
+        status: String,
+    },
+    ProductStatusChanged {
+        product_id: i32,
+        seller_id: i32,
+        previous_status: String,
+        status: String,
+    },
+    /// Published by order-service whenever an order moves to a new status
+    OrderStatusChanged {
+        order_id: i32,
+        product_id: i32,
+        buyer_id: i32,
+        seller_id: i32,
+        quantity: i32,
+        status: String,
+    },
+    /// Anything published by another service that this one doesn't react to
+    #[serde(other)]
+    Other,
+}
+
+impl DomainEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            DomainEvent::ProductCreated { .. } => "ProductCreated",
+            DomainEvent::ProductStatusChanged { .. } => "ProductStatusChanged",
+            DomainEvent::OrderStatusChanged { .. } => "OrderStatusChanged",
+            DomainEvent::Other => "Other",
+        }
+    }
+
+    pub fn product_created(product: &Product) -> Self {
+        DomainEvent::ProductCreated {
+            product_id: product.id,
+            seller_id: product.seller_id,
+            category_id: product.category_id,
+            title: product.title.clone(),
+            price: product.price,
+            quantity: product.quantity,
+            status: product.status.clone(),
+        }
+    }
+
+    /// `None` if the listing is still in `previous_status`
+    pub fn status_changed(previous_status: &str, product: &Product) -> Option<Self> {
+        if product.status == previous_status {
+            return None;
+        }
+        Some(DomainEvent::ProductStatusChanged {
+            product_id: product.id,
+            seller_id: product.seller_id,
+            previous_status: previous_status.to_string(),
+            status: product.status.clone(),
+        })
+    }
+}
+
+/// Queue `event` for the bus. Call it inside the transaction that made the
+/// change, so the event is published if and only if the change commits.
+pub async fn record(conn: &mut AsyncPgConnection, event: &DomainEvent) -> QueryResult<()> {
+    outbox::record(conn, event.event_type(), event).await
+}
+
+/// Record a `ProductStatusChanged` if the listing left `previous_status`
+pub async fn record_status_change(
+    conn: &mut AsyncPgConnection,
+    previous_status: &str,
+    product: &Product,
+) -> QueryResult<()> {
+    match DomainEvent::status_changed(previous_status, product) {
+        Some(event) => record(conn, &event).await,
+        None => Ok(()),
+    }
+}
+
+/// Mark an incoming event as handled. `false` if it already was, in which
+/// case the caller should skip it; run in the same transaction as its effect.
+pub async fn claim(
+    conn: &mut AsyncPgConnection,
+    event_id: &str,
+    event_type: &str,
+) -> QueryResult<bool> {
+    diesel::insert_into(processed_events::table)
+        .values(&NewProcessedEvent {
+            event_id: event_id.to_string(),
+            event_type: event_type.to_string(),
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await
+        .map(|inserted| inserted == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_tagged_by_type() {
+        let event = DomainEvent::ProductStatusChanged {
+            product_id: 3,
+            seller_id: 9,
+            previous_status: "active".to_string(),
+            status: "reserved".to_string(),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "ProductStatusChanged");
+        assert_eq!(json["status"], "reserved");
+        assert_eq!(serde_json::from_value::<DomainEvent>(json).unwrap(), event);
+    }
+
+    #[test]
+    fn ignores_events_it_does_not_consume() {
+        let event: DomainEvent = serde_json::from_str(
+            r#"{"type": "UserRegistered", "user_id": 1, "email": "a@b.c", "name": "A"}"#,
+        )
+        .unwrap();
+        assert_eq!(event, DomainEvent::Other);
+    }
+}
@@ -0,0 +1,167 @@
+const EARTH_RADIUS_KM: f64 = 6371.0;
+const KM_PER_DEGREE: f64 = 111.32;
+const BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Precision used when storing a product's geohash
+pub const STORED_PRECISION: usize = 9;
+
+/// Calculate distance between two points using the Haversine formula
+/// Returns distance in kilometers
+pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let lat1_rad = lat1.to_radians();
+    let lat2_rad = lat2.to_radians();
+    let delta_lat = (lat2 - lat1).to_radians();
+    let delta_lon = (lon2 - lon1).to_radians();
+
+    let a = (delta_lat / 2.0).sin().powi(2)
+        + lat1_rad.cos() * lat2_rad.cos() * (delta_lon / 2.0).sin().powi(2);
+    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+
+    EARTH_RADIUS_KM * c
+}
+
+/// SQL expression computing the Haversine distance in kilometers between
+/// `products.latitude/longitude` and the given point. Coordinates must be
+/// validated with `is_valid_coordinate` first since they are inlined.
+pub fn distance_sql(lat: f64, lon: f64) -> String {
+    format!(
+        "({r} * 2 * ASIN(SQRT(POWER(SIN(RADIANS(products.latitude - ({lat:.6})) / 2), 2) \
+         + COS(RADIANS({lat:.6})) * COS(RADIANS(products.latitude)) \
+         * POWER(SIN(RADIANS(products.longitude - ({lon:.6})) / 2), 2))))",
+        r = EARTH_RADIUS_KM,
+        lat = lat,
+        lon = lon,
+    )
+}
+
+pub fn is_valid_coordinate(lat: f64, lon: f64) -> bool {
+    lat.is_finite() && lon.is_finite() && (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)
+}
+
+/// Parse a `lat,lon` pair as used by the `near` query parameter
+pub fn parse_lat_lon(value: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = value.split_once(',')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lon: f64 = lon.trim().parse().ok()?;
+    is_valid_coordinate(lat, lon).then_some((lat, lon))
+}
+
+/// Encode a coordinate as a geohash of the given length
+pub fn encode(lat: f64, lon: f64, precision: usize) -> String {
+    let (mut lat_min, mut lat_max) = (-90.0, 90.0);
+    let (mut lon_min, mut lon_max) = (-180.0, 180.0);
+    let mut hash = String::with_capacity(precision);
+    let mut even = true;
+    let mut bit = 0;
+    let mut idx = 0usize;
+
+    while hash.len() < precision {
+        if even {
+            let mid = (lon_min + lon_max) / 2.0;
+            if lon >= mid {
+                idx = idx * 2 + 1;
+                lon_min = mid;
+            } else {
+                idx *= 2;
+                lon_max = mid;
+            }
+        } else {
+            let mid = (lat_min + lat_max) / 2.0;
+            if lat >= mid {
+                idx = idx * 2 + 1;
+                lat_min = mid;
+            } else {
+                idx *= 2;
+                lat_max = mid;
+            }
+        }
+        even = !even;
+        bit += 1;
+
+        if bit == 5 {
+            hash.push(BASE32[idx] as char);
+            bit = 0;
+            idx = 0;
+        }
+    }
+
+    hash
+}
+
+/// Size of a geohash cell in degrees as (latitude, longitude)
+fn cell_size(precision: usize) -> (f64, f64) {
+    let bits = 5 * precision as i32;
+    let lon_bits = (bits + 1) / 2;
+    let lat_bits = bits / 2;
+    (180.0 / 2f64.powi(lat_bits), 360.0 / 2f64.powi(lon_bits))
+}
+
+/// Longest geohash precision whose cells are at least `radius_km` across at
+/// the given latitude, so the 3x3 block around a point covers the radius.
+pub fn precision_for_radius(lat: f64, radius_km: f64) -> usize {
+    (1..=STORED_PRECISION)
+        .rev()
+        .find(|&precision| {
+            let (lat_deg, lon_deg) = cell_size(precision);
+            let height_km = lat_deg * KM_PER_DEGREE;
+            let width_km = lon_deg * KM_PER_DEGREE * lat.to_radians().cos();
+            height_km.min(width_km) >= radius_km
+        })
+        .unwrap_or(1)
+}
+
+/// Geohash prefixes of the cell containing the point and its eight
+/// neighbours, covering every point within `radius_km`.
+pub fn covering_prefixes(lat: f64, lon: f64, radius_km: f64) -> Vec<String> {
+    let precision = precision_for_radius(lat, radius_km);
+    let (lat_deg, lon_deg) = cell_size(precision);
+    let mut prefixes = Vec::with_capacity(9);
+
+    for dlat in [-1.0, 0.0, 1.0] {
+        for dlon in [-1.0, 0.0, 1.0] {
+            let cell_lat = (lat + dlat * lat_deg).clamp(-90.0, 90.0);
+            let mut cell_lon = lon + dlon * lon_deg;
+            if cell_lon > 180.0 {
+                cell_lon -= 360.0;
+            } else if cell_lon < -180.0 {
+                cell_lon += 360.0;
+            }
+
+            let prefix = encode(cell_lat, cell_lon, precision);
+            if !prefixes.contains(&prefix) {
+                prefixes.push(prefix);
+            }
+        }
+    }
+
+    prefixes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_known_geohashes() {
+        assert_eq!(encode(57.64911, 10.40744, 11), "u4pruydqqvj");
+        assert_eq!(encode(42.6, -5.6, 5), "ezs42");
+    }
+
+    #[test]
+    fn covering_prefixes_include_nearby_point() {
+        // Two points ~8 km apart in Jakarta
+        let (lat, lon) = (-6.2088, 106.8456);
+        let nearby = encode(-6.1751, 106.8650, STORED_PRECISION);
+        let prefixes = covering_prefixes(lat, lon, 10.0);
+
+        assert!(prefixes.iter().any(|p| nearby.starts_with(p.as_str())));
+        assert!(prefixes.len() <= 9);
+    }
+
+    #[test]
+    fn parses_only_valid_lat_lon_pairs() {
+        assert_eq!(parse_lat_lon("-6.2,106.8"), Some((-6.2, 106.8)));
+        assert_eq!(parse_lat_lon("91,0"), None);
+        assert_eq!(parse_lat_lon("abc"), None);
+    }
+}
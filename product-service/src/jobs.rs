@@ -0,0 +1,304 @@
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use rocket::fairing::AdHoc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use handshake_common::db::DbPool;
+use handshake_common::jobs::spawn_periodic;
+use crate::events;
+use crate::email::{
+    listing_renew_url, product_url, saved_search_unsubscribe_url, send_favorite_alert,
+    send_listing_expiry_reminder, send_saved_search_digest, DigestItem, FavoriteAlertRequest,
+    FavoriteAlertType, ListingExpiryReminderRequest, SavedSearchDigestRequest,
+};
+use crate::listing::generate_renew_token;
+use crate::models::{Favorite, Product, SavedSearch};
+use crate::routes::saved_searches::{matching_products, SearchFrequency};
+use crate::schema::{favorites, products, saved_searches};
+use crate::settings::Settings;
+
+/// Periodically emails watchers when a favorited product's price drops or
+/// when it gets reserved by another buyer.
+pub fn favorite_alerts() -> AdHoc {
+    AdHoc::on_liftoff("Favorite alerts job", |rocket| {
+        Box::pin(async move {
+            let pool = match rocket.state::<DbPool>() {
+                Some(pool) => pool.clone(),
+                None => {
+                    tracing::warn!("Favorite alerts job disabled: database pool unavailable");
+                    return;
+                }
+            };
+            let Some(settings) = rocket.state::<Settings>().cloned().map(Arc::new) else {
+                tracing::warn!("Favorite alerts job disabled: settings unavailable");
+                return;
+            };
+            let period = Duration::from_secs(settings.favorite_alert_interval_secs);
+
+            spawn_periodic("Favorite alerts job", pool, period, move |mut conn| {
+                let settings = settings.clone();
+                async move { run_favorite_alerts(&settings, &mut conn).await }
+            });
+        })
+    })
+}
+
+/// Periodically emails a digest of new listings matching each saved search,
+/// respecting the per-search frequency.
+pub fn saved_search_digests() -> AdHoc {
+    AdHoc::on_liftoff("Saved search digests job", |rocket| {
+        Box::pin(async move {
+            let pool = match rocket.state::<DbPool>() {
+                Some(pool) => pool.clone(),
+                None => {
+                    tracing::warn!("Saved search digests job disabled: database pool unavailable");
+                    return;
+                }
+            };
+            let Some(settings) = rocket.state::<Settings>().cloned().map(Arc::new) else {
+                tracing::warn!("Saved search digests job disabled: settings unavailable");
+                return;
+            };
+            let period = Duration::from_secs(settings.saved_search_interval_secs);
+
+            spawn_periodic("Saved search digests job", pool, period, move |mut conn| {
+                let settings = settings.clone();
+                async move { run_saved_search_digests(&settings, &mut conn).await }
+            });
+        })
+    })
+}
+
+/// Periodically expires listings past `expires_at` and reminds sellers a few
+/// days beforehand with a one-click renewal link.
+pub fn listing_expiry() -> AdHoc {
+    AdHoc::on_liftoff("Listing expiry job", |rocket| {
+        Box::pin(async move {
+            let pool = match rocket.state::<DbPool>() {
+                Some(pool) => pool.clone(),
+                None => {
+                    tracing::warn!("Listing expiry job disabled: database pool unavailable");
+                    return;
+                }
+            };
+            let Some(settings) = rocket.state::<Settings>().cloned().map(Arc::new) else {
+                tracing::warn!("Listing expiry job disabled: settings unavailable");
+                return;
+            };
+            let period = Duration::from_secs(settings.listing_expiry_interval_secs);
+
+            spawn_periodic("Listing expiry job", pool, period, move |mut conn| {
+                let settings = settings.clone();
+                async move { run_listing_expiry(&settings, &mut conn).await }
+            });
+        })
+    })
+}
+
+async fn run_favorite_alerts(
+    settings: &Settings,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), String> {
+    // Let later drops be measured against a raised price
+    diesel::sql_query(
+        "UPDATE favorites SET notified_price = products.price \
+         FROM products \
+         WHERE products.id = favorites.product_id \
+         AND products.price > favorites.notified_price",
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let pending: Vec<(Favorite, Product)> = favorites::table
+        .inner_join(products::table)
+        .filter(
+            products::price
+                .lt(favorites::notified_price)
+                .and(products::status.eq("active"))
+                .or(products::status
+                    .eq("reserved")
+                    .and(favorites::reserved_notified.eq(false))),
+        )
+        .load::<(Favorite, Product)>(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for (favorite, product) in pending {
+        let reserved = product.status == "reserved";
+        let alert_type = if reserved {
+            FavoriteAlertType::Reserved
+        } else {
+            FavoriteAlertType::PriceDrop
+        };
+
+        let request = FavoriteAlertRequest {
+            to_email: favorite.user_email.clone(),
+            alert_type,
+            product_id: product.id,
+            product_title: product.title.clone(),
+            product_url: product_url(settings, product.id),
+            old_price: favorite.notified_price,
+            new_price: product.price,
+        };
+
+        if let Err(e) = send_favorite_alert(settings, &request).await {
+            tracing::error!(favorite_id = favorite.id, error = %e, "Failed to send favorite alert");
+            continue;
+        }
+
+        let target = favorites::table.find(favorite.id);
+        let updated = if reserved {
+            diesel::update(target)
+                .set(favorites::reserved_notified.eq(true))
+                .execute(conn)
+                .await
+        } else {
+            diesel::update(target)
+                .set(favorites::notified_price.eq(product.price))
+                .execute(conn)
+                .await
+        };
+        updated.map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+async fn run_saved_search_digests(
+    settings: &Settings,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), String> {
+    let searches: Vec<SavedSearch> = saved_searches::table
+        .filter(saved_searches::active.eq(true))
+        .load(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let now = chrono::Utc::now().naive_utc();
+
+    for search in searches {
+        let frequency = SearchFrequency::parse(&search.frequency).unwrap_or(SearchFrequency::Daily);
+        if search.last_notified_at + frequency.period() > now {
+            continue;
+        }
+
+        let search_id = search.id;
+        let matches: Vec<Product> = matching_products(conn, &search, search.notified_through)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !matches.is_empty() {
+            let request = SavedSearchDigestRequest {
+                to_email: search.user_email.clone(),
+                search_name: search.name.clone(),
+                unsubscribe_url: saved_search_unsubscribe_url(settings, &search.unsubscribe_token),
+                products: matches
+                    .iter()
+                    .map(|p| DigestItem {
+                        title: p.title.clone(),
+                        price: p.price,
+                        url: product_url(settings, p.id),
+                    })
+                    .collect(),
+            };
+
+            if let Err(e) = send_saved_search_digest(settings, &request).await {
+                tracing::error!(saved_search_id = search_id, error = %e, "Failed to send saved search digest");
+                continue;
+            }
+        }
+
+        // Move past the newest listing sent rather than to `now`, so one listed
+        // while this run was going isn't sent again in the next digest
+        let notified_through = matches
+            .iter()
+            .map(|p| p.created_at)
+            .max()
+            .unwrap_or(search.notified_through);
+        diesel::update(saved_searches::table.find(search_id))
+            .set((
+                saved_searches::last_notified_at.eq(now),
+                saved_searches::notified_through.eq(notified_through),
+            ))
+            .execute(conn)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+async fn run_listing_expiry(
+    settings: &Settings,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), String> {
+    let now = chrono::Utc::now().naive_utc();
+    let remind_before = now + chrono::Duration::days(settings.listing_expiry_reminder_days);
+
+    conn.transaction(|conn| {
+        async move {
+            let expired: Vec<Product> = diesel::update(
+                products::table
+                    .filter(products::status.eq("active"))
+                    .filter(products::expires_at.le(now)),
+            )
+            .set(products::status.eq("expired"))
+            .get_results(conn)
+            .await?;
+            for product in &expired {
+                events::record_status_change(conn, "active", product).await?;
+            }
+            Ok::<_, diesel::result::Error>(())
+        }
+        .scope_boxed()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let expiring: Vec<Product> = products::table
+        .filter(products::status.eq("active"))
+        .filter(products::expires_at.le(remind_before))
+        .filter(products::expiry_reminded_at.is_null())
+        .filter(products::seller_email.is_not_null())
+        .load::<Product>(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for product in expiring {
+        let Some(to_email) = product.seller_email.clone() else {
+            continue;
+        };
+
+        let product_id = product.id;
+        let token = generate_renew_token();
+        diesel::update(products::table.find(product_id))
+            .set(products::renew_token.eq(&token))
+            .execute(conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let request = ListingExpiryReminderRequest {
+            to_email,
+            product_title: product.title.clone(),
+            product_url: product_url(settings, product.id),
+            renew_url: listing_renew_url(settings, &token),
+            expires_at: product.expires_at.format("%B %-d, %Y").to_string(),
+        };
+
+        if let Err(e) = send_listing_expiry_reminder(settings, &request).await {
+            tracing::error!(product_id, error = %e, "Failed to send expiry reminder");
+            continue;
+        }
+
+        diesel::update(products::table.find(product_id))
+            .set(products::expiry_reminded_at.eq(now))
+            .execute(conn)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemCondition {
+    New,
+    LikeNew,
+    Used,
+    Refurbished,
+    ForParts,
+}
+
+impl ItemCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemCondition::New => "new",
+            ItemCondition::LikeNew => "like_new",
+            ItemCondition::Used => "used",
+            ItemCondition::Refurbished => "refurbished",
+            ItemCondition::ForParts => "for_parts",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "new" => Some(ItemCondition::New),
+            "like_new" => Some(ItemCondition::LikeNew),
+            "used" => Some(ItemCondition::Used),
+            "refurbished" => Some(ItemCondition::Refurbished),
+            "for_parts" => Some(ItemCondition::ForParts),
+            _ => None,
+        }
+    }
+}
+
+/// What kind of listing a product is, which decides how it is handed over
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingType {
+    /// A physical item handed over at an in-person meetup
+    Physical,
+    /// A service; where it happens is arranged between the parties
+    Service,
+    /// A digital product delivered online
+    Digital,
+}
+
+impl ListingType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingType::Physical => "physical",
+            ListingType::Service => "service",
+            ListingType::Digital => "digital",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "physical" => Some(ListingType::Physical),
+            "service" => Some(ListingType::Service),
+            "digital" => Some(ListingType::Digital),
+            _ => None,
+        }
+    }
+
+    /// Whether orders must include a meetup location
+    pub fn requires_meetup(&self) -> bool {
+        matches!(self, ListingType::Physical)
+    }
+}
+
+/// Lifecycle state of a listing
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingStatus {
+    /// Not published yet; only the seller can see it
+    Draft,
+    Active,
+    /// All units are held by pending orders
+    Reserved,
+    Sold,
+    /// Ran past `expires_at` without being renewed
+    Expired,
+    Removed,
+    /// Taken down automatically after reports, pending moderation
+    Hidden,
+}
+
+impl ListingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingStatus::Draft => "draft",
+            ListingStatus::Active => "active",
+            ListingStatus::Reserved => "reserved",
+            ListingStatus::Sold => "sold",
+            ListingStatus::Expired => "expired",
+            ListingStatus::Removed => "removed",
+            ListingStatus::Hidden => "hidden",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "draft" => Some(ListingStatus::Draft),
+            "active" => Some(ListingStatus::Active),
+            "reserved" => Some(ListingStatus::Reserved),
+            "sold" => Some(ListingStatus::Sold),
+            "expired" => Some(ListingStatus::Expired),
+            "removed" => Some(ListingStatus::Removed),
+            "hidden" => Some(ListingStatus::Hidden),
+            _ => None,
+        }
+    }
+
+    /// Drafts, removed listings and ones hidden after reports, which only
+    /// their seller and moderators may see
+    pub fn is_unlisted(&self) -> bool {
+        matches!(
+            self,
+            ListingStatus::Draft | ListingStatus::Removed | ListingStatus::Hidden
+        )
+    }
+
+    /// Whether a seller may move their own listing from `self` to `next`.
+    /// Reserved, expired and hidden are only ever set by the system, and
+    /// expired listings come back through renewal rather than an edit.
+    pub fn seller_can_change_to(&self, next: ListingStatus) -> bool {
+        use ListingStatus::*;
+
+        match (*self, next) {
+            (current, next) if current == next => true,
+            (Draft, Active) | (Active, Draft) | (Expired, Draft) => true,
+            (Active | Reserved, Sold) => true,
+            (Removed, _) => false,
+            (_, Removed) => true,
+            _ => false,
+        }
+    }
+}
+
+pub fn generate_renew_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sellers_publish_and_unpublish_drafts() {
+        assert!(ListingStatus::Draft.seller_can_change_to(ListingStatus::Active));
+        assert!(ListingStatus::Active.seller_can_change_to(ListingStatus::Draft));
+        assert!(ListingStatus::Reserved.seller_can_change_to(ListingStatus::Sold));
+    }
+
+    #[test]
+    fn system_states_cannot_be_set_by_sellers() {
+        assert!(!ListingStatus::Active.seller_can_change_to(ListingStatus::Reserved));
+        assert!(!ListingStatus::Active.seller_can_change_to(ListingStatus::Expired));
+        assert!(!ListingStatus::Expired.seller_can_change_to(ListingStatus::Active));
+        assert!(!ListingStatus::Hidden.seller_can_change_to(ListingStatus::Active));
+        assert!(!ListingStatus::Removed.seller_can_change_to(ListingStatus::Draft));
+    }
+
+    #[test]
+    fn only_published_listings_are_listed() {
+        assert!(ListingStatus::Hidden.is_unlisted());
+        assert!(ListingStatus::Draft.is_unlisted());
+        assert!(!ListingStatus::Active.is_unlisted());
+        assert!(!ListingStatus::Sold.is_unlisted());
+    }
+}
@@ -0,0 +1,43 @@
+use serde::Deserialize;
+
+use crate::settings::Settings;
+
+#[derive(Debug, Deserialize)]
+pub struct PublicLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Fetch a user's public (privacy-fuzzed) location from order-service's
+/// internal endpoint.
+/// Returns `Ok(None)` when the user hasn't set a location yet.
+pub async fn fetch_public_location(
+    settings: &Settings,
+    user_id: i32,
+) -> Result<Option<PublicLocation>, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!(
+            "{}/locations/users/{}",
+            settings.order_service_url, user_id
+        ))
+        .header("X-Internal-Token", settings.internal_token())
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to order service: {}", e))?;
+
+    let status = response.status();
+
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(format!("Order service returned error ({})", status));
+    }
+
+    response
+        .json()
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to parse order service response: {}", e))
+}
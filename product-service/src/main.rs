@@ -1,97 +1,60 @@
-pub mod auth;
+pub mod attributes;
 pub mod db;
+pub mod email;
+pub mod etag;
+pub mod events;
+pub mod geo;
 pub mod health;
+pub mod jobs;
+pub mod listing;
+pub mod locations;
 pub mod models;
+pub mod revisions;
 pub mod routes;
 pub mod schema;
+pub mod settings;
+pub mod validation;
 
-use diesel::{Connection, PgConnection};
-use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use rocket::fairing::{Fairing, Info, Kind};
-use rocket::figment::value::{Map, Value};
-use rocket::http::Header;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
+use handshake_common::config;
+use handshake_common::metrics::RequestMetrics;
+use handshake_common::telemetry::{self, RequestTracing};
 use rocket::routes;
-use rocket::{Request, Response};
 
-use rocket_cors::CorsOptions;
+use crate::settings::Settings;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 
-fn run_migrations(connection: &mut PgConnection) {
-    match connection.run_pending_migrations(MIGRATIONS) {
-        Ok(migrations) => {
-            if migrations.is_empty() {
-                println!("No pending migrations to run.");
-            } else {
-                println!("Successfully ran {} migrations:", migrations.len());
-                for migration in migrations {
-                    println!("  - {}", migration);
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Error running migrations: {}", e);
-            std::process::exit(1);
-        }
-    }
-}
-
-pub struct CORS;
-
-#[rocket::async_trait]
-impl Fairing for CORS {
-    fn info(&self) -> Info {
-        Info {
-            name: "Add CORS headers to responses",
-            kind: Kind::Response,
-        }
-    }
-
-    async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
-        response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
-        response.set_header(Header::new(
-            "Access-Control-Allow-Methods",
-            "POST, GET, PUT, DELETE, OPTIONS",
-        ));
-        response.set_header(Header::new(
-            "Access-Control-Allow-Headers",
-            "*, Authorization",
-        ));
-        response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
-    }
-}
-
+#[allow(clippy::result_large_err)]
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
-    dotenv::dotenv().ok();
-
-    let database_url =
-        std::env::var("DATABASE_URL").expect("DATABASE_URL must be set (e.g. in .env)");
-
-    // Run database migrations on startup
-    println!("Running database migrations...");
-    let mut connection = PgConnection::establish(&database_url)
-        .unwrap_or_else(|e| {
-            eprintln!("Error connecting to database for migrations: {}", e);
-            std::process::exit(1);
-        });
-    run_migrations(&mut connection);
-
-    let mut db: Map<String, Value> = Map::new();
-    db.insert("url".to_string(), database_url.into());
-
-    let mut databases: Map<String, Value> = Map::new();
-    databases.insert("product_db".to_string(), db.into());
-
-    let figment = rocket::Config::figment().merge(("databases", databases));
-
-    let cors = CorsOptions::default().to_cors().unwrap();
-
-    let _rocket = rocket::custom(figment)
-        // .attach(CORS)
-        .attach(cors)
-        .attach(db::DbConn::fairing())
+    config::load_env();
+    let _telemetry = telemetry::init("product-service");
+    let settings: Settings = config::load();
+
+    handshake_common::db::run_migrations(&settings.database.url, MIGRATIONS);
+
+    let pool = db::pool(&settings.database);
+
+    let _rocket = rocket::build()
+        .attach(config::cors(settings.app_url.as_deref()))
+        .attach(RequestTracing)
+        .attach(RequestMetrics)
+        .manage(pool)
+        .attach(handshake_common::metrics::pool_metrics())
+        .attach(jobs::favorite_alerts())
+        .attach(jobs::saved_search_digests())
+        .attach(jobs::listing_expiry())
+        .attach(handshake_common::events::relay::relay(
+            events::SOURCE,
+            settings.events.clone(),
+        ))
+        .attach(events::consumer::consumer(settings.events.clone()))
+        .manage(settings.auth.clone())
+        .manage(settings)
+        .register("/", handshake_common::error::catchers())
         .mount("/", routes![health::live, health::ready])
+        .mount("/", handshake_common::metrics::routes())
         .mount(
             "/products",
             routes![
@@ -100,13 +63,53 @@ async fn main() -> Result<(), rocket::Error> {
                 routes::products::create_product,
                 routes::products::update_product,
                 routes::products::delete_product,
+                routes::reports::report_product,
+                routes::favorites::add_favorite,
+                routes::favorites::remove_favorite,
+                routes::stock::reserve_stock,
+                routes::stock::release_stock,
+                routes::products::renew_product,
+                routes::products::renew_by_token,
+                routes::products::list_revisions,
+                routes::products::product_snapshot,
+                routes::products::product_summaries,
+            ],
+        )
+        .mount(
+            "/me",
+            routes![routes::favorites::my_favorites, routes::products::my_listings],
+        )
+        .mount(
+            "/saved-searches",
+            routes![
+                routes::saved_searches::create_saved_search,
+                routes::saved_searches::list_saved_searches,
+                routes::saved_searches::update_saved_search,
+                routes::saved_searches::delete_saved_search,
+                routes::saved_searches::unsubscribe,
             ],
         )
         .mount(
             "/categories",
             routes![
                 routes::categories::list_categories,
+                routes::categories::category_tree,
+                routes::categories::get_category,
                 routes::categories::get_category_products,
+                routes::categories::create_category,
+                routes::categories::update_category,
+                routes::categories::delete_category,
+                routes::categories::get_category_attributes,
+                routes::categories::create_category_attribute,
+                routes::categories::delete_category_attribute,
+            ],
+        )
+        .mount(
+            "/moderation",
+            routes![
+                routes::moderation::moderation_queue,
+                routes::moderation::resolve_reports,
+                routes::moderation::moderation_history,
             ],
         )
         .launch()
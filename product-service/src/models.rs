@@ -9,6 +9,45 @@ pub struct Category {
     pub name: String,
     pub slug: String,
     pub icon: Option<String>,
+    pub parent_id: Option<i32>,
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Deserialize, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::categories)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewCategory {
+    pub name: String,
+    pub slug: String,
+    pub icon: Option<String>,
+    pub parent_id: Option<i32>,
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(Category))]
+#[diesel(table_name = crate::schema::category_attributes)]
+pub struct CategoryAttribute {
+    pub id: i32,
+    pub category_id: i32,
+    pub key: String,
+    pub label: String,
+    pub value_type: String,
+    pub options: Option<serde_json::Value>,
+    pub required: bool,
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::category_attributes)]
+pub struct NewCategoryAttribute {
+    pub category_id: i32,
+    pub key: String,
+    pub label: String,
+    pub value_type: String,
+    pub options: Option<serde_json::Value>,
+    pub required: bool,
+    pub sort_order: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Associations)]
@@ -24,6 +63,25 @@ pub struct Product {
     pub image_url: Option<String>,
     pub status: String,
     pub created_at: NaiveDateTime,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    #[serde(skip_serializing)]
+    pub geohash: Option<String>,
+    pub attributes: serde_json::Value,
+    pub condition: String,
+    pub quantity: i32,
+    pub listing_type: String,
+    #[serde(skip_serializing)]
+    pub seller_email: Option<String>,
+    pub expires_at: NaiveDateTime,
+    #[serde(skip_serializing)]
+    pub expiry_reminded_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub renew_token: Option<String>,
+    #[serde(skip_serializing)]
+    pub deleted_at: Option<NaiveDateTime>,
+    pub revision: i32,
+    pub updated_at: NaiveDateTime,
 }
 
 #[derive(Debug, Deserialize, Insertable)]
@@ -35,4 +93,175 @@ pub struct NewProduct {
     pub description: String,
     pub price: f64,
     pub image_url: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub geohash: Option<String>,
+    pub attributes: serde_json::Value,
+    pub condition: String,
+    pub quantity: i32,
+    pub listing_type: String,
+    pub status: String,
+    pub seller_email: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// A partial update to a listing; `None` fields are left untouched
+#[derive(Debug, Default, AsChangeset)]
+#[diesel(table_name = crate::schema::products)]
+pub struct ProductChangeset {
+    pub category_id: Option<i32>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub price: Option<f64>,
+    pub image_url: Option<Option<String>>,
+    pub status: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub geohash: Option<String>,
+    pub attributes: Option<serde_json::Value>,
+    pub condition: Option<String>,
+    pub quantity: Option<i32>,
+    pub listing_type: Option<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(Product))]
+#[diesel(table_name = crate::schema::product_revisions)]
+pub struct ProductRevision {
+    pub id: i32,
+    pub product_id: i32,
+    pub revision: i32,
+    pub editor_id: i32,
+    pub changed_fields: serde_json::Value,
+    pub snapshot: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::product_revisions)]
+pub struct NewProductRevision {
+    pub product_id: i32,
+    pub revision: i32,
+    pub editor_id: i32,
+    pub changed_fields: serde_json::Value,
+    pub snapshot: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(Product))]
+#[diesel(table_name = crate::schema::product_reports)]
+pub struct ProductReport {
+    pub id: i32,
+    pub product_id: i32,
+    pub reporter_id: i32,
+    pub reason: String,
+    pub details: Option<String>,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub resolved_by: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::product_reports)]
+pub struct NewProductReport {
+    pub product_id: i32,
+    pub reporter_id: i32,
+    pub reason: String,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(Product))]
+#[diesel(table_name = crate::schema::moderation_actions)]
+pub struct ModerationAction {
+    pub id: i32,
+    pub product_id: i32,
+    pub moderator_id: Option<i32>,
+    pub action: String,
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::moderation_actions)]
+pub struct NewModerationAction {
+    pub product_id: i32,
+    pub moderator_id: Option<i32>,
+    pub action: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(Product))]
+#[diesel(table_name = crate::schema::favorites)]
+pub struct Favorite {
+    pub id: i32,
+    pub user_id: i32,
+    pub user_email: String,
+    pub product_id: i32,
+    pub notified_price: f64,
+    pub reserved_notified: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::favorites)]
+pub struct NewFavorite {
+    pub user_id: i32,
+    pub user_email: String,
+    pub product_id: i32,
+    pub notified_price: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable)]
+#[diesel(table_name = crate::schema::saved_searches)]
+pub struct SavedSearch {
+    pub id: i32,
+    pub user_id: i32,
+    #[serde(skip_serializing)]
+    pub user_email: String,
+    pub name: String,
+    pub category_slug: Option<String>,
+    pub keywords: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub max_distance_km: Option<f64>,
+    pub frequency: String,
+    pub active: bool,
+    #[serde(skip_serializing)]
+    pub unsubscribe_token: String,
+    pub last_notified_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    /// `created_at` of the newest listing sent so far; later digests start after it
+    #[serde(skip_serializing)]
+    pub notified_through: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = crate::schema::saved_searches)]
+pub struct NewSavedSearch {
+    pub user_id: i32,
+    pub user_email: String,
+    pub name: String,
+    pub category_slug: Option<String>,
+    pub keywords: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub max_distance_km: Option<f64>,
+    pub frequency: String,
+    pub unsubscribe_token: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::processed_events)]
+pub struct NewProcessedEvent {
+    pub event_id: String,
+    pub event_type: String,
 }
@@ -0,0 +1,176 @@
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{NewProductRevision, Product};
+use crate::schema::{product_revisions, products};
+
+/// The buyer-visible state of a listing, as recorded in each revision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListingSnapshot {
+    pub category_id: i32,
+    pub title: String,
+    pub description: String,
+    pub price: f64,
+    pub image_url: Option<String>,
+    pub status: String,
+    pub attributes: serde_json::Value,
+    pub condition: String,
+    pub quantity: i32,
+    pub listing_type: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+impl From<&Product> for ListingSnapshot {
+    fn from(product: &Product) -> Self {
+        ListingSnapshot {
+            category_id: product.category_id,
+            title: product.title.clone(),
+            description: product.description.clone(),
+            price: product.price,
+            image_url: product.image_url.clone(),
+            status: product.status.clone(),
+            attributes: product.attributes.clone(),
+            condition: product.condition.clone(),
+            quantity: product.quantity,
+            listing_type: product.listing_type.clone(),
+            latitude: product.latitude,
+            longitude: product.longitude,
+        }
+    }
+}
+
+impl ListingSnapshot {
+    /// Names of the fields that differ between `self` and `other`
+    pub fn changed_fields(&self, other: &ListingSnapshot) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.category_id != other.category_id {
+            changed.push("category_id");
+        }
+        if self.title != other.title {
+            changed.push("title");
+        }
+        if self.description != other.description {
+            changed.push("description");
+        }
+        if self.price != other.price {
+            changed.push("price");
+        }
+        if self.image_url != other.image_url {
+            changed.push("image_url");
+        }
+        if self.status != other.status {
+            changed.push("status");
+        }
+        if self.attributes != other.attributes {
+            changed.push("attributes");
+        }
+        if self.condition != other.condition {
+            changed.push("condition");
+        }
+        if self.quantity != other.quantity {
+            changed.push("quantity");
+        }
+        if self.listing_type != other.listing_type {
+            changed.push("listing_type");
+        }
+        if self.latitude != other.latitude || self.longitude != other.longitude {
+            changed.push("location");
+        }
+        changed
+    }
+}
+
+/// Record the first revision of a newly created listing
+pub async fn record_initial(conn: &mut AsyncPgConnection, product: &Product) -> QueryResult<()> {
+    let snapshot = ListingSnapshot::from(product);
+
+    diesel::insert_into(product_revisions::table)
+        .values(&NewProductRevision {
+            product_id: product.id,
+            revision: product.revision,
+            editor_id: product.seller_id,
+            changed_fields: serde_json::json!([]),
+            snapshot: serde_json::to_value(&snapshot).unwrap_or_default(),
+        })
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Record an edit from `before` to `after`, bumping the product's revision
+/// number. Does nothing if no listed field changed. Must run inside the
+/// transaction that made the edit.
+pub async fn record_edit(
+    conn: &mut AsyncPgConnection,
+    before: &Product,
+    after: Product,
+    editor_id: i32,
+) -> QueryResult<Product> {
+    let old = ListingSnapshot::from(before);
+    let new = ListingSnapshot::from(&after);
+    let changed = old.changed_fields(&new);
+    if changed.is_empty() {
+        return Ok(after);
+    }
+
+    let product: Product = diesel::update(products::table.find(after.id))
+        .set(products::revision.eq(products::revision + 1))
+        .get_result(conn)
+        .await?;
+
+    diesel::insert_into(product_revisions::table)
+        .values(&NewProductRevision {
+            product_id: product.id,
+            revision: product.revision,
+            editor_id,
+            changed_fields: serde_json::json!(changed),
+            snapshot: serde_json::to_value(&new).unwrap_or_default(),
+        })
+        .execute(conn)
+        .await?;
+
+    Ok(product)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> ListingSnapshot {
+        ListingSnapshot {
+            category_id: 1,
+            title: "Bike".to_string(),
+            description: "Blue road bike".to_string(),
+            price: 150.0,
+            image_url: None,
+            status: "active".to_string(),
+            attributes: serde_json::json!({}),
+            condition: "used".to_string(),
+            quantity: 1,
+            listing_type: "physical".to_string(),
+            latitude: Some(-6.2),
+            longitude: Some(106.85),
+        }
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_changes() {
+        assert!(snapshot().changed_fields(&snapshot()).is_empty());
+    }
+
+    #[test]
+    fn reports_each_changed_field() {
+        let mut edited = snapshot();
+        edited.price = 120.0;
+        edited.latitude = Some(-6.3);
+        edited.image_url = Some("https://example.com/bike.jpg".to_string());
+
+        assert_eq!(
+            snapshot().changed_fields(&edited),
+            vec!["price", "image_url", "location"]
+        );
+    }
+}
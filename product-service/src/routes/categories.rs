@@ -1,58 +1,528 @@
 use rocket::serde::json::Json;
 use rocket::http::Status;
-use rocket::get;
+use rocket::{get, post, put, delete};
+use serde::{Deserialize, Serialize};
 use diesel::prelude::*;
+use diesel::dsl::now;
+use diesel::PgJsonbExpressionMethods;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use std::collections::HashMap;
+use chrono::NaiveDateTime;
 
 use crate::db::DbConn;
-use crate::models::Category;
-use crate::schema::categories;
+use crate::attributes::{filter_object, ValueType};
+use crate::validation;
+use crate::models::{Category, CategoryAttribute, NewCategory, NewCategoryAttribute};
+use crate::schema::{categories, category_attributes, products};
+use handshake_common::error::ApiError;
+use handshake_common::auth::AdminUser;
+use crate::routes::favorites::favorite_counts;
 use crate::routes::products::ProductResponse;
+use handshake_common::pagination::{clamp_limit, Cursor, Page};
+
+#[derive(Debug, Serialize)]
+pub struct CategoryDetail {
+    #[serde(flatten)]
+    pub category: Category,
+    /// Ancestors from the root down to and including this category
+    pub breadcrumb: Vec<Category>,
+    pub children: Vec<Category>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub children: Vec<CategoryNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CategoryAttributeRequest {
+    pub key: String,
+    pub label: String,
+    pub value_type: String,
+    pub options: Option<Vec<String>>,
+    pub required: Option<bool>,
+    pub sort_order: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CategoryRequest {
+    pub name: String,
+    pub slug: String,
+    pub icon: Option<String>,
+    pub parent_id: Option<i32>,
+    pub sort_order: Option<i32>,
+}
+
+/// Load every category in display order. The table is small enough that
+/// walking the hierarchy in memory is cheaper than recursive queries.
+pub async fn load_all(conn: &mut AsyncPgConnection) -> QueryResult<Vec<Category>> {
+    categories::table
+        .order((categories::sort_order.asc(), categories::name.asc()))
+        .load(conn)
+        .await
+}
+
+/// Ids of a category and all of its descendants
+pub fn descendant_ids(all: &[Category], root_id: i32) -> Vec<i32> {
+    let mut ids = vec![root_id];
+    let mut i = 0;
+    while i < ids.len() {
+        let parent = ids[i];
+        ids.extend(
+            all.iter()
+                .filter(|c| c.parent_id == Some(parent) && !ids.contains(&c.id))
+                .map(|c| c.id)
+                .collect::<Vec<_>>(),
+        );
+        i += 1;
+    }
+    ids
+}
+
+/// Path from the root category down to `id`
+pub fn breadcrumb(all: &[Category], id: i32) -> Vec<Category> {
+    let mut path = Vec::new();
+    let mut current = all.iter().find(|c| c.id == id);
+    while let Some(category) = current {
+        if path.iter().any(|c: &Category| c.id == category.id) {
+            break;
+        }
+        path.push(category.clone());
+        current = category
+            .parent_id
+            .and_then(|parent_id| all.iter().find(|c| c.id == parent_id));
+    }
+    path.reverse();
+    path
+}
+
+/// Attribute schema of a category, including attributes inherited from its ancestors
+pub async fn effective_schema(
+    conn: &mut AsyncPgConnection,
+    category_id: i32,
+) -> QueryResult<Vec<CategoryAttribute>> {
+    let all = load_all(conn).await?;
+    let ids: Vec<i32> = breadcrumb(&all, category_id).iter().map(|c| c.id).collect();
+
+    category_attributes::table
+        .filter(category_attributes::category_id.eq_any(ids))
+        .order((category_attributes::sort_order.asc(), category_attributes::key.asc()))
+        .load(conn)
+        .await
+}
+
+fn build_tree(all: &[Category], parent_id: Option<i32>) -> Vec<CategoryNode> {
+    all.iter()
+        .filter(|c| c.parent_id == parent_id)
+        .map(|c| CategoryNode {
+            category: c.clone(),
+            children: build_tree(all, Some(c.id)),
+        })
+        .collect()
+}
+
+fn validate_request(request: &CategoryRequest) -> Result<(), ApiError> {
+    let slug_ok = !request.slug.is_empty()
+        && request
+            .slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+    if request.name.trim().is_empty() {
+        return Err(ApiError::unprocessable("Name must not be empty"));
+    }
+    if !slug_ok {
+        return Err(ApiError::unprocessable(
+            "Slug may only contain lowercase letters, digits and hyphens",
+        ));
+    }
+    Ok(())
+}
 
 #[get("/")]
-pub async fn list_categories(db: DbConn) -> Result<Json<Vec<Category>>, Status> {
-    let categories: Vec<Category> = db.run(|conn| {
-        categories::table
-            .order(categories::name.asc())
-            .load(conn)
-    }).await.map_err(|_| Status::InternalServerError)?;
+pub async fn list_categories(mut db: DbConn) -> Result<Json<Vec<Category>>, ApiError> {
+    let categories: Vec<Category> = load_all(&mut db).await.map_err(ApiError::internal)?;
 
     Ok(Json(categories))
 }
 
-#[get("/<slug>/products?<limit>")]
+#[get("/tree")]
+pub async fn category_tree(mut db: DbConn) -> Result<Json<Vec<CategoryNode>>, ApiError> {
+    let categories: Vec<Category> = load_all(&mut db).await.map_err(ApiError::internal)?;
+
+    Ok(Json(build_tree(&categories, None)))
+}
+
+#[get("/<slug>", rank = 2)]
+pub async fn get_category(mut db: DbConn, slug: String) -> Result<Json<CategoryDetail>, ApiError> {
+    let categories: Vec<Category> = load_all(&mut db).await.map_err(ApiError::internal)?;
+
+    let category = categories
+        .iter()
+        .find(|c| c.slug == slug)
+        .cloned()
+        .ok_or_else(|| ApiError::not_found("Category not found"))?;
+
+    Ok(Json(CategoryDetail {
+        breadcrumb: breadcrumb(&categories, category.id),
+        children: categories
+            .iter()
+            .filter(|c| c.parent_id == Some(category.id))
+            .cloned()
+            .collect(),
+        category,
+    }))
+}
+
+#[get("/<slug>/products?<limit>&<cursor>&<attr>")]
 pub async fn get_category_products(
-    db: DbConn,
+    mut db: DbConn,
     slug: String,
     limit: Option<i64>,
-) -> Result<Json<Vec<ProductResponse>>, Status> {
-    use crate::schema::products;
+    cursor: Option<String>,
+    attr: HashMap<String, String>,
+) -> Result<Json<Page<ProductResponse>>, ApiError> {
     use crate::models::Product;
 
-    let limit = limit.unwrap_or(20).min(100);
+    let limit = clamp_limit(limit);
+    let after = match cursor {
+        Some(ref value) => Some(Cursor::<NaiveDateTime>::decode(value).ok_or_else(|| ApiError::bad_request("Invalid cursor"))?),
+        None => None,
+    };
+    let has_filters = !attr.is_empty();
+
+    let all = load_all(&mut db).await.map_err(ApiError::internal)?;
+    let root = all
+        .iter()
+        .find(|c| c.slug == slug)
+        .ok_or_else(|| ApiError::not_found("Category not found"))?;
+    let ids = descendant_ids(&all, root.id);
+
+    let definitions = if has_filters {
+        effective_schema(&mut db, root.id).await.map_err(ApiError::internal)?
+    } else {
+        Vec::new()
+    };
 
-    let results: Vec<(Product, Category)> = db.run(move |conn| {
-        products::table
+    let filter = if has_filters {
+        Some(filter_object(&definitions, &attr).map_err(validation::bad_filters)?)
+    } else {
+        None
+    };
+
+    let filtered = || {
+        let mut query = products::table
             .inner_join(categories::table.on(products::category_id.eq(categories::id)))
-            .filter(categories::slug.eq(&slug))
+            .filter(products::category_id.eq_any(ids.clone()))
             .filter(products::status.eq("active"))
-            .order(products::created_at.desc())
-            .limit(limit)
-            .load(conn)
-    }).await.map_err(|_| Status::InternalServerError)?;
-
-    let response: Vec<ProductResponse> = results.into_iter().map(|(p, c)| {
-        ProductResponse {
-            id: p.id,
-            seller_id: p.seller_id,
-            category_id: p.category_id,
-            category_name: c.name,
-            title: p.title,
-            description: p.description,
-            price: p.price,
-            image_url: p.image_url,
-            status: p.status,
-        }
-    }).collect();
-
-    Ok(Json(response))
+            .filter(products::expires_at.gt(now))
+            .into_boxed();
+
+        if let Some(ref filter) = filter {
+            query = query.filter(products::attributes.contains(filter.clone()));
+        }
+
+        query
+    };
+
+    let total: i64 = filtered()
+        .count()
+        .get_result(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let mut query = filtered();
+    if let Some(c) = after {
+        query = query.filter(
+            products::created_at
+                .lt(c.key)
+                .or(products::created_at.eq(c.key).and(products::id.lt(c.id))),
+        );
+    }
+
+    let results: Vec<(Product, Category)> = query
+        .order((products::created_at.desc(), products::id.desc()))
+        .limit(limit + 1)
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let ids: Vec<i32> = results.iter().map(|(p, _)| p.id).collect();
+    let counts = favorite_counts(&mut db, &ids).await.map_err(ApiError::internal)?;
+
+    let page = Page::from_rows(results, limit, total, |(p, _)| {
+        Cursor::new(p.created_at, p.id).encode()
+    });
+
+    Ok(Json(page.map(|(p, c)| {
+        let favorite_count = counts.get(&p.id).copied().unwrap_or(0);
+        ProductResponse::new(p, c, favorite_count)
+    })))
+}
+
+#[get("/<slug>/attributes")]
+pub async fn get_category_attributes(
+    mut db: DbConn,
+    slug: String,
+) -> Result<Json<Vec<CategoryAttribute>>, ApiError> {
+    let category: Category = categories::table
+        .filter(categories::slug.eq(&slug))
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Category not found"))?;
+    let attributes = effective_schema(&mut db, category.id)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(attributes))
+}
+
+#[post("/<id>/attributes", data = "<request>")]
+pub async fn create_category_attribute(
+    mut db: DbConn,
+    _admin: AdminUser,
+    id: i32,
+    request: Json<CategoryAttributeRequest>,
+) -> Result<Json<CategoryAttribute>, ApiError> {
+    let request = request.into_inner();
+
+    let value_type = ValueType::parse(&request.value_type)
+        .ok_or_else(|| ApiError::unprocessable("Unknown value type"))?;
+    let key_ok = !request.key.is_empty()
+        && request
+            .key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    let options = match (value_type, request.options) {
+        (ValueType::Enum, Some(options)) if !options.is_empty() => Some(serde_json::json!(options)),
+        (ValueType::Enum, _) => return Err(ApiError::unprocessable("Enum attributes need options")),
+        (_, Some(_)) => return Err(ApiError::unprocessable("Only enum attributes take options")),
+        (_, None) => None,
+    };
+
+    if !key_ok {
+        return Err(ApiError::unprocessable(
+            "Key may only contain lowercase letters, digits and underscores",
+        ));
+    }
+    if request.label.trim().is_empty() {
+        return Err(ApiError::unprocessable("Label must not be empty"));
+    }
+
+    let attribute: CategoryAttribute = db.transaction(|conn| async move {
+        categories::table.find(id).first::<Category>(conn).await?;
+
+        diesel::insert_into(category_attributes::table)
+            .values(&NewCategoryAttribute {
+                category_id: id,
+                key: request.key,
+                label: request.label,
+                value_type: request.value_type,
+                options,
+                required: request.required.unwrap_or(false),
+                sort_order: request.sort_order.unwrap_or(0),
+            })
+            .get_result(conn)
+            .await
+    }.scope_boxed()).await.map_err(|e| match e {
+        diesel::result::Error::NotFound => ApiError::not_found("Category not found"),
+        e => category_write_error(e),
+    })?;
+
+    Ok(Json(attribute))
+}
+
+#[delete("/<id>/attributes/<key>")]
+pub async fn delete_category_attribute(
+    mut db: DbConn,
+    _admin: AdminUser,
+    id: i32,
+    key: String,
+) -> Result<Status, ApiError> {
+    let deleted = diesel::delete(
+        category_attributes::table
+            .filter(category_attributes::category_id.eq(id))
+            .filter(category_attributes::key.eq(key)),
+    )
+    .execute(&mut db)
+    .await
+    .map_err(ApiError::internal)?;
+
+    if deleted == 0 {
+        return Err(ApiError::not_found("Attribute not found"));
+    }
+
+    Ok(Status::NoContent)
+}
+
+#[post("/", data = "<request>")]
+pub async fn create_category(
+    mut db: DbConn,
+    _admin: AdminUser,
+    request: Json<CategoryRequest>,
+) -> Result<Json<Category>, ApiError> {
+    validate_request(&request)?;
+    let request = request.into_inner();
+
+    let category: Category = db.transaction(|conn| async move {
+        if let Some(parent_id) = request.parent_id {
+            categories::table.find(parent_id).first::<Category>(conn).await?;
+        }
+
+        diesel::insert_into(categories::table)
+            .values(&NewCategory {
+                name: request.name,
+                slug: request.slug,
+                icon: request.icon,
+                parent_id: request.parent_id,
+                sort_order: request.sort_order.unwrap_or(0),
+            })
+            .get_result(conn)
+            .await
+    }.scope_boxed()).await.map_err(category_write_error)?;
+
+    Ok(Json(category))
+}
+
+#[put("/<id>", data = "<request>")]
+pub async fn update_category(
+    mut db: DbConn,
+    _admin: AdminUser,
+    id: i32,
+    request: Json<CategoryRequest>,
+) -> Result<Json<Category>, ApiError> {
+    validate_request(&request)?;
+    let request = request.into_inner();
+
+    let all: Vec<Category> = load_all(&mut db).await.map_err(ApiError::internal)?;
+
+    let existing = all
+        .iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| ApiError::not_found("Category not found"))?;
+
+    // A category can't be moved under itself or one of its descendants
+    if let Some(parent_id) = request.parent_id {
+        if !all.iter().any(|c| c.id == parent_id) {
+            return Err(ApiError::unprocessable("Parent category does not exist"));
+        }
+        if descendant_ids(&all, id).contains(&parent_id) {
+            return Err(ApiError::unprocessable(
+                "A category can't be moved under itself or its subcategories",
+            ));
+        }
+    }
+
+    let changes = NewCategory {
+        name: request.name,
+        slug: request.slug,
+        icon: request.icon,
+        parent_id: request.parent_id,
+        sort_order: request.sort_order.unwrap_or(existing.sort_order),
+    };
+
+    let category: Category = diesel::update(categories::table.find(id))
+        .set(&changes)
+        .get_result(&mut db)
+        .await
+        .map_err(category_write_error)?;
+
+    Ok(Json(category))
+}
+
+#[delete("/<id>")]
+pub async fn delete_category(
+    mut db: DbConn,
+    _admin: AdminUser,
+    id: i32,
+) -> Result<Status, ApiError> {
+    categories::table
+        .find(id)
+        .first::<Category>(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Category not found"))?;
+
+    let children: i64 = categories::table
+        .filter(categories::parent_id.eq(id))
+        .count()
+        .get_result(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+    let product_count: i64 = products::table
+        .filter(products::category_id.eq(id))
+        .count()
+        .get_result(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    // Products and subcategories must be moved elsewhere first
+    if children > 0 || product_count > 0 {
+        return Err(ApiError::conflict(
+            "Move this category's products and subcategories before deleting it",
+        ));
+    }
+
+    diesel::delete(categories::table.find(id))
+        .execute(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Status::NoContent)
+}
+
+fn category_write_error(e: diesel::result::Error) -> ApiError {
+    use diesel::result::{DatabaseErrorKind, Error};
+
+    match e {
+        Error::NotFound => ApiError::unprocessable("Parent category does not exist"),
+        Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            ApiError::conflict("That slug or key is already taken")
+        }
+        Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+            ApiError::unprocessable("Parent category does not exist")
+        }
+        e => ApiError::internal(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: i32, parent_id: Option<i32>) -> Category {
+        Category {
+            id,
+            name: format!("Category {}", id),
+            slug: format!("category-{}", id),
+            icon: None,
+            parent_id,
+            sort_order: 0,
+        }
+    }
+
+    #[test]
+    fn descendants_include_the_category_and_its_subtree() {
+        let all = vec![
+            category(1, None),
+            category(2, Some(1)),
+            category(3, Some(2)),
+            category(4, None),
+        ];
+
+        let mut ids = descendant_ids(&all, 1);
+        ids.sort();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(descendant_ids(&all, 4), vec![4]);
+    }
+
+    #[test]
+    fn breadcrumb_runs_from_root_to_category() {
+        let all = vec![category(1, None), category(2, Some(1)), category(3, Some(2))];
+
+        let path: Vec<i32> = breadcrumb(&all, 3).iter().map(|c| c.id).collect();
+        assert_eq!(path, vec![1, 2, 3]);
+    }
 }
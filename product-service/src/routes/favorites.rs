@@ -0,0 +1,148 @@
+use rocket::serde::json::Json;
+use rocket::http::Status;
+use rocket::{get, post, delete};
+use serde::Serialize;
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use std::collections::HashMap;
+
+use crate::db::DbConn;
+use crate::models::{Category, NewFavorite, Product};
+use crate::schema::{categories, favorites, products};
+use handshake_common::error::ApiError;
+use handshake_common::auth::{AdminUser, AuthenticatedUser};
+use crate::routes::products::{can_view, ProductResponse};
+
+#[derive(Debug, Serialize)]
+pub struct FavoriteResponse {
+    pub product_id: i32,
+    pub favorited: bool,
+    pub favorite_count: i64,
+}
+
+/// Count how many users have favorited each of the given products
+pub async fn favorite_counts(
+    conn: &mut AsyncPgConnection,
+    product_ids: &[i32],
+) -> QueryResult<HashMap<i32, i64>> {
+    if product_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let counts: Vec<(i32, i64)> = favorites::table
+        .filter(favorites::product_id.eq_any(product_ids))
+        .group_by(favorites::product_id)
+        .select((favorites::product_id, diesel::dsl::count_star()))
+        .load(conn)
+        .await?;
+
+    Ok(counts.into_iter().collect())
+}
+
+#[post("/<id>/favorite")]
+pub async fn add_favorite(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    admin: Option<AdminUser>,
+    id: i32,
+) -> Result<Json<FavoriteResponse>, ApiError> {
+    let product: Product = products::table
+        .find(id)
+        .filter(products::deleted_at.is_null())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
+
+    // Favoriting mustn't confirm that someone else's unlisted listing exists
+    if !can_view(&product, Some(auth.user_id), admin.is_some()) {
+        return Err(ApiError::not_found("Product not found"));
+    }
+
+    let new_favorite = NewFavorite {
+        user_id: auth.user_id,
+        user_email: auth.email,
+        product_id: id,
+        notified_price: product.price,
+    };
+
+    let favorite_count: i64 = db.transaction(|conn| async move {
+        diesel::insert_into(favorites::table)
+            .values(&new_favorite)
+            .on_conflict((favorites::user_id, favorites::product_id))
+            .do_nothing()
+            .execute(conn)
+            .await?;
+
+        favorites::table
+            .filter(favorites::product_id.eq(id))
+            .count()
+            .get_result(conn)
+            .await
+    }.scope_boxed()).await.map_err(ApiError::internal)?;
+
+    Ok(Json(FavoriteResponse {
+        product_id: id,
+        favorited: true,
+        favorite_count,
+    }))
+}
+
+#[delete("/<id>/favorite")]
+pub async fn remove_favorite(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<FavoriteResponse>, ApiError> {
+    let user_id = auth.user_id;
+
+    let favorite_count: i64 = db.transaction(|conn| async move {
+        diesel::delete(
+            favorites::table
+                .filter(favorites::user_id.eq(user_id))
+                .filter(favorites::product_id.eq(id)),
+        )
+        .execute(conn)
+        .await?;
+
+        favorites::table
+            .filter(favorites::product_id.eq(id))
+            .count()
+            .get_result(conn)
+            .await
+    }.scope_boxed()).await.map_err(ApiError::internal)?;
+
+    Ok(Json(FavoriteResponse {
+        product_id: id,
+        favorited: false,
+        favorite_count,
+    }))
+}
+
+#[get("/favorites")]
+pub async fn my_favorites(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<ProductResponse>>, ApiError> {
+    let user_id = auth.user_id;
+
+    let results: Vec<(Product, Category)> = favorites::table
+        .inner_join(products::table.inner_join(categories::table))
+        .filter(favorites::user_id.eq(user_id))
+        .filter(products::status.ne_all(["draft", "expired", "removed"]))
+        .order(favorites::created_at.desc())
+        .select((products::all_columns, categories::all_columns))
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let ids: Vec<i32> = results.iter().map(|(p, _)| p.id).collect();
+    let counts = favorite_counts(&mut db, &ids).await.map_err(ApiError::internal)?;
+
+    let response: Vec<ProductResponse> = results.into_iter().map(|(p, c)| {
+        let favorite_count = counts.get(&p.id).copied().unwrap_or(0);
+        ProductResponse::new(p, c, favorite_count)
+    }).collect();
+
+    Ok(Json(response))
+}
@@ -1,2 +1,7 @@
 pub mod categories;
+pub mod favorites;
+pub mod moderation;
 pub mod products;
+pub mod reports;
+pub mod saved_searches;
+pub mod stock;
@@ -0,0 +1,160 @@
+use rocket::serde::json::Json;
+use rocket::http::Status;
+use rocket::{get, post};
+use serde::{Deserialize, Serialize};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+
+use crate::db::DbConn;
+use crate::events;
+use crate::models::{ModerationAction, NewModerationAction, Product, ProductReport};
+use crate::schema::{moderation_actions, product_reports, products};
+use handshake_common::error::ApiError;
+use handshake_common::auth::AdminUser;
+
+#[derive(Debug, Serialize)]
+pub struct ModerationQueueItem {
+    pub product_id: i32,
+    pub seller_id: i32,
+    pub title: String,
+    pub product_status: String,
+    pub report_count: usize,
+    pub reports: Vec<ProductReport>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationDecision {
+    /// Reports were unfounded; restore the listing if it was hidden
+    Dismiss,
+    /// Reports were valid; take the listing down
+    Remove,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveReportsRequest {
+    pub decision: ModerationDecision,
+    pub note: Option<String>,
+}
+
+#[get("/reports?<status>")]
+pub async fn moderation_queue(
+    mut db: DbConn,
+    _admin: AdminUser,
+    status: Option<String>,
+) -> Result<Json<Vec<ModerationQueueItem>>, ApiError> {
+    let status = status.unwrap_or_else(|| "open".to_string());
+
+    let results: Vec<(ProductReport, Product)> = product_reports::table
+        .inner_join(products::table)
+        .filter(product_reports::status.eq(&status))
+        .order((product_reports::product_id.asc(), product_reports::created_at.asc()))
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let mut queue: Vec<ModerationQueueItem> = Vec::new();
+    for (report, product) in results {
+        match queue.last_mut() {
+            Some(item) if item.product_id == product.id => item.reports.push(report),
+            _ => queue.push(ModerationQueueItem {
+                product_id: product.id,
+                seller_id: product.seller_id,
+                title: product.title,
+                product_status: product.status,
+                report_count: 0,
+                reports: vec![report],
+            }),
+        }
+    }
+
+    for item in queue.iter_mut() {
+        item.report_count = item.reports.len();
+    }
+
+    // Most reported listings first
+    queue.sort_by_key(|item| std::cmp::Reverse(item.report_count));
+
+    Ok(Json(queue))
+}
+
+#[post("/products/<id>/resolve", data = "<request>")]
+pub async fn resolve_reports(
+    mut db: DbConn,
+    admin: AdminUser,
+    id: i32,
+    request: Json<ResolveReportsRequest>,
+) -> Result<Json<ModerationAction>, ApiError> {
+    let moderator_id = admin.user_id;
+    let decision = request.decision;
+    let note = request.note.clone();
+
+    let product: Product = products::table
+        .find(id)
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
+
+    let action: ModerationAction = db.transaction(|conn| async move {
+        let now = chrono::Utc::now().naive_utc();
+        let (report_status, action) = match decision {
+            ModerationDecision::Dismiss => ("dismissed", "dismissed"),
+            ModerationDecision::Remove => ("actioned", "removed"),
+        };
+
+        diesel::update(
+            product_reports::table
+                .filter(product_reports::product_id.eq(id))
+                .filter(product_reports::status.eq("open")),
+        )
+        .set((
+            product_reports::status.eq(report_status),
+            product_reports::resolved_at.eq(Some(now)),
+            product_reports::resolved_by.eq(Some(moderator_id)),
+        ))
+        .execute(conn)
+        .await?;
+
+        let next_status = match decision {
+            ModerationDecision::Dismiss if product.status == "hidden" => Some("active"),
+            ModerationDecision::Remove => Some("removed"),
+            _ => None,
+        };
+        if let Some(next_status) = next_status {
+            let updated: Product = diesel::update(products::table.find(id))
+                .set(products::status.eq(next_status))
+                .get_result(conn)
+                .await?;
+            events::record_status_change(conn, &product.status, &updated).await?;
+        }
+
+        diesel::insert_into(moderation_actions::table)
+            .values(&NewModerationAction {
+                product_id: id,
+                moderator_id: Some(moderator_id),
+                action: action.to_string(),
+                note,
+            })
+            .get_result(conn)
+            .await
+    }.scope_boxed()).await.map_err(ApiError::internal)?;
+
+    Ok(Json(action))
+}
+
+#[get("/products/<id>/actions")]
+pub async fn moderation_history(
+    mut db: DbConn,
+    _admin: AdminUser,
+    id: i32,
+) -> Result<Json<Vec<ModerationAction>>, ApiError> {
+    let actions: Vec<ModerationAction> = moderation_actions::table
+        .filter(moderation_actions::product_id.eq(id))
+        .order(moderation_actions::created_at.asc())
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(actions))
+}
@@ -1,13 +1,43 @@
 use rocket::serde::json::Json;
 use rocket::http::Status;
-use rocket::{get, post, put, delete};
+use rocket::{get, post, put, delete, State};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use diesel::prelude::*;
+use diesel::PgJsonbExpressionMethods;
+use diesel::dsl::{now, sql};
+use diesel::pg::Pg;
+use diesel::sql_types::{Bool, Double};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use chrono::{NaiveDateTime, Utc};
 
 use crate::db::DbConn;
-use crate::models::{Product, NewProduct, Category};
-use crate::schema::{products, categories};
-use crate::auth::AuthenticatedUser;
+use crate::models::{Product, NewProduct, ProductChangeset, Category, CategoryAttribute, ProductRevision};
+use crate::schema::{products, categories, category_attributes, product_revisions};
+use handshake_common::error::ApiError;
+use handshake_common::auth::{AdminUser, AuthenticatedUser, InternalService};
+use crate::etag::{self, IfMatch, Tagged};
+use crate::events::{self, DomainEvent};
+use crate::geo;
+use crate::revisions;
+use crate::listing::{ItemCondition, ListingStatus, ListingType};
+use crate::locations::fetch_public_location;
+use crate::settings::Settings;
+use crate::attributes::{self, filter_object};
+use crate::routes::categories::{descendant_ids, effective_schema, load_all};
+use crate::routes::favorites::favorite_counts;
+use handshake_common::pagination::{clamp_limit, Cursor, Page};
+use crate::validation::{self, double_option, FieldError};
+
+const DEFAULT_RADIUS_KM: f64 = 10.0;
+const MAX_RADIUS_KM: f64 = 200.0;
+
+/// Where the requested page starts, depending on how results are ordered
+enum After {
+    CreatedAt(Cursor<NaiveDateTime>),
+    Distance(Cursor<f64>),
+}
 
 #[derive(Debug, Serialize)]
 pub struct ProductResponse {
@@ -20,6 +50,48 @@ pub struct ProductResponse {
     pub price: f64,
     pub image_url: Option<String>,
     pub status: String,
+    pub attributes: serde_json::Value,
+    pub condition: String,
+    pub quantity: i32,
+    pub listing_type: String,
+    pub requires_meetup: bool,
+    pub favorite_count: i64,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Distance from the `near` point, only set for location searches
+    pub distance_km: Option<f64>,
+    pub expires_at: NaiveDateTime,
+    /// Bumped on every edit; orders record it to show the listing as ordered
+    pub revision: i32,
+}
+
+impl ProductResponse {
+    pub fn new(product: Product, category: Category, favorite_count: i64) -> Self {
+        ProductResponse {
+            id: product.id,
+            seller_id: product.seller_id,
+            category_id: product.category_id,
+            category_name: category.name,
+            title: product.title,
+            description: product.description,
+            price: product.price,
+            image_url: product.image_url,
+            status: product.status,
+            attributes: product.attributes,
+            requires_meetup: ListingType::parse(&product.listing_type)
+                .map(|t| t.requires_meetup())
+                .unwrap_or(true),
+            condition: product.condition,
+            quantity: product.quantity,
+            listing_type: product.listing_type,
+            favorite_count,
+            latitude: product.latitude,
+            longitude: product.longitude,
+            distance_km: None,
+            expires_at: product.expires_at,
+            revision: product.revision,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,178 +101,851 @@ pub struct CreateProductRequest {
     pub description: String,
     pub price: f64,
     pub image_url: Option<String>,
+    /// Pickup location; defaults to the seller's saved location
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Values for the category's attribute schema
+    pub attributes: Option<serde_json::Value>,
+    pub condition: Option<ItemCondition>,
+    /// Units available; defaults to 1
+    pub quantity: Option<i32>,
+    pub listing_type: Option<ListingType>,
+    /// `draft` to save without publishing; defaults to `active`
+    pub status: Option<ListingStatus>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateProductRequest {
+    pub category_id: Option<i32>,
     pub title: Option<String>,
     pub description: Option<String>,
     pub price: Option<f64>,
-    pub image_url: Option<String>,
+    /// `null` removes the image
+    #[serde(default, deserialize_with = "double_option")]
+    pub image_url: Option<Option<String>>,
     pub status: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub attributes: Option<serde_json::Value>,
+    pub condition: Option<String>,
+    pub quantity: Option<i32>,
+    pub listing_type: Option<String>,
+    /// `updated_at` from the last read, for clients that can't send If-Match
+    pub updated_at: Option<NaiveDateTime>,
 }
 
-#[get("/?<category_id>&<limit>&<offset>")]
+#[get("/?<category_id>&<limit>&<cursor>&<near>&<radius_km>&<attr>")]
 pub async fn list_products(
-    db: DbConn,
+    mut db: DbConn,
     category_id: Option<i32>,
     limit: Option<i64>,
-    offset: Option<i64>,
-) -> Result<Json<Vec<ProductResponse>>, Status> {
-    let limit = limit.unwrap_or(20).min(100);
-    let offset = offset.unwrap_or(0);
+    cursor: Option<String>,
+    near: Option<String>,
+    radius_km: Option<f64>,
+    attr: HashMap<String, String>,
+) -> Result<Json<Page<ProductResponse>>, ApiError> {
+    let limit = clamp_limit(limit);
+
+    let near = match near {
+        Some(ref value) => Some(geo::parse_lat_lon(value)
+            .ok_or_else(|| ApiError::bad_request("near must be \"lat,lon\""))?),
+        None => None,
+    };
+    let radius_km = radius_km.unwrap_or(DEFAULT_RADIUS_KM);
+    if !radius_km.is_finite() || radius_km <= 0.0 || radius_km > MAX_RADIUS_KM {
+        return Err(ApiError::bad_request(format!(
+            "radius_km must be between 0 and {}",
+            MAX_RADIUS_KM
+        )));
+    }
 
-    let products: Vec<(Product, Category)> = db.run(move |conn| {
+    // Location searches page by distance, everything else by recency
+    let after = match (cursor, near) {
+        (Some(ref value), Some(_)) => {
+            let cursor = Cursor::<f64>::decode(value)
+                .filter(|c| c.key.is_finite())
+                .ok_or_else(|| ApiError::bad_request("Invalid cursor"))?;
+            Some(After::Distance(cursor))
+        }
+        (Some(ref value), None) => {
+            let cursor = Cursor::decode(value)
+                .ok_or_else(|| ApiError::bad_request("Invalid cursor"))?;
+            Some(After::CreatedAt(cursor))
+        }
+        (None, _) => None,
+    };
+
+    let attribute_filter = if attr.is_empty() {
+        None
+    } else {
+        let keys: Vec<String> = attr.keys().cloned().collect();
+        let definitions: Vec<CategoryAttribute> = match category_id {
+            Some(cat_id) => effective_schema(&mut db, cat_id).await,
+            None => category_attributes::table
+                .filter(category_attributes::key.eq_any(keys))
+                .load(&mut db)
+                .await,
+        }.map_err(ApiError::internal)?;
+
+        Some(filter_object(&definitions, &attr).map_err(validation::bad_filters)?)
+    };
+
+    let category_ids = match category_id {
+        Some(cat_id) => {
+            let all = load_all(&mut db).await.map_err(ApiError::internal)?;
+            Some(descendant_ids(&all, cat_id))
+        }
+        None => None,
+    };
+
+    // Same filters for the page and for the total
+    let filtered = || {
         let mut query = products::table
             .inner_join(categories::table.on(products::category_id.eq(categories::id)))
+            .filter(products::status.eq("active"))
+            // Listings past expiry drop out now, not when the expiry job next runs
+            .filter(products::expires_at.gt(now))
             .into_boxed();
 
-        if let Some(cat_id) = category_id {
-            query = query.filter(products::category_id.eq(cat_id));
+        if let Some(ref ids) = category_ids {
+            query = query.filter(products::category_id.eq_any(ids.clone()));
+        }
+
+        if let Some(ref filter) = attribute_filter {
+            query = query.filter(products::attributes.contains(filter.clone()));
+        }
+
+        if let Some((lat, lon)) = near {
+            // Narrow down with the geohash index, then filter on exact distance
+            let mut cells: Box<dyn BoxableExpression<_, Pg, SqlType = Bool>> =
+                Box::new(sql::<Bool>("FALSE"));
+            for prefix in geo::covering_prefixes(lat, lon, radius_km) {
+                let pattern = format!("{}%", prefix);
+                cells = Box::new(cells.or(products::geohash.like(pattern).assume_not_null()));
+            }
+
+            let distance = geo::distance_sql(lat, lon);
+            query = query
+                .filter(cells)
+                .filter(sql::<Bool>(&format!("{} <= {}", distance, radius_km)));
         }
 
         query
-            .filter(products::status.eq("active"))
-            .order(products::created_at.desc())
-            .limit(limit)
-            .offset(offset)
-            .load(conn)
-    }).await.map_err(|_| Status::InternalServerError)?;
+    };
 
-    let response: Vec<ProductResponse> = products.into_iter().map(|(p, c)| {
-        ProductResponse {
-            id: p.id,
-            seller_id: p.seller_id,
-            category_id: p.category_id,
-            category_name: c.name,
-            title: p.title,
-            description: p.description,
-            price: p.price,
-            image_url: p.image_url,
-            status: p.status,
+    let total: i64 = filtered()
+        .count()
+        .get_result(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let mut query = filtered();
+    let distance = near.map(|(lat, lon)| geo::distance_sql(lat, lon));
+    match (&distance, &after) {
+        (Some(d), Some(After::Distance(c))) => {
+            query = query.filter(sql::<Bool>(&format!(
+                "({d} > {key} OR ({d} = {key} AND products.id > {id}))",
+                d = d,
+                key = c.key,
+                id = c.id,
+            )));
         }
-    }).collect();
+        (None, Some(After::CreatedAt(c))) => {
+            query = query.filter(
+                products::created_at
+                    .lt(c.key)
+                    .or(products::created_at.eq(c.key).and(products::id.lt(c.id))),
+            );
+        }
+        _ => {}
+    }
+    query = match distance {
+        Some(ref d) => query.order((sql::<Double>(d).asc(), products::id.asc())),
+        None => query.order((products::created_at.desc(), products::id.desc())),
+    };
 
-    Ok(Json(response))
+    // The distance is selected from SQL so cursors compare exactly
+    let products: Vec<(Product, Category, f64)> = query
+        .select((
+            products::all_columns,
+            categories::all_columns,
+            sql::<Double>(distance.as_deref().unwrap_or("0")),
+        ))
+        .limit(limit + 1)
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let ids: Vec<i32> = products.iter().map(|(p, _, _)| p.id).collect();
+    let counts = favorite_counts(&mut db, &ids).await.map_err(ApiError::internal)?;
+
+    let rows: Vec<(ProductResponse, NaiveDateTime, f64)> = products
+        .into_iter()
+        .map(|(p, c, distance)| {
+            let favorite_count = counts.get(&p.id).copied().unwrap_or(0);
+            let created_at = p.created_at;
+            let mut item = ProductResponse::new(p, c, favorite_count);
+            if near.is_some() {
+                item.distance_km = Some(distance);
+            }
+            (item, created_at, distance)
+        })
+        .collect();
+
+    let page = Page::from_rows(rows, limit, total, |(item, created_at, distance)| {
+        if near.is_some() {
+            Cursor::new(*distance, item.id).encode()
+        } else {
+            Cursor::new(*created_at, item.id).encode()
+        }
+    });
+
+    Ok(Json(page.map(|(item, _, _)| item)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProductSummariesRequest {
+    pub ids: Vec<i32>,
+}
+
+/// Just enough of a listing to label it in another service's UI
+#[derive(Debug, Serialize, Queryable)]
+pub struct ProductSummary {
+    pub id: i32,
+    pub title: String,
+    pub image_url: Option<String>,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenewResponse {
+    pub message: String,
+    pub expires_at: NaiveDateTime,
 }
 
 #[get("/<id>")]
 pub async fn get_product(
-    db: DbConn,
+    mut db: DbConn,
+    auth: Option<AuthenticatedUser>,
+    admin: Option<AdminUser>,
     id: i32,
-) -> Result<Json<ProductResponse>, Status> {
-    let product: (Product, Category) = db.run(move |conn| {
-        products::table
-            .inner_join(categories::table.on(products::category_id.eq(categories::id)))
-            .filter(products::id.eq(id))
-            .first(conn)
-    }).await.map_err(|_| Status::NotFound)?;
-
-    Ok(Json(ProductResponse {
-        id: product.0.id,
-        seller_id: product.0.seller_id,
-        category_id: product.0.category_id,
-        category_name: product.1.name,
-        title: product.0.title,
-        description: product.0.description,
-        price: product.0.price,
-        image_url: product.0.image_url,
-        status: product.0.status,
-    }))
+) -> Result<Tagged<Json<ProductResponse>>, ApiError> {
+    let (product, category): (Product, Category) = products::table
+        .inner_join(categories::table.on(products::category_id.eq(categories::id)))
+        .filter(products::id.eq(id))
+        .filter(products::deleted_at.is_null())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
+
+    if !can_view(&product, auth.map(|user| user.user_id), admin.is_some()) {
+        return Err(ApiError::not_found("Product not found"));
+    }
+
+    let counts = favorite_counts(&mut db, &[id]).await.map_err(ApiError::internal)?;
+
+    let favorite_count = counts.get(&id).copied().unwrap_or(0);
+    let tag = etag::for_product(product.id, product.updated_at);
+
+    Ok(Tagged::new(Json(ProductResponse::new(product, category, favorite_count)), tag))
 }
 
 #[post("/", data = "<request>")]
 pub async fn create_product(
-    db: DbConn,
+    mut db: DbConn,
+    settings: &State<Settings>,
     auth: AuthenticatedUser,
     request: Json<CreateProductRequest>,
-) -> Result<Json<Product>, Status> {
+) -> Result<Json<Product>, ApiError> {
+    // Same checks as an update, all reported at once
+    let mut errors = Vec::new();
+    validation::check_title(&request.title, &mut errors);
+    validation::check_description(&request.description, &mut errors);
+    validation::check_price(request.price, &mut errors);
+    if let Some(ref url) = request.image_url {
+        validation::check_image_url(url, &mut errors);
+    }
+
+    let quantity = request.quantity.unwrap_or(1);
+    if quantity < 1 {
+        errors.push(FieldError::new("quantity", "must be at least 1"));
+    }
+
+    let status = request.status.unwrap_or(ListingStatus::Active);
+    if !matches!(status, ListingStatus::Draft | ListingStatus::Active) {
+        errors.push(FieldError::new("status", "must be draft or active"));
+    }
+
+    let given_location = match (request.latitude, request.longitude) {
+        (Some(lat), Some(lon)) if geo::is_valid_coordinate(lat, lon) => Some((lat, lon)),
+        (None, None) => None,
+        (Some(_), Some(_)) => {
+            errors.push(FieldError::new("latitude", "is not a valid coordinate"));
+            None
+        }
+        _ => {
+            errors.push(FieldError::new(
+                "latitude",
+                "latitude and longitude must be given together",
+            ));
+            None
+        }
+    };
+
+    let category_id = request.category_id;
+    let exists = categories::table
+        .find(category_id)
+        .select(categories::id)
+        .first::<i32>(&mut db)
+        .await
+        .optional()
+        .map_err(ApiError::internal)?
+        .is_some();
+
+    let attributes = if exists {
+        let schema = effective_schema(&mut db, category_id).await.map_err(ApiError::internal)?;
+        match attributes::validate(
+            &schema,
+            request.attributes.as_ref().unwrap_or(&serde_json::Value::Null),
+        ) {
+            Ok(values) => values,
+            Err(attribute_errors) => {
+                errors.extend(attribute_errors.into_iter().map(FieldError::from));
+                serde_json::Value::Null
+            }
+        }
+    } else {
+        errors.push(FieldError::new("category_id", "does not exist"));
+        serde_json::Value::Null
+    };
+
+    if !errors.is_empty() {
+        return Err(ApiError::invalid(errors));
+    }
+
+    let location = match given_location {
+        Some((lat, lon)) => Some(settings.fuzz().fuzz(auth.user_id, lat, lon)),
+        // Already fuzzed by order-service with the same settings
+        None => fetch_public_location(settings, auth.user_id)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "Could not fetch seller location");
+                None
+            })
+            .map(|l| (l.latitude, l.longitude)),
+    };
+
     let new_product = NewProduct {
         seller_id: auth.user_id,
         category_id: request.category_id,
-        title: request.title.clone(),
+        title: request.title.trim().to_string(),
         description: request.description.clone(),
         price: request.price,
         image_url: request.image_url.clone(),
+        latitude: location.map(|(lat, _)| lat),
+        longitude: location.map(|(_, lon)| lon),
+        geohash: location.map(|(lat, lon)| geo::encode(lat, lon, geo::STORED_PRECISION)),
+        attributes,
+        condition: request.condition.unwrap_or(ItemCondition::Used).as_str().to_string(),
+        quantity,
+        listing_type: request.listing_type.unwrap_or(ListingType::Physical).as_str().to_string(),
+        status: status.as_str().to_string(),
+        seller_email: Some(auth.email.clone()),
+        expires_at: Utc::now().naive_utc() + settings.listing_ttl(),
     };
 
-    let product: Product = db.run(move |conn| {
-        diesel::insert_into(products::table)
+    let product: Product = db.transaction(|conn| async move {
+        let product: Product = diesel::insert_into(products::table)
             .values(&new_product)
             .get_result(conn)
-    }).await.map_err(|_| Status::InternalServerError)?;
+            .await?;
+        revisions::record_initial(conn, &product).await?;
+        events::record(conn, &DomainEvent::product_created(&product)).await?;
+        Ok::<_, diesel::result::Error>(product)
+    }.scope_boxed()).await.map_err(ApiError::internal)?;
 
     Ok(Json(product))
 }
 
+/// Unlisted listings are only visible to their seller and to moderators;
+/// everyone else gets a 404 as if they didn't exist
+pub fn can_view(product: &Product, viewer_id: Option<i32>, is_admin: bool) -> bool {
+    let unlisted = ListingStatus::parse(&product.status).is_some_and(|status| status.is_unlisted());
+    !unlisted || is_admin || viewer_id == Some(product.seller_id)
+}
+
+fn stale_listing() -> ApiError {
+    ApiError::new(
+        Status::PreconditionFailed,
+        "The listing changed since you last loaded it",
+    )
+}
+
+fn not_renewable() -> ApiError {
+    ApiError::conflict("Only active or expired listings can be renewed")
+}
+
+/// Why a changeset couldn't be written inside the update transaction
+enum UpdateFailure {
+    /// The listing changed since the client last read it
+    Stale,
+    Database(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for UpdateFailure {
+    fn from(err: diesel::result::Error) -> Self {
+        UpdateFailure::Database(err)
+    }
+}
+
+/// Check an update against the listing's current state and turn it into a
+/// changeset, collecting every invalid field rather than stopping at the first.
+/// Category existence and attribute schemas are checked by the caller.
+fn build_changeset(
+    product: &Product,
+    request: &UpdateProductRequest,
+    settings: &Settings,
+) -> Result<ProductChangeset, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let mut changeset = ProductChangeset {
+        category_id: request.category_id,
+        ..Default::default()
+    };
+
+    if let Some(ref title) = request.title {
+        validation::check_title(title, &mut errors);
+        changeset.title = Some(title.trim().to_string());
+    }
+    if let Some(ref description) = request.description {
+        validation::check_description(description, &mut errors);
+        changeset.description = Some(description.clone());
+    }
+    if let Some(price) = request.price {
+        validation::check_price(price, &mut errors);
+        changeset.price = Some(price);
+    }
+    if let Some(ref image_url) = request.image_url {
+        if let Some(url) = image_url {
+            validation::check_image_url(url, &mut errors);
+        }
+        changeset.image_url = Some(image_url.clone());
+    }
+    if let Some(quantity) = request.quantity {
+        if quantity < 0 {
+            errors.push(FieldError::new("quantity", "must not be negative"));
+        }
+        changeset.quantity = Some(quantity);
+    }
+
+    match (request.latitude, request.longitude) {
+        (Some(lat), Some(lon)) if geo::is_valid_coordinate(lat, lon) => {
+            let (lat, lon) = settings.fuzz().fuzz(product.seller_id, lat, lon);
+            changeset.latitude = Some(lat);
+            changeset.longitude = Some(lon);
+            changeset.geohash = Some(geo::encode(lat, lon, geo::STORED_PRECISION));
+        }
+        (None, None) => {}
+        (Some(_), Some(_)) => errors.push(FieldError::new("latitude", "is not a valid coordinate")),
+        _ => errors.push(FieldError::new(
+            "latitude",
+            "latitude and longitude must be given together",
+        )),
+    }
+
+    if let Some(ref value) = request.condition {
+        match ItemCondition::parse(value) {
+            Some(condition) => changeset.condition = Some(condition.as_str().to_string()),
+            None => errors.push(FieldError::new("condition", "is not a known condition")),
+        }
+    }
+    if let Some(ref value) = request.listing_type {
+        match ListingType::parse(value) {
+            Some(listing_type) => changeset.listing_type = Some(listing_type.as_str().to_string()),
+            None => errors.push(FieldError::new("listing_type", "is not a known listing type")),
+        }
+    }
+
+    if let Some(ref value) = request.status {
+        let current = ListingStatus::parse(&product.status);
+        match ListingStatus::parse(value) {
+            None => errors.push(FieldError::new("status", "is not a known status")),
+            Some(next) if !current.is_some_and(|c| c.seller_can_change_to(next)) => {
+                errors.push(FieldError::new(
+                    "status",
+                    &format!("cannot change from {} to {}", product.status, value),
+                ));
+            }
+            Some(next) => {
+                // Publishing a draft starts its listing period
+                if current == Some(ListingStatus::Draft) && next == ListingStatus::Active {
+                    changeset.expires_at = Some(Utc::now().naive_utc() + settings.listing_ttl());
+                }
+                changeset.status = Some(next.as_str().to_string());
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(changeset)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Update any subset of a listing's fields in one transaction. Send the
+/// `ETag` from a previous read as `If-Match` (or its `updated_at` in the
+/// body) to fail with 412 instead of overwriting someone else's change.
 #[put("/<id>", data = "<request>")]
 pub async fn update_product(
-    db: DbConn,
+    mut db: DbConn,
+    settings: &State<Settings>,
     auth: AuthenticatedUser,
+    if_match: IfMatch,
     id: i32,
     request: Json<UpdateProductRequest>,
-) -> Result<Json<Product>, Status> {
+) -> Result<Tagged<Json<Product>>, ApiError> {
     let user_id = auth.user_id;
-    
+    let request = request.into_inner();
+
     // Check ownership
-    let product: Product = db.run(move |conn| {
-        products::table.find(id).first(conn)
-    }).await.map_err(|_| Status::NotFound)?;
+    let product: Product = products::table
+        .find(id)
+        .filter(products::deleted_at.is_null())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
 
     if product.seller_id != user_id {
-        return Err(Status::Forbidden);
+        return Err(ApiError::forbidden("Only the seller can edit this listing"));
     }
 
-    let updated: Product = db.run(move |conn| {
-        let target = products::table.find(id);
-        
-        if let Some(ref title) = request.title {
-            diesel::update(target)
-                .set(products::title.eq(title))
-                .execute(conn)?;
-        }
-        if let Some(ref description) = request.description {
-            diesel::update(target)
-                .set(products::description.eq(description))
-                .execute(conn)?;
+    let is_stale = move |current: &Product| {
+        !if_match.allows(&etag::for_product(current.id, current.updated_at))
+            || request.updated_at.is_some_and(|seen| seen != current.updated_at)
+    };
+    if is_stale(&product) {
+        return Err(stale_listing());
+    }
+
+    let mut errors = Vec::new();
+    let mut changeset = match build_changeset(&product, &request, settings) {
+        Ok(changeset) => changeset,
+        Err(field_errors) => {
+            errors = field_errors;
+            ProductChangeset::default()
         }
-        if let Some(price) = request.price {
-            diesel::update(target)
-                .set(products::price.eq(price))
-                .execute(conn)?;
+    };
+
+    // Attributes are checked against the schema of the category the listing
+    // ends up in; moving category re-checks the existing values too
+    let category_id = request.category_id.unwrap_or(product.category_id);
+    let category_changed = category_id != product.category_id;
+    if category_changed || request.attributes.is_some() {
+        let exists = categories::table
+            .find(category_id)
+            .select(categories::id)
+            .first::<i32>(&mut db)
+            .await
+            .optional()
+            .map_err(ApiError::internal)?
+            .is_some();
+        let schema = if exists {
+            Some(effective_schema(&mut db, category_id).await.map_err(ApiError::internal)?)
+        } else {
+            None
+        };
+
+        match schema {
+            Some(schema) => {
+                let values = request.attributes.as_ref().unwrap_or(&product.attributes);
+                match attributes::validate(&schema, values) {
+                    Ok(values) => changeset.attributes = Some(values),
+                    Err(attribute_errors) => {
+                        errors.extend(attribute_errors.into_iter().map(FieldError::from));
+                    }
+                }
+            }
+            None => errors.push(FieldError::new("category_id", "does not exist")),
         }
-        if let Some(ref status) = request.status {
-            diesel::update(target)
-                .set(products::status.eq(status))
-                .execute(conn)?;
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    changeset.updated_at = Some(Utc::now().naive_utc());
+
+    let updated = db.transaction(|conn| async move {
+        let target = products::table.find(id);
+        let before: Product = target.for_update().first(conn).await?;
+        if is_stale(&before) {
+            return Err(UpdateFailure::Stale);
         }
-        
-        products::table.find(id).first(conn)
-    }).await.map_err(|_| Status::InternalServerError)?;
 
-    Ok(Json(updated))
+        let after: Product = diesel::update(target)
+            .set(&changeset)
+            .get_result(conn)
+            .await?;
+        events::record_status_change(conn, &before.status, &after).await?;
+
+        Ok(revisions::record_edit(conn, &before, after, user_id).await?)
+    }.scope_boxed()).await.map_err(|err| match err {
+        UpdateFailure::Stale => stale_listing(),
+        UpdateFailure::Database(e) => ApiError::internal(e),
+    })?;
+
+    let tag = etag::for_product(updated.id, updated.updated_at);
+    Ok(Tagged::new(Json(updated), tag))
 }
 
 #[delete("/<id>")]
 pub async fn delete_product(
-    db: DbConn,
+    mut db: DbConn,
     auth: AuthenticatedUser,
     id: i32,
-) -> Result<Status, Status> {
+) -> Result<Status, ApiError> {
     let user_id = auth.user_id;
     
     // Check ownership
-    let product: Product = db.run(move |conn| {
-        products::table.find(id).first(conn)
-    }).await.map_err(|_| Status::NotFound)?;
+    let product: Product = products::table
+        .find(id)
+        .filter(products::deleted_at.is_null())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
 
     if product.seller_id != user_id {
-        return Err(Status::Forbidden);
+        return Err(ApiError::forbidden("Only the seller can delete this listing"));
     }
 
-    db.run(move |conn| {
-        diesel::delete(products::table.find(id)).execute(conn)
-    }).await.map_err(|_| Status::InternalServerError)?;
+    // Orders keep pointing at the row, so it is only taken off the market
+    db.transaction(|conn| async move {
+        let removed: Product = diesel::update(products::table.find(id))
+            .set((
+                products::status.eq(ListingStatus::Removed.as_str()),
+                products::deleted_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn)
+            .await?;
+        events::record_status_change(conn, &product.status, &removed).await
+    }.scope_boxed()).await.map_err(ApiError::internal)?;
 
     Ok(Status::NoContent)
 }
+
+/// Seller's own listings in every state, including drafts and expired ones
+#[get("/listings?<status>")]
+pub async fn my_listings(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    status: Option<String>,
+) -> Result<Json<Vec<ProductResponse>>, ApiError> {
+    let user_id = auth.user_id;
+    let status = match status {
+        Some(ref value) => Some(ListingStatus::parse(value)
+            .ok_or_else(|| ApiError::bad_request("Unknown listing status"))?),
+        None => None,
+    };
+
+    let mut query = products::table
+        .inner_join(categories::table.on(products::category_id.eq(categories::id)))
+        .filter(products::seller_id.eq(user_id))
+        .filter(products::deleted_at.is_null())
+        .into_boxed();
+
+    if let Some(status) = status {
+        query = query.filter(products::status.eq(status.as_str()));
+    }
+
+    let results: Vec<(Product, Category)> = query
+        .order(products::created_at.desc())
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let ids: Vec<i32> = results.iter().map(|(p, _)| p.id).collect();
+    let counts = favorite_counts(&mut db, &ids).await.map_err(ApiError::internal)?;
+
+    let response: Vec<ProductResponse> = results.into_iter().map(|(p, c)| {
+        let favorite_count = counts.get(&p.id).copied().unwrap_or(0);
+        ProductResponse::new(p, c, favorite_count)
+    }).collect();
+
+    Ok(Json(response))
+}
+
+/// Push an active or expired listing's expiry out by another listing period
+async fn renew(
+    conn: &mut AsyncPgConnection,
+    settings: &Settings,
+    product: &Product,
+) -> QueryResult<Product> {
+    let status = if product.quantity > 0 { "active" } else { "sold" };
+
+    conn.transaction(|conn| async move {
+        let renewed: Product = diesel::update(products::table.find(product.id))
+            .set((
+                products::status.eq(status),
+                products::expires_at.eq(Utc::now().naive_utc() + settings.listing_ttl()),
+                products::expiry_reminded_at.eq(None::<NaiveDateTime>),
+                products::renew_token.eq(None::<String>),
+            ))
+            .get_result(conn)
+            .await?;
+        events::record_status_change(conn, &product.status, &renewed).await?;
+        Ok(renewed)
+    }.scope_boxed()).await
+}
+
+fn is_renewable(product: &Product) -> bool {
+    matches!(product.status.as_str(), "active" | "expired")
+}
+
+#[post("/<id>/renew")]
+pub async fn renew_product(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<Product>, ApiError> {
+    let product: Product = products::table
+        .find(id)
+        .filter(products::deleted_at.is_null())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
+
+    if product.seller_id != auth.user_id {
+        return Err(ApiError::forbidden("Only the seller can renew this listing"));
+    }
+
+    if !is_renewable(&product) {
+        return Err(not_renewable());
+    }
+
+    let renewed = renew(&mut db, settings, &product).await.map_err(ApiError::internal)?;
+
+    Ok(Json(renewed))
+}
+
+/// One-click renewal link included in expiry reminder emails
+#[get("/renew/<token>")]
+pub async fn renew_by_token(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    token: String,
+) -> Result<Json<RenewResponse>, ApiError> {
+    let product: Product = products::table
+        .filter(products::renew_token.eq(token))
+        .filter(products::deleted_at.is_null())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
+
+    if !is_renewable(&product) {
+        return Err(not_renewable());
+    }
+
+    let renewed = renew(&mut db, settings, &product).await.map_err(ApiError::internal)?;
+
+    Ok(Json(RenewResponse {
+        message: format!("\"{}\" has been renewed.", renewed.title),
+        expires_at: renewed.expires_at,
+    }))
+}
+
+/// Edit history of a listing, newest first. Seller only.
+#[get("/<id>/revisions")]
+pub async fn list_revisions(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Json<Vec<ProductRevision>>, ApiError> {
+    let product: Product = products::table
+        .find(id)
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
+
+    if product.seller_id != auth.user_id {
+        return Err(ApiError::forbidden("Only the seller can see a listing's history"));
+    }
+
+    let history = product_revisions::table
+        .filter(product_revisions::product_id.eq(id))
+        .order(product_revisions::revision.desc())
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(history))
+}
+
+/// A listing as it was at a given revision, or at a point in time for orders
+/// placed before revisions were recorded. Called by order-service so order
+/// participants can see what they agreed to, even after edits or deletion.
+#[get("/<id>/snapshot?<revision>&<at>")]
+pub async fn product_snapshot(
+    mut db: DbConn,
+    _service: InternalService,
+    id: i32,
+    revision: Option<i32>,
+    at: Option<String>,
+) -> Result<Json<ProductRevision>, ApiError> {
+    let at = match at {
+        Some(ref value) => Some(value
+            .parse::<NaiveDateTime>()
+            .map_err(|_| ApiError::bad_request("at must be a timestamp"))?),
+        None => None,
+    };
+
+    let mut query = product_revisions::table
+        .filter(product_revisions::product_id.eq(id))
+        .into_boxed();
+
+    match (revision, at) {
+        (Some(revision), _) => {
+            query = query.filter(product_revisions::revision.eq(revision));
+        }
+        (None, Some(at)) => {
+            query = query.filter(product_revisions::created_at.le(at));
+        }
+        (None, None) => {}
+    }
+
+    let snapshot = query
+        .order(product_revisions::revision.desc())
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "No revision of this product"))?;
+
+    Ok(Json(snapshot))
+}
+
+/// Titles and images for a batch of listings, including deleted ones, so
+/// order-service can label order lists without a request per order
+#[post("/summaries", data = "<request>")]
+pub async fn product_summaries(
+    mut db: DbConn,
+    _service: InternalService,
+    request: Json<ProductSummariesRequest>,
+) -> Result<Json<Vec<ProductSummary>>, ApiError> {
+    const MAX_SUMMARIES: usize = 200;
+
+    let ids = request.into_inner().ids;
+    if ids.len() > MAX_SUMMARIES {
+        return Err(ApiError::new(
+            Status::PayloadTooLarge,
+            format!("At most {} summaries can be requested at once", MAX_SUMMARIES),
+        ));
+    }
+
+    let summaries = products::table
+        .filter(products::id.eq_any(ids))
+        .select((products::id, products::title, products::image_url, products::status))
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(summaries))
+}
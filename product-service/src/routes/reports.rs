@@ -0,0 +1,134 @@
+use rocket::serde::json::Json;
+use rocket::http::Status;
+use rocket::{post, State};
+use serde::Deserialize;
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+
+use crate::db::DbConn;
+use crate::events;
+use crate::models::{NewModerationAction, NewProductReport, Product, ProductReport};
+use crate::schema::{moderation_actions, product_reports, products};
+use crate::settings::Settings;
+use handshake_common::error::ApiError;
+use handshake_common::auth::AuthenticatedUser;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Scam,
+    ProhibitedItem,
+    Counterfeit,
+    Misleading,
+    Offensive,
+    Spam,
+    Other,
+}
+
+impl ReportReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportReason::Scam => "scam",
+            ReportReason::ProhibitedItem => "prohibited_item",
+            ReportReason::Counterfeit => "counterfeit",
+            ReportReason::Misleading => "misleading",
+            ReportReason::Offensive => "offensive",
+            ReportReason::Spam => "spam",
+            ReportReason::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReportRequest {
+    pub reason: ReportReason,
+    pub details: Option<String>,
+}
+
+#[post("/<id>/reports", data = "<request>")]
+pub async fn report_product(
+    mut db: DbConn,
+    settings: &State<Settings>,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<CreateReportRequest>,
+) -> Result<Json<ProductReport>, ApiError> {
+    let reporter_id = auth.user_id;
+
+    let product: Product = products::table
+        .find(id)
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
+
+    if product.seller_id == reporter_id {
+        return Err(ApiError::bad_request("You can't report your own listing"));
+    }
+
+    // One open report per reporter; after a moderator resolves it they may
+    // report the listing again
+    let existing: Option<ProductReport> = product_reports::table
+        .filter(product_reports::product_id.eq(id))
+        .filter(product_reports::reporter_id.eq(reporter_id))
+        .filter(product_reports::status.eq("open"))
+        .first(&mut db)
+        .await
+        .optional()
+        .map_err(ApiError::internal)?;
+
+    if existing.is_some() {
+        return Err(ApiError::conflict("You already have an open report on this listing"));
+    }
+
+    let new_report = NewProductReport {
+        product_id: id,
+        reporter_id,
+        reason: request.reason.as_str().to_string(),
+        details: request.details.clone(),
+    };
+    let threshold = settings.report_hide_threshold;
+
+    let report: ProductReport = db.transaction(|conn| async move {
+        let report: ProductReport = diesel::insert_into(product_reports::table)
+            .values(&new_report)
+            .get_result(conn)
+            .await?;
+
+        let open_reports: i64 = product_reports::table
+            .filter(product_reports::product_id.eq(id))
+            .filter(product_reports::status.eq("open"))
+            .count()
+            .get_result(conn)
+            .await?;
+
+        if open_reports >= threshold {
+            let hidden: Option<Product> = diesel::update(
+                products::table
+                    .find(id)
+                    .filter(products::status.eq("active")),
+            )
+            .set(products::status.eq("hidden"))
+            .get_result(conn)
+            .await
+            .optional()?;
+
+            if let Some(product) = hidden {
+                events::record_status_change(conn, "active", &product).await?;
+                diesel::insert_into(moderation_actions::table)
+                    .values(&NewModerationAction {
+                        product_id: id,
+                        moderator_id: None,
+                        action: "auto_hidden".to_string(),
+                        note: Some(format!("{} open reports", open_reports)),
+                    })
+                    .execute(conn)
+                    .await?;
+            }
+        }
+
+        Ok::<_, diesel::result::Error>(report)
+    }.scope_boxed()).await.map_err(ApiError::internal)?;
+
+    Ok(Json(report))
+}
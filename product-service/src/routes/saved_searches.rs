@@ -0,0 +1,336 @@
+use rocket::serde::json::Json;
+use rocket::http::Status;
+use rocket::{get, post, put, delete};
+use serde::{Deserialize, Serialize};
+use diesel::prelude::*;
+use diesel::dsl::{now, sql};
+use diesel::sql_types::Bool;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use chrono::{Duration, NaiveDateTime};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+
+use crate::db::DbConn;
+use crate::models::{NewSavedSearch, Product, SavedSearch};
+use crate::schema::{categories, products, saved_searches};
+use handshake_common::error::ApiError;
+use handshake_common::auth::AuthenticatedUser;
+use crate::geo;
+use crate::routes::categories::{descendant_ids, load_all};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchFrequency {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+impl SearchFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchFrequency::Hourly => "hourly",
+            SearchFrequency::Daily => "daily",
+            SearchFrequency::Weekly => "weekly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hourly" => Some(SearchFrequency::Hourly),
+            "daily" => Some(SearchFrequency::Daily),
+            "weekly" => Some(SearchFrequency::Weekly),
+            _ => None,
+        }
+    }
+
+    /// Minimum time between two digests for a search
+    pub fn period(&self) -> Duration {
+        match self {
+            SearchFrequency::Hourly => Duration::hours(1),
+            SearchFrequency::Daily => Duration::days(1),
+            SearchFrequency::Weekly => Duration::weeks(1),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSavedSearchRequest {
+    pub name: Option<String>,
+    pub category_slug: Option<String>,
+    pub keywords: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub max_distance_km: Option<f64>,
+    pub frequency: Option<SearchFrequency>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSavedSearchRequest {
+    pub frequency: Option<SearchFrequency>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnsubscribeResponse {
+    pub message: String,
+}
+
+fn generate_unsubscribe_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// `%keyword%` for ILIKE, with `%`, `_` and backslashes in the keyword matched literally
+fn contains_pattern(keyword: &str) -> String {
+    let mut pattern = String::with_capacity(keyword.len() + 2);
+    pattern.push('%');
+    for c in keyword.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Active products matching a saved search that were listed after `since`
+pub async fn matching_products(
+    conn: &mut AsyncPgConnection,
+    search: &SavedSearch,
+    since: NaiveDateTime,
+) -> QueryResult<Vec<Product>> {
+    let mut query = products::table
+        .inner_join(categories::table)
+        .select(products::all_columns)
+        .filter(products::status.eq("active"))
+        .filter(products::expires_at.gt(now))
+        .filter(products::created_at.gt(since))
+        .filter(products::seller_id.ne(search.user_id))
+        .into_boxed();
+
+    if let Some(ref slug) = search.category_slug {
+        let all = load_all(conn).await?;
+        let ids = match all.iter().find(|c| &c.slug == slug) {
+            Some(root) => descendant_ids(&all, root.id),
+            None => return Ok(Vec::new()),
+        };
+        query = query.filter(categories::id.eq_any(ids));
+    }
+    if let Some(min_price) = search.min_price {
+        query = query.filter(products::price.ge(min_price));
+    }
+    if let Some(max_price) = search.max_price {
+        query = query.filter(products::price.le(max_price));
+    }
+    if let (Some(lat), Some(lon), Some(radius_km)) =
+        (search.latitude, search.longitude, search.max_distance_km)
+    {
+        if geo::is_valid_coordinate(lat, lon) && radius_km.is_finite() {
+            query = query.filter(sql::<Bool>(&format!(
+                "{} <= {}",
+                geo::distance_sql(lat, lon),
+                radius_km
+            )));
+        }
+    }
+    if let Some(ref keywords) = search.keywords {
+        for keyword in keywords.split_whitespace() {
+            let pattern = contains_pattern(keyword);
+            query = query.filter(
+                products::title
+                    .ilike(pattern.clone())
+                    .or(products::description.ilike(pattern)),
+            );
+        }
+    }
+
+    query
+        .order(products::created_at.desc())
+        .limit(50)
+        .load(conn)
+        .await
+}
+
+#[post("/", data = "<request>")]
+pub async fn create_saved_search(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    request: Json<CreateSavedSearchRequest>,
+) -> Result<Json<SavedSearch>, ApiError> {
+    let request = request.into_inner();
+
+    if let (Some(min), Some(max)) = (request.min_price, request.max_price) {
+        if min > max {
+            return Err(ApiError::bad_request("min_price must not exceed max_price"));
+        }
+    }
+    match (request.latitude, request.longitude, request.max_distance_km) {
+        (Some(lat), Some(lon), Some(radius_km)) => {
+            if !geo::is_valid_coordinate(lat, lon) || !radius_km.is_finite() || radius_km <= 0.0 {
+                return Err(ApiError::bad_request("Not a valid location or distance"));
+            }
+        }
+        (None, None, None) => {}
+        _ => {
+            return Err(ApiError::bad_request(
+                "latitude, longitude and max_distance_km must be given together",
+            ))
+        }
+    }
+
+    let name = request.name.unwrap_or_else(|| {
+        request
+            .keywords
+            .clone()
+            .or_else(|| request.category_slug.clone())
+            .unwrap_or_else(|| "Saved search".to_string())
+    });
+
+    let new_search = NewSavedSearch {
+        user_id: auth.user_id,
+        user_email: auth.email,
+        name,
+        category_slug: request.category_slug,
+        keywords: request.keywords,
+        min_price: request.min_price,
+        max_price: request.max_price,
+        latitude: request.latitude,
+        longitude: request.longitude,
+        max_distance_km: request.max_distance_km,
+        frequency: request
+            .frequency
+            .unwrap_or(SearchFrequency::Daily)
+            .as_str()
+            .to_string(),
+        unsubscribe_token: generate_unsubscribe_token(),
+    };
+
+    let search: SavedSearch = diesel::insert_into(saved_searches::table)
+        .values(&new_search)
+        .get_result(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(search))
+}
+
+#[get("/")]
+pub async fn list_saved_searches(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<SavedSearch>>, ApiError> {
+    let user_id = auth.user_id;
+
+    let searches: Vec<SavedSearch> = saved_searches::table
+        .filter(saved_searches::user_id.eq(user_id))
+        .order(saved_searches::created_at.desc())
+        .load(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(searches))
+}
+
+#[put("/<id>", data = "<request>")]
+pub async fn update_saved_search(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+    request: Json<UpdateSavedSearchRequest>,
+) -> Result<Json<SavedSearch>, ApiError> {
+    let user_id = auth.user_id;
+
+    let search: SavedSearch = saved_searches::table
+        .find(id)
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Saved search not found"))?;
+
+    if search.user_id != user_id {
+        return Err(ApiError::forbidden("This saved search belongs to someone else"));
+    }
+
+    let frequency = request
+        .frequency
+        .map(|f| f.as_str().to_string())
+        .unwrap_or(search.frequency);
+    let active = request.active.unwrap_or(search.active);
+
+    let updated: SavedSearch = diesel::update(saved_searches::table.find(id))
+        .set((
+            saved_searches::frequency.eq(frequency),
+            saved_searches::active.eq(active),
+        ))
+        .get_result(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(updated))
+}
+
+#[delete("/<id>")]
+pub async fn delete_saved_search(
+    mut db: DbConn,
+    auth: AuthenticatedUser,
+    id: i32,
+) -> Result<Status, ApiError> {
+    let user_id = auth.user_id;
+
+    let search: SavedSearch = saved_searches::table
+        .find(id)
+        .first(&mut db)
+        .await
+        .map_err(ApiError::when_missing(Status::NotFound, "Saved search not found"))?;
+
+    if search.user_id != user_id {
+        return Err(ApiError::forbidden("This saved search belongs to someone else"));
+    }
+
+    diesel::delete(saved_searches::table.find(id))
+        .execute(&mut db)
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(Status::NoContent)
+}
+
+/// One-click unsubscribe link included in every digest email
+#[get("/unsubscribe/<token>")]
+pub async fn unsubscribe(
+    mut db: DbConn,
+    token: String,
+) -> Result<Json<UnsubscribeResponse>, ApiError> {
+    let updated =
+        diesel::update(saved_searches::table.filter(saved_searches::unsubscribe_token.eq(token)))
+            .set(saved_searches::active.eq(false))
+            .execute(&mut db)
+            .await
+            .map_err(ApiError::internal)?;
+
+    if updated == 0 {
+        return Err(ApiError::not_found("Unsubscribe link is invalid"));
+    }
+
+    Ok(Json(UnsubscribeResponse {
+        message: "You will no longer receive emails for this saved search.".to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_pattern_escapes_wildcards() {
+        assert_eq!(contains_pattern("bike"), "%bike%");
+        assert_eq!(contains_pattern("100%"), "%100\\%%");
+        assert_eq!(contains_pattern("a_b\\c"), "%a\\_b\\\\c%");
+    }
+}
@@ -0,0 +1,137 @@
+use chrono::Utc;
+use rocket::serde::json::Json;
+use rocket::http::Status;
+use rocket::post;
+use serde::{Deserialize, Serialize};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+use crate::db::DbConn;
+use crate::events;
+use crate::models::Product;
+use crate::schema::products;
+use handshake_common::error::{ApiError, FieldError};
+use handshake_common::auth::InternalService;
+
+#[derive(Debug, Deserialize)]
+pub struct StockRequest {
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StockResponse {
+    pub product_id: i32,
+    pub quantity: i32,
+    pub status: String,
+}
+
+/// Take units out of stock for a new order. Called by order-service; fails
+/// with 409 Conflict if the listing isn't active, has lapsed or has too few
+/// units left.
+#[post("/<id>/stock/reserve", data = "<request>")]
+pub async fn reserve_stock(
+    mut db: DbConn,
+    _service: InternalService,
+    id: i32,
+    request: Json<StockRequest>,
+) -> Result<Json<StockResponse>, ApiError> {
+    let quantity = request.quantity;
+    if quantity < 1 {
+        return Err(FieldError::new("quantity", "must be at least 1").into());
+    }
+
+    let now = Utc::now().naive_utc();
+    let product: Option<Product> = db.transaction(|conn| async move {
+        let product: Option<Product> = diesel::update(
+            products::table
+                .find(id)
+                .filter(products::status.eq("active"))
+                // Not yet swept by the expiry job, but no longer on sale
+                .filter(products::expires_at.gt(now))
+                .filter(products::quantity.ge(quantity)),
+        )
+        .set(products::quantity.eq(products::quantity - quantity))
+        .get_result(conn)
+        .await
+        .optional()?;
+
+        // The last unit is spoken for until the order completes or is cancelled
+        match product {
+            Some(p) if p.quantity == 0 => {
+                let reserved: Product = diesel::update(products::table.find(id))
+                    .set(products::status.eq("reserved"))
+                    .get_result(conn)
+                    .await?;
+                events::record_status_change(conn, &p.status, &reserved).await?;
+                Ok::<_, diesel::result::Error>(Some(reserved))
+            }
+            other => Ok(other),
+        }
+    }.scope_boxed()).await.map_err(ApiError::internal)?;
+
+    let product = product
+        .ok_or_else(|| ApiError::conflict("Listing is not active or has too few units left"))?;
+
+    Ok(Json(StockResponse {
+        product_id: product.id,
+        quantity: product.quantity,
+        status: product.status,
+    }))
+}
+
+/// Put units back into stock, e.g. when an order fails to be placed after its
+/// units were reserved. Cancelled orders return theirs through the event bus.
+#[post("/<id>/stock/release", data = "<request>")]
+pub async fn release_stock(
+    mut db: DbConn,
+    _service: InternalService,
+    id: i32,
+    request: Json<StockRequest>,
+) -> Result<Json<StockResponse>, ApiError> {
+    let quantity = request.quantity;
+    if quantity < 1 {
+        return Err(FieldError::new("quantity", "must be at least 1").into());
+    }
+
+    let product: Product = db.transaction(|conn| async move {
+        return_units(conn, id, quantity).await
+    }.scope_boxed()).await.map_err(ApiError::when_missing(Status::NotFound, "Product not found"))?;
+
+    Ok(Json(StockResponse {
+        product_id: product.id,
+        quantity: product.quantity,
+        status: product.status,
+    }))
+}
+
+/// Put `quantity` units back on the listing, reopening it if it was reserved
+/// for the order that held its last units. Run inside a transaction.
+pub async fn return_units(
+    conn: &mut AsyncPgConnection,
+    id: i32,
+    quantity: i32,
+) -> QueryResult<Product> {
+    diesel::update(products::table.find(id))
+        .set(products::quantity.eq(products::quantity + quantity))
+        .execute(conn)
+        .await?;
+
+    let reopened: Option<Product> = diesel::update(
+        products::table
+            .find(id)
+            .filter(products::status.eq("reserved")),
+    )
+    .set(products::status.eq("active"))
+    .get_result(conn)
+    .await
+    .optional()?;
+
+    match reopened {
+        Some(product) => {
+            events::record_status_change(conn, "reserved", &product).await?;
+            Ok(product)
+        }
+        None => products::table.find(id).first(conn).await,
+    }
+}
@@ -9,6 +9,87 @@ diesel::table! {
         slug -> Varchar,
         #[max_length = 255]
         icon -> Nullable<Varchar>,
+        parent_id -> Nullable<Int4>,
+        sort_order -> Int4,
+    }
+}
+
+diesel::table! {
+    category_attributes (id) {
+        id -> Int4,
+        category_id -> Int4,
+        #[max_length = 50]
+        key -> Varchar,
+        #[max_length = 100]
+        label -> Varchar,
+        #[max_length = 20]
+        value_type -> Varchar,
+        options -> Nullable<Jsonb>,
+        required -> Bool,
+        sort_order -> Int4,
+    }
+}
+
+diesel::table! {
+    favorites (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 255]
+        user_email -> Varchar,
+        product_id -> Int4,
+        notified_price -> Float8,
+        reserved_notified -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    moderation_actions (id) {
+        id -> Int4,
+        product_id -> Int4,
+        moderator_id -> Nullable<Int4>,
+        #[max_length = 50]
+        action -> Varchar,
+        note -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    processed_events (event_id) {
+        #[max_length = 100]
+        event_id -> Varchar,
+        #[max_length = 64]
+        event_type -> Varchar,
+        processed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    product_revisions (id) {
+        id -> Int4,
+        product_id -> Int4,
+        revision -> Int4,
+        editor_id -> Int4,
+        changed_fields -> Jsonb,
+        snapshot -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    product_reports (id) {
+        id -> Int4,
+        product_id -> Int4,
+        reporter_id -> Int4,
+        #[max_length = 50]
+        reason -> Varchar,
+        details -> Nullable<Text>,
+        #[max_length = 50]
+        status -> Varchar,
+        created_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
+        resolved_by -> Nullable<Int4>,
     }
 }
 
@@ -26,9 +107,71 @@ diesel::table! {
         #[max_length = 50]
         status -> Varchar,
         created_at -> Timestamp,
+        latitude -> Nullable<Float8>,
+        longitude -> Nullable<Float8>,
+        #[max_length = 12]
+        geohash -> Nullable<Varchar>,
+        attributes -> Jsonb,
+        #[max_length = 20]
+        condition -> Varchar,
+        quantity -> Int4,
+        #[max_length = 20]
+        listing_type -> Varchar,
+        #[max_length = 255]
+        seller_email -> Nullable<Varchar>,
+        expires_at -> Timestamp,
+        expiry_reminded_at -> Nullable<Timestamp>,
+        #[max_length = 64]
+        renew_token -> Nullable<Varchar>,
+        deleted_at -> Nullable<Timestamp>,
+        revision -> Int4,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    saved_searches (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 255]
+        user_email -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+        #[max_length = 100]
+        category_slug -> Nullable<Varchar>,
+        #[max_length = 255]
+        keywords -> Nullable<Varchar>,
+        min_price -> Nullable<Float8>,
+        max_price -> Nullable<Float8>,
+        latitude -> Nullable<Float8>,
+        longitude -> Nullable<Float8>,
+        max_distance_km -> Nullable<Float8>,
+        #[max_length = 20]
+        frequency -> Varchar,
+        active -> Bool,
+        #[max_length = 64]
+        unsubscribe_token -> Varchar,
+        last_notified_at -> Timestamp,
+        created_at -> Timestamp,
+        notified_through -> Timestamp,
     }
 }
 
+diesel::joinable!(category_attributes -> categories (category_id));
+diesel::joinable!(favorites -> products (product_id));
+diesel::joinable!(moderation_actions -> products (product_id));
+diesel::joinable!(product_reports -> products (product_id));
+diesel::joinable!(product_revisions -> products (product_id));
 diesel::joinable!(products -> categories (category_id));
 
-diesel::allow_tables_to_appear_in_same_query!(categories, products,);
+diesel::allow_tables_to_appear_in_same_query!(
+    categories,
+    category_attributes,
+    favorites,
+    moderation_actions,
+    processed_events,
+    product_reports,
+    product_revisions,
+    products,
+    saved_searches,
+);
@@ -0,0 +1,146 @@
+use chrono::Duration;
+use handshake_common::auth::AuthConfig;
+use handshake_common::config::{self, check_positive, check_url, Validate};
+use handshake_common::db::DatabaseConfig;
+use handshake_common::error::FieldError;
+use handshake_common::events::EventSettings;
+use handshake_common::privacy::{FuzzConfig, LocationPrivacy};
+use serde::Deserialize;
+
+/// Everything product-service reads from `Rocket.toml` and the environment,
+/// loaded once in `main` and managed as Rocket state
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub database: DatabaseConfig,
+    /// Public URL of the frontend, for listing links in emails and the only
+    /// origin besides the local dev server allowed to call in
+    #[serde(default, deserialize_with = "config::optional_text")]
+    pub app_url: Option<String>,
+    /// Public URL of this service, for one-click renew and unsubscribe links
+    #[serde(default = "default_product_service_url")]
+    pub product_service_url: String,
+    #[serde(default = "default_order_service_url")]
+    pub order_service_url: String,
+    #[serde(default = "default_email_service_url")]
+    pub email_service_url: String,
+    /// Applied to seller-supplied pickup locations before they are stored
+    #[serde(flatten)]
+    pub location: LocationPrivacy,
+    /// How long a listing stays up before it has to be renewed
+    #[serde(default = "default_listing_ttl_days")]
+    pub listing_ttl_days: i64,
+    /// How long before expiry sellers are reminded to renew
+    #[serde(default = "default_listing_expiry_reminder_days")]
+    pub listing_expiry_reminder_days: i64,
+    /// Number of distinct reporters after which a listing is hidden automatically
+    #[serde(default = "default_report_hide_threshold")]
+    pub report_hide_threshold: i64,
+    #[serde(default = "default_favorite_alert_interval_secs")]
+    pub favorite_alert_interval_secs: u64,
+    #[serde(default = "default_saved_search_interval_secs")]
+    pub saved_search_interval_secs: u64,
+    #[serde(default = "default_listing_expiry_interval_secs")]
+    pub listing_expiry_interval_secs: u64,
+    #[serde(flatten)]
+    pub events: EventSettings,
+    #[serde(flatten)]
+    pub auth: AuthConfig,
+}
+
+fn default_product_service_url() -> String {
+    "http://localhost:8002".to_string()
+}
+
+fn default_order_service_url() -> String {
+    "http://localhost:8003".to_string()
+}
+
+fn default_email_service_url() -> String {
+    "http://localhost:8004".to_string()
+}
+
+fn default_listing_ttl_days() -> i64 {
+    30
+}
+
+fn default_listing_expiry_reminder_days() -> i64 {
+    3
+}
+
+fn default_report_hide_threshold() -> i64 {
+    3
+}
+
+fn default_favorite_alert_interval_secs() -> u64 {
+    300
+}
+
+fn default_saved_search_interval_secs() -> u64 {
+    900
+}
+
+fn default_listing_expiry_interval_secs() -> u64 {
+    3600
+}
+
+impl Settings {
+    /// Sent as `X-Internal-Token` on calls to order-service
+    pub fn internal_token(&self) -> &str {
+        self.auth.internal_api_token.as_deref().unwrap_or_default()
+    }
+
+    /// Base of listing links in emails; the local dev frontend when unset
+    pub fn frontend_url(&self) -> &str {
+        self.app_url
+            .as_deref()
+            .unwrap_or(config::DEV_FRONTEND_ORIGIN)
+    }
+
+    pub fn listing_ttl(&self) -> Duration {
+        Duration::days(self.listing_ttl_days)
+    }
+
+    pub fn fuzz(&self) -> FuzzConfig {
+        self.location.fuzz(&self.auth)
+    }
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = self.auth.validate();
+        problems.extend(self.database.validate());
+        if let Some(app_url) = &self.app_url {
+            problems.extend(check_url("app_url", app_url));
+        }
+        problems.extend(check_url("product_service_url", &self.product_service_url));
+        problems.extend(check_url("order_service_url", &self.order_service_url));
+        problems.extend(check_url("email_service_url", &self.email_service_url));
+        problems.extend(self.location.validate());
+        problems.extend(check_positive("listing_ttl_days", self.listing_ttl_days));
+        if self.listing_expiry_reminder_days < 0 {
+            problems.push(FieldError::new(
+                "listing_expiry_reminder_days",
+                "must not be negative",
+            ));
+        }
+        problems.extend(check_positive(
+            "report_hide_threshold",
+            self.report_hide_threshold,
+        ));
+        problems.extend(check_positive(
+            "favorite_alert_interval_secs",
+            self.favorite_alert_interval_secs,
+        ));
+        problems.extend(check_positive(
+            "saved_search_interval_secs",
+            self.saved_search_interval_secs,
+        ));
+        problems.extend(check_positive(
+            "listing_expiry_interval_secs",
+            self.listing_expiry_interval_secs,
+        ));
+        problems.extend(self.events.validate());
+        problems
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Deserializer};
+
+use crate::attributes::AttributeError;
+use handshake_common::error::ApiError;
+pub use handshake_common::error::FieldError;
+
+const MAX_TITLE_LENGTH: usize = 255;
+const MAX_IMAGE_URL_LENGTH: usize = 500;
+
+impl From<AttributeError> for FieldError {
+    fn from(err: AttributeError) -> Self {
+        FieldError {
+            field: format!("attributes.{}", err.key),
+            message: err.message,
+        }
+    }
+}
+
+/// 400 for `attr[...]` query filters that don't fit the category's schema
+pub fn bad_filters(errors: Vec<AttributeError>) -> ApiError {
+    ApiError::bad_request("Some attribute filters are invalid")
+        .with_fields(errors.into_iter().map(FieldError::from).collect())
+}
+
+/// Deserialize a field that distinguishes "absent" (`None`) from an explicit
+/// `null` (`Some(None)`), for fields that can be cleared. Use together with
+/// `#[serde(default)]`.
+pub fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+pub fn check_title(title: &str, errors: &mut Vec<FieldError>) {
+    if title.trim().is_empty() {
+        errors.push(FieldError::new("title", "must not be empty"));
+    } else if title.chars().count() > MAX_TITLE_LENGTH {
+        errors.push(FieldError::new("title", "must be at most 255 characters"));
+    }
+}
+
+pub fn check_description(description: &str, errors: &mut Vec<FieldError>) {
+    if description.trim().is_empty() {
+        errors.push(FieldError::new("description", "must not be empty"));
+    }
+}
+
+pub fn check_price(price: f64, errors: &mut Vec<FieldError>) {
+    if !price.is_finite() || price < 0.0 {
+        errors.push(FieldError::new("price", "must be a non-negative number"));
+    }
+}
+
+pub fn check_image_url(url: &str, errors: &mut Vec<FieldError>) {
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        errors.push(FieldError::new("image_url", "must be an http or https URL"));
+    } else if url.len() > MAX_IMAGE_URL_LENGTH {
+        errors.push(FieldError::new("image_url", "must be at most 500 characters"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_blank_titles_and_negative_prices() {
+        let mut errors = Vec::new();
+        check_title("   ", &mut errors);
+        check_price(-1.0, &mut errors);
+        check_price(f64::NAN, &mut errors);
+        check_price(0.0, &mut errors);
+
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["title", "price", "price"]);
+    }
+
+    #[test]
+    fn image_urls_must_be_http() {
+        let mut errors = Vec::new();
+        check_image_url("https://example.com/a.jpg", &mut errors);
+        assert!(errors.is_empty());
+
+        check_image_url("javascript:alert(1)", &mut errors);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn null_is_distinct_from_missing() {
+        #[derive(Deserialize)]
+        struct Patch {
+            #[serde(default, deserialize_with = "double_option")]
+            image_url: Option<Option<String>>,
+        }
+
+        let missing: Patch = serde_json::from_str("{}").unwrap();
+        let cleared: Patch = serde_json::from_str(r#"{"image_url":null}"#).unwrap();
+        assert_eq!(missing.image_url, None);
+        assert_eq!(cleared.image_url, Some(None));
+    }
+}